@@ -0,0 +1,10 @@
+// Build script for Ubuntu Config Scripts
+//
+// Records the target triple, so SBOMs list only the dependencies compiled
+// for the platform the binaries are built for.
+
+fn main() {
+    let target = std::env::var("TARGET").unwrap_or_default();
+    println!("cargo:rustc-env=UCS_BUILD_TARGET={}", target);
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,270 @@
+// Derive macros for Ubuntu Config Scripts
+//
+// This crate provides `#[derive(Validate)]` for configuration structs including:
+// - `#[validate(range(0, 100))]` / `#[validate(range(min = 1))]` numeric bounds
+// - `#[validate(one_of("debug", "info"))]` or `#[validate(one_of(LOG_LEVELS))]`
+// - `#[validate(regex = "^[a-z]+$")]` patterns, checked at compile time, or
+//   `#[validate(regex(path = PATTERN))]` naming a string constant
+// - `#[validate(path_exists)]` filesystem paths
+// - `#[validate(nested)]` sections validated recursively with their path prefix
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, Data, DeriveInput, Expr, Fields, LitStr, Token};
+
+/// Derive `Validate` and `ValidateFields` from `#[validate(...)]` field attributes
+///
+/// `schemars` also reads `#[validate(...)]`; structs deriving `JsonSchema` must
+/// write bounds as `range(min = .., max = ..)` and patterns as
+/// `regex(path = ..)`, which both derives understand.
+#[proc_macro_derive(Validate, attributes(validate))]
+pub fn derive_validate(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// A single rule parsed from a `#[validate(...)]` attribute
+enum Rule {
+    Range(Option<Box<Expr>>, Option<Box<Expr>>),
+    OneOf(Vec<Expr>),
+    Regex(Box<Expr>),
+    PathExists,
+    Nested,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "#[derive(Validate)] requires a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "#[derive(Validate)] only supports structs",
+            ))
+        }
+    };
+
+    let krate = quote!(::ubuntu_config_scripts);
+    let mut rules = Vec::new();
+    let mut checks = Vec::new();
+
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+        let name = ident.to_string();
+
+        for rule in parse_rules(&field.attrs)? {
+            match rule {
+                Rule::Nested => {
+                    rules.push(quote! {
+                        for rule in <#ty as #krate::ValidateFields>::field_rules() {
+                            rules.push(#krate::FieldRule {
+                                path: format!("/{}{}", #name, rule.path),
+                                constraint: rule.constraint,
+                            });
+                        }
+                    });
+                    checks.push(quote! {
+                        #krate::ValidateFields::validate_fields(
+                            &self.#ident,
+                            &format!("{}/{}", path, #name),
+                            errors,
+                        );
+                    });
+                }
+                rule => {
+                    let constraint = constraint_tokens(&krate, rule);
+                    rules.push(quote! {
+                        rules.push(#krate::FieldRule {
+                            path: format!("/{}", #name),
+                            constraint: #constraint,
+                        });
+                    });
+                    checks.push(quote! {
+                        errors.check_field(&format!("{}/{}", path, #name), &self.#ident, &#constraint);
+                    });
+                }
+            }
+        }
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #krate::ValidateFields for #ident #ty_generics #where_clause {
+            fn field_rules() -> ::std::vec::Vec<#krate::FieldRule> {
+                let mut rules = ::std::vec::Vec::new();
+                #(#rules)*
+                rules
+            }
+
+            fn validate_fields(&self, path: &str, errors: &mut #krate::ValidationErrors) {
+                #(#checks)*
+            }
+        }
+
+        impl #impl_generics #krate::Validate for #ident #ty_generics #where_clause {
+            type Error = #krate::ValidationErrors;
+
+            fn validate(&self) -> ::std::result::Result<(), #krate::ValidationErrors> {
+                let mut errors = #krate::ValidationErrors::new();
+                #krate::ValidateFields::validate_fields(self, "", &mut errors);
+                if errors.is_empty() {
+                    Ok(())
+                } else {
+                    Err(errors)
+                }
+            }
+        }
+    })
+}
+
+fn constraint_tokens(krate: &TokenStream2, rule: Rule) -> TokenStream2 {
+    match rule {
+        Rule::Range(min, max) => {
+            let bound = |expr: Option<Box<Expr>>| match expr {
+                Some(expr) => quote!(::std::option::Option::Some((#expr) as f64)),
+                None => quote!(::std::option::Option::None),
+            };
+            let (min, max) = (bound(min), bound(max));
+            quote!(#krate::Constraint::Range { min: #min, max: #max })
+        }
+        Rule::OneOf(values) => {
+            if let [Expr::Lit(_), ..] = values.as_slice() {
+                quote!(#krate::Constraint::one_of(&[#(#values),*]))
+            } else {
+                let values = &values[0];
+                quote!(#krate::Constraint::one_of(&#values))
+            }
+        }
+        Rule::Regex(pattern) => quote! {
+            #krate::Constraint::Pattern { pattern: (#pattern).to_string() }
+        },
+        Rule::PathExists => quote!(#krate::Constraint::PathExists),
+        Rule::Nested => unreachable!("nested fields have no constraint"),
+    }
+}
+
+fn check_pattern(pattern: &LitStr) -> syn::Result<()> {
+    regex::Regex::new(&pattern.value())
+        .map(|_| ())
+        .map_err(|e| syn::Error::new_spanned(pattern, format!("invalid regex: {}", e)))
+}
+
+fn parse_rules(attrs: &[syn::Attribute]) -> syn::Result<Vec<Rule>> {
+    let mut rules = Vec::new();
+
+    for attr in attrs.iter().filter(|a| a.path().is_ident("validate")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("range") {
+                let content;
+                syn::parenthesized!(content in meta.input);
+                let args = Punctuated::<Expr, Token![,]>::parse_terminated(&content)?;
+                rules.push(parse_range(&meta, args)?);
+            } else if meta.path.is_ident("one_of") {
+                let content;
+                syn::parenthesized!(content in meta.input);
+                let values: Vec<Expr> = Punctuated::<Expr, Token![,]>::parse_terminated(&content)?
+                    .into_iter()
+                    .collect();
+                let literals = values.iter().all(|v| matches!(v, Expr::Lit(_)));
+                if values.is_empty() || (!literals && values.len() != 1) {
+                    return Err(meta.error(
+                        "expected string literals or a single constant: one_of(\"a\", \"b\") or one_of(NAMES)",
+                    ));
+                }
+                rules.push(Rule::OneOf(values));
+            } else if meta.path.is_ident("regex") {
+                let pattern = if meta.input.peek(Token![=]) {
+                    let pattern: LitStr = meta.value()?.parse()?;
+                    check_pattern(&pattern)?;
+                    Expr::Lit(syn::ExprLit {
+                        attrs: Vec::new(),
+                        lit: syn::Lit::Str(pattern),
+                    })
+                } else {
+                    let mut path = None;
+                    meta.parse_nested_meta(|inner| {
+                        if !inner.path.is_ident("path") {
+                            return Err(inner.error("expected regex(path = PATTERN)"));
+                        }
+                        path = Some(inner.value()?.parse::<Expr>()?);
+                        Ok(())
+                    })?;
+                    let path = path.ok_or_else(|| meta.error("expected regex(path = PATTERN)"))?;
+                    if let Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(ref pattern),
+                        ..
+                    }) = path
+                    {
+                        check_pattern(pattern)?;
+                    }
+                    path
+                };
+                rules.push(Rule::Regex(Box::new(pattern)));
+            } else if meta.path.is_ident("path_exists") {
+                rules.push(Rule::PathExists);
+            } else if meta.path.is_ident("nested") {
+                rules.push(Rule::Nested);
+            } else {
+                return Err(meta.error(
+                    "unknown validation rule; expected range, one_of, regex, path_exists or nested",
+                ));
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(rules)
+}
+
+/// `range(0, 100)`, `range(min = 0)` or `range(max = 100)`
+fn parse_range(
+    meta: &syn::meta::ParseNestedMeta,
+    args: Punctuated<Expr, Token![,]>,
+) -> syn::Result<Rule> {
+    let mut min = None;
+    let mut max = None;
+
+    for (index, arg) in args.into_iter().enumerate() {
+        match arg {
+            Expr::Assign(assign) => {
+                let Expr::Path(ref left) = *assign.left else {
+                    return Err(syn::Error::new_spanned(&assign.left, "expected min or max"));
+                };
+                if left.path.is_ident("min") {
+                    min = Some(assign.right);
+                } else if left.path.is_ident("max") {
+                    max = Some(assign.right);
+                } else {
+                    return Err(syn::Error::new_spanned(left, "expected min or max"));
+                }
+            }
+            expr if index == 0 => min = Some(Box::new(expr)),
+            expr if index == 1 => max = Some(Box::new(expr)),
+            expr => {
+                return Err(syn::Error::new_spanned(
+                    expr,
+                    "range takes at most two bounds",
+                ))
+            }
+        }
+    }
+
+    if min.is_none() && max.is_none() {
+        return Err(meta.error("range needs a minimum, a maximum or both"));
+    }
+    Ok(Rule::Range(min, max))
+}
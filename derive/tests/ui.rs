@@ -0,0 +1,10 @@
+// Tests for the Validate derive
+//
+// Each file under tests/ui must fail to compile with the error recorded next
+// to it, so bad `#[validate(...)]` attributes are caught at build time
+
+#[test]
+fn test_attribute_errors() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,9 @@
+use ubuntu_config_scripts::Validate;
+
+#[derive(Validate)]
+enum Mode {
+    Debug,
+    Release,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use ubuntu_config_scripts::Validate;
+
+#[derive(Validate)]
+struct Config {
+    #[validate(regex = "^[a-z+$")]
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use ubuntu_config_scripts::Validate;
+
+#[derive(Validate)]
+struct Config {
+    #[validate(regex(path = "(unclosed"))]
+    name: String,
+}
+
+fn main() {}
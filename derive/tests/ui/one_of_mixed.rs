@@ -0,0 +1,11 @@
+use ubuntu_config_scripts::Validate;
+
+const LEVELS: [&str; 2] = ["debug", "info"];
+
+#[derive(Validate)]
+struct Config {
+    #[validate(one_of(LEVELS, "trace"))]
+    log_level: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use ubuntu_config_scripts::Validate;
+
+#[derive(Validate)]
+struct Config {
+    #[validate(range(0, 100, 1000))]
+    volume: u8,
+}
+
+fn main() {}
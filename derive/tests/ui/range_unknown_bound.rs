@@ -0,0 +1,9 @@
+use ubuntu_config_scripts::Validate;
+
+#[derive(Validate)]
+struct Config {
+    #[validate(range(low = 0))]
+    volume: u8,
+}
+
+fn main() {}
@@ -6,6 +6,21 @@ use ubuntu_config_scripts::*;
 async fn main() -> anyhow::Result<()> {
     init_logger()?;
     log_script_start("configure_speakers");
+    check_script_requirements("configure_speakers");
+
+    let config = load_config("audio")?;
+    let audio = &config.audio;
+    log_info(
+        &format!(
+            "Default sink {}, volume {}",
+            audio.default_sink.as_deref().unwrap_or("unchanged"),
+            audio
+                .volume_level
+                .map(|level| format!("{}%", level))
+                .unwrap_or_else(|| "unchanged".to_string())
+        ),
+        "AUDIO",
+    );
 
     println!("Speaker configuration utility - Placeholder");
 
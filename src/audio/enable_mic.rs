@@ -6,6 +6,22 @@ use ubuntu_config_scripts::*;
 async fn main() -> anyhow::Result<()> {
     init_logger()?;
     log_script_start("enable_mic");
+    check_script_requirements("enable_mic");
+
+    let config = load_config("audio")?;
+    let audio = &config.audio;
+    log_info(
+        &format!(
+            "Default source {}, echo cancellation {}",
+            audio.default_source.as_deref().unwrap_or("unchanged"),
+            if audio.enable_echo_cancellation {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        ),
+        "AUDIO",
+    );
 
     println!("enable_mic utility - Placeholder");
 
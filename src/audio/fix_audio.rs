@@ -6,6 +6,18 @@ use ubuntu_config_scripts::*;
 async fn main() -> anyhow::Result<()> {
     init_logger()?;
     log_script_start("fix_audio");
+    check_script_requirements("fix_audio");
+
+    let config = load_config("audio")?;
+    let audio = &config.audio;
+    log_info(
+        &format!(
+            "Restoring sink {} and source {}",
+            audio.default_sink.as_deref().unwrap_or("default"),
+            audio.default_source.as_deref().unwrap_or("default")
+        ),
+        "AUDIO",
+    );
 
     println!("fix_audio utility - Placeholder");
 
@@ -5,7 +5,37 @@ use ubuntu_config_scripts::*;
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     init_logger()?;
+    let _trace = TraceGuard::from_env();
     log_script_start("deploy");
+    check_script_requirements("deploy");
+
+    let config = load_config("deploy")?;
+    let deploy = &config.deploy;
+    log_info(
+        &format!(
+            "Deploying {} to {}",
+            if deploy.binaries.is_empty() {
+                "all binaries".to_string()
+            } else {
+                deploy.binaries.join(", ")
+            },
+            deploy.install_dir
+        ),
+        "DEPLOY",
+    );
+
+    // Installed binaries must be writable now and runnable later
+    let install_dir = expand_tilde(&deploy.install_dir)?;
+    let report = Preflight::new(&install_dir)
+        .writable()
+        .executable()
+        .check()?;
+    if let Some(ref mount) = report.mount {
+        log_info(
+            &format!("{} is on {} ({})", install_dir, mount.fs_type, mount.source),
+            "DEPLOY",
+        );
+    }
 
     println!("Deployment utility - Placeholder");
 
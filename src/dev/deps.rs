@@ -1,13 +1,464 @@
 // deps utility for Ubuntu systems
+//
+// Answers questions about the Cargo dependency graph straight from
+// Cargo.lock (no cargo invocation): the tree, duplicate versions, why a
+// crate is present, DOT/JSON exports, an offline RustSec audit, the
+// deny.toml license policy and CycloneDX/SPDX SBOMs. `update` runs
+// `cargo update` and reports each version change; `system` checks the
+// host against config/system-deps.toml.
 
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 use ubuntu_config_scripts::*;
 
+#[derive(Parser)]
+#[command(name = "manage_deps", about = "Inspect the Cargo dependency graph")]
+struct Cli {
+    /// Project directory holding Cargo.toml and Cargo.lock
+    #[arg(long, default_value = ".", global = true)]
+    project: PathBuf,
+
+    /// What to show (the tree when omitted)
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the dependency tree of every workspace member
+    Tree {
+        /// Stop after this many levels below the roots
+        #[arg(long)]
+        depth: Option<usize>,
+    },
+    /// List crates locked at more than one version
+    Duplicates {
+        /// Output format: table, markdown, csv or json
+        #[arg(long, default_value = "table")]
+        format: OutputFormat,
+    },
+    /// Show the shortest chain from a root to each version of a crate
+    Why { name: String },
+    /// Check locked crates against a local RustSec advisory database
+    Audit {
+        /// advisory-db checkout (defaults to UCS_ADVISORY_DB, then ~/.cargo/advisory-db)
+        #[arg(long)]
+        db: Option<PathBuf>,
+        /// Output format: table, markdown, csv or json
+        #[arg(long, default_value = "table")]
+        format: OutputFormat,
+        /// Also fail on informational advisories (unmaintained, unsound)
+        #[arg(long)]
+        deny_warnings: bool,
+    },
+    /// Check every locked crate's license against the policy in deny.toml
+    Licenses {
+        /// cargo-deny configuration holding the [licenses] policy
+        #[arg(long, default_value = DENY_TOML)]
+        policy: PathBuf,
+        /// Output format: table, markdown, csv or json
+        #[arg(long, default_value = "table")]
+        format: OutputFormat,
+        /// Only list crates that fail the check
+        #[arg(long)]
+        failures: bool,
+        /// Also fail on crates whose license could not be read
+        #[arg(long)]
+        deny_unknown: bool,
+    },
+    /// Write a software bill of materials for the workspace members
+    Sbom {
+        /// SBOM format: cyclonedx or spdx
+        #[arg(long, default_value = "cyclonedx")]
+        format: SbomFormat,
+        /// File to write (stdout when omitted)
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// Include the Debian packages providing the commands the scripts run
+        #[arg(long)]
+        system: bool,
+    },
+    /// Run cargo update and list the packages it changed
+    Update {
+        /// Only update these packages (every package when omitted)
+        #[arg(long, short)]
+        package: Vec<String>,
+        /// Update the single selected package to exactly this version
+        #[arg(long, requires = "package")]
+        precise: Option<String>,
+        /// Show what would change without writing Cargo.lock
+        #[arg(long)]
+        dry_run: bool,
+        /// Output format: table, markdown, csv or json
+        #[arg(long, default_value = "table")]
+        format: OutputFormat,
+    },
+    /// Check the system packages the scripts need and print an install plan
+    System {
+        /// Only check what this script needs (every requirement when omitted)
+        #[arg(long)]
+        script: Option<String>,
+        /// Package manager to plan for (detected when omitted)
+        #[arg(long)]
+        manager: Option<PackageManager>,
+        /// Output format: table, markdown, csv or json
+        #[arg(long, default_value = "table")]
+        format: OutputFormat,
+    },
+    /// Export the whole graph
+    Graph {
+        /// Graph format: tree, dot or json
+        #[arg(long, default_value = "dot")]
+        format: GraphFormat,
+    },
+}
+
+fn duplicates_table(graph: &DependencyGraph) -> Table {
+    let depths = graph.depths();
+    let mut table = Table::new(vec![
+        Column::new("Crate"),
+        Column::new("Version"),
+        Column::new("Depth").align(Align::Right),
+        Column::new("Required by").wrap(),
+    ]);
+    for name in graph.duplicates().keys() {
+        for index in graph.find(name) {
+            let package = graph.package(index);
+            let dependents: Vec<String> = graph
+                .dependents(index)
+                .into_iter()
+                .map(|d| graph.package(d).name.clone())
+                .collect();
+            table.add_row(vec![
+                name.to_string(),
+                package.version.to_string(),
+                depths[index].map(|d| d.to_string()).unwrap_or_default(),
+                dependents.join(", "),
+            ]);
+        }
+    }
+    table
+}
+
+fn updates_table(results: &[UpdateResult]) -> Table {
+    let mut table = Table::new(vec![
+        Column::new("Crate"),
+        Column::new("From"),
+        Column::new("To"),
+        Column::new("Result").wrap(),
+    ]);
+    for result in results {
+        let outcome = match (&result.error, &result.to_version) {
+            (Some(error), _) => format!("failed: {}", error),
+            (None, Some(_)) if result.from_version.is_empty() => "added".to_string(),
+            (None, Some(_)) => "updated".to_string(),
+            (None, None) => "removed".to_string(),
+        };
+        table.add_row(vec![
+            result.name.clone(),
+            result.from_version.clone(),
+            result.to_version.clone().unwrap_or_default(),
+            outcome,
+        ]);
+    }
+    table
+}
+
+fn why(graph: &DependencyGraph, name: &str) -> anyhow::Result<()> {
+    for path in graph.why(name)? {
+        let Some(&target) = path.last() else {
+            continue;
+        };
+        println!("{}", graph.package(target).display_name());
+        for (depth, &index) in path.iter().rev().skip(1).enumerate() {
+            println!(
+                "{}└── {}",
+                "    ".repeat(depth),
+                graph.package(index).display_name()
+            );
+        }
+        let dependents = graph.dependents(target);
+        if dependents.len() > 1 {
+            println!(
+                "  (required by {} packages: {})",
+                dependents.len(),
+                dependents
+                    .iter()
+                    .map(|&d| graph.package(d).name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+    }
+    Ok(())
+}
+
+fn audit(
+    project: &std::path::Path,
+    db: Option<PathBuf>,
+    format: OutputFormat,
+    deny_warnings: bool,
+) -> anyhow::Result<bool> {
+    let db = match db {
+        Some(db) => db,
+        None => advisory_db_path()?,
+    };
+    let report = audit_project(project, &db)?;
+    log_info(
+        &format!(
+            "Checked {} packages against {} advisories from {}",
+            report.packages,
+            report.advisories,
+            db.display()
+        ),
+        "AUDIT",
+    );
+
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if !report.findings.is_empty() {
+        print!("{}", report.table().fit_terminal().render(format));
+    }
+
+    let vulnerabilities = report.vulnerabilities().len();
+    let warnings = report.warnings().len();
+    if vulnerabilities == 0 && warnings == 0 {
+        log_success("No advisories apply to the locked crates", "AUDIT");
+        return Ok(true);
+    }
+    log_error(
+        &format!("{} vulnerabilities, {} warnings", vulnerabilities, warnings),
+        "AUDIT",
+    );
+    Ok(vulnerabilities == 0 && !(deny_warnings && warnings > 0))
+}
+
+fn licenses(
+    project: &std::path::Path,
+    policy: &std::path::Path,
+    format: OutputFormat,
+    failures: bool,
+    deny_unknown: bool,
+) -> anyhow::Result<bool> {
+    let policy = if policy.is_relative() && !policy.exists() {
+        project.join(policy)
+    } else {
+        policy.to_path_buf()
+    };
+    let report = license_report(project, &policy)?;
+    let failing = report.failures(deny_unknown);
+
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if !failures {
+        print!("{}", report.table(false).fit_terminal().render(format));
+    } else if !failing.is_empty() {
+        let failing = LicenseReport {
+            crates: failing.iter().map(|c| (*c).clone()).collect(),
+        };
+        print!("{}", failing.table(false).fit_terminal().render(format));
+    }
+
+    let unknown = report.unknown().len();
+    if unknown > 0 {
+        let message = format!(
+            "{} crates are not in the local registry cache and were not checked",
+            unknown
+        );
+        if deny_unknown {
+            log_error(&message, "LICENSE");
+        } else {
+            log_warn(&message, "LICENSE");
+        }
+    }
+    let rejected = report.rejected().len();
+    if rejected > 0 {
+        log_error(
+            &format!(
+                "{} crates have licenses {} does not allow",
+                rejected,
+                policy.display()
+            ),
+            "LICENSE",
+        );
+    }
+    if !failing.is_empty() {
+        return Ok(false);
+    }
+    log_success(
+        &format!(
+            "{} crates pass the license policy",
+            report.with_status(LicenseStatus::Allowed).len()
+        ),
+        "LICENSE",
+    );
+    Ok(true)
+}
+
+/// Check system requirements; returns false when any is missing or outdated
+fn system(
+    script: Option<&str>,
+    manager: Option<PackageManager>,
+    format: OutputFormat,
+) -> anyhow::Result<bool> {
+    let mut requirements = SystemRequirements::load()?;
+    if let Some(script) = script {
+        requirements = requirements.for_script(script);
+    }
+    let report = requirements.check();
+    let plan = manager
+        .or_else(PackageManager::detect)
+        .map(|m| requirements.install_plan(&report, m));
+
+    if format == OutputFormat::Json {
+        let output = serde_json::json!({ "checks": report.checks, "install_plan": plan });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        print!("{}", report.table().fit_terminal().render(format));
+    }
+
+    if report.is_ok() {
+        log_success(
+            &format!("All {} system requirements met", report.checks.len()),
+            "DEPS",
+        );
+        return Ok(true);
+    }
+    match plan {
+        Some(plan) => {
+            if let Some(command) = plan.command() {
+                log_info(&format!("Install with: {}", command.join(" ")), "DEPS");
+            }
+            if !plan.unavailable.is_empty() {
+                log_warn(
+                    &format!(
+                        "No {} package for: {}",
+                        plan.manager,
+                        plan.unavailable.join(", ")
+                    ),
+                    "DEPS",
+                );
+            }
+        }
+        None => log_warn("No supported package manager found", "DEPS"),
+    }
+    log_error(
+        &format!("{} system requirements unmet", report.unmet().len()),
+        "DEPS",
+    );
+    Ok(false)
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     init_logger()?;
     log_script_start("deps");
 
-    println!("deps utility - Placeholder");
+    let cli = Cli::parse();
+    check_script_requirements("manage_deps");
+    let graph = DependencyGraph::load(&cli.project)?;
+
+    match cli.command.unwrap_or(Command::Tree { depth: None }) {
+        Command::Tree { depth } => print!("{}", graph.tree(depth)),
+        Command::Duplicates { format } => {
+            let duplicates = graph.duplicates();
+            if duplicates.is_empty() {
+                log_success("No crate is locked at more than one version", "DEPS");
+            } else {
+                print!("{}", duplicates_table(&graph).fit_terminal().render(format));
+                log_warn(
+                    &format!("{} crates are locked at several versions", duplicates.len()),
+                    "DEPS",
+                );
+            }
+        }
+        Command::Why { name } => why(&graph, &name)?,
+        Command::Audit {
+            db,
+            format,
+            deny_warnings,
+        } => {
+            if !audit(&cli.project, db, format, deny_warnings)? {
+                std::process::exit(1);
+            }
+        }
+        Command::Licenses {
+            ref policy,
+            format,
+            failures,
+            deny_unknown,
+        } => {
+            if !licenses(&cli.project, policy, format, failures, deny_unknown)? {
+                std::process::exit(1);
+            }
+        }
+        Command::Sbom {
+            format,
+            output,
+            system,
+        } => {
+            let sbom = generate_sbom(&cli.project, system)?;
+            let document = sbom.render(format);
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, document)?;
+                    log_success(
+                        &format!(
+                            "Wrote {} SBOM with {} components to {}",
+                            format,
+                            sbom.components.len(),
+                            path.display()
+                        ),
+                        "SBOM",
+                    );
+                }
+                None => print!("{}", document),
+            }
+        }
+        Command::Update {
+            package,
+            precise,
+            dry_run,
+            format,
+        } => {
+            let mut update = CargoUpdate::new(&cli.project).dry_run(dry_run);
+            for name in &package {
+                update = update.package(name);
+            }
+            if let Some(ref version) = precise {
+                update = update.precise(version);
+            }
+            let results = update.run()?;
+
+            if format == OutputFormat::Json {
+                println!("{}", serde_json::to_string_pretty(&results)?);
+            } else if !results.is_empty() {
+                print!("{}", updates_table(&results).fit_terminal().render(format));
+            }
+            if results.iter().any(|r| r.error.is_some()) {
+                log_error("cargo update failed", "DEPS");
+                std::process::exit(1);
+            }
+            log_success(
+                &format!(
+                    "{} {} packages",
+                    if dry_run { "Would change" } else { "Changed" },
+                    results.len()
+                ),
+                "DEPS",
+            );
+        }
+        Command::System {
+            ref script,
+            manager,
+            format,
+        } => {
+            if !system(script.as_deref(), manager, format)? {
+                std::process::exit(1);
+            }
+        }
+        Command::Graph { format } => print!("{}", graph.render(format)),
+    }
 
     log_script_complete("deps");
     Ok(())
@@ -0,0 +1,115 @@
+// schema_test_runner utility for Ubuntu systems
+//
+// Runs the YAML schema test suites under schemas/ through ruchydbg and
+// reports each variant against its expected outcome (replaces
+// scripts/schema-test-runner.ts).
+
+use clap::Parser;
+use std::path::PathBuf;
+use std::time::Duration;
+use ubuntu_config_scripts::*;
+
+#[derive(Parser)]
+#[command(
+    name = "schema_test_runner",
+    about = "Run schema test suites and report results"
+)]
+struct Cli {
+    /// Suite files to run (defaults to every schemas/*.yaml)
+    suites: Vec<PathBuf>,
+
+    /// Report format: text, tap, junit or json
+    #[arg(long, default_value = "text")]
+    format: ReportFormat,
+
+    /// Write the report to a file instead of stdout
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Command used to run each generated program
+    #[arg(long, default_value = "ruchydbg run")]
+    runner: String,
+
+    /// Run only the variant with this id (repeatable)
+    #[arg(long = "only", value_name = "ID")]
+    only: Vec<String>,
+
+    /// Timeout for every variant, overriding the suite values
+    #[arg(long, value_name = "MS")]
+    timeout_ms: Option<u64>,
+
+    /// Skip the remaining variants after the first unexpected result
+    #[arg(long)]
+    stop_on_failure: bool,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    init_logger()?;
+    log_script_start("schema_test_runner");
+
+    let cli = Cli::parse();
+    check_script_requirements("schema_test_runner");
+
+    let command: Vec<String> = cli.runner.split_whitespace().map(String::from).collect();
+    require_command(command.first().map(String::as_str).unwrap_or_default()).await?;
+
+    let paths = if cli.suites.is_empty() {
+        find_suites(std::path::Path::new(SCHEMA_DIR))?
+    } else {
+        cli.suites.clone()
+    };
+    if paths.is_empty() {
+        return Err(anyhow::anyhow!("No schema suites found in {}", SCHEMA_DIR));
+    }
+
+    let mut runner = SuiteRunner::new()
+        .command(command)
+        .only(cli.only.clone())
+        .timeout(cli.timeout_ms.map(Duration::from_millis));
+    if cli.stop_on_failure {
+        runner = runner.stop_on_first_failure(true);
+    }
+
+    let mut reports = Vec::new();
+    for path in &paths {
+        let suite = SchemaSuite::from_file(path)?;
+        log_info(
+            &format!("Running {} ({} variants)", suite.name, suite.variants.len()),
+            "SCHEMA",
+        );
+        reports.push(runner.run(&suite).await?);
+    }
+
+    let rendered = match cli.format {
+        ReportFormat::Junit => junit_report(&reports),
+        ReportFormat::Json => serde_json::to_string_pretty(&reports)? + "\n",
+        format => reports
+            .iter()
+            .map(|report| report.render(format))
+            .collect::<anyhow::Result<Vec<_>>>()?
+            .join("\n"),
+    };
+    match cli.output {
+        Some(ref path) => {
+            std::fs::write(path, rendered)?;
+            log_success(&format!("Report written to {}", path.display()), "SCHEMA");
+        }
+        None => print!("{}", rendered),
+    }
+
+    let mismatched: usize = reports.iter().map(|r| r.mismatches().len()).sum();
+    if mismatched > 0 {
+        log_error(
+            &format!(
+                "{} variants did not match their expected outcome",
+                mismatched
+            ),
+            "SCHEMA",
+        );
+        std::process::exit(1);
+    }
+
+    log_script_complete("schema_test_runner");
+    Ok(())
+}
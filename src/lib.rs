@@ -3,16 +3,51 @@
 // This is the main library entry point for the Ubuntu configuration scripts.
 // All modules are organized under this main library structure.
 
+// Lets `#[derive(Validate)]` refer to this crate by name from inside it
+extern crate self as ubuntu_config_scripts;
+
 pub mod lib {
+    pub mod advisory;
     pub mod common;
+    pub mod config;
+    pub mod config_edit;
     pub mod deploy;
     pub mod deps_manager;
+    pub mod license;
+    pub mod lockfile;
     pub mod logger;
+    pub mod migrate;
+    pub mod preflight;
+    pub mod profile;
+    pub mod progress;
+    pub mod redact;
+    pub mod render;
+    pub mod sbom;
     pub mod schema;
+    pub mod schema_suite;
+    pub mod secret;
+    pub mod system_deps;
+    pub mod trace;
 }
 
 // Re-export commonly used items for convenience
+pub use lib::advisory::*;
 pub use lib::common::*;
+pub use lib::config::*;
+pub use lib::config_edit::*;
 pub use lib::deps_manager::*;
+pub use lib::license::*;
+pub use lib::lockfile::*;
 pub use lib::logger::*;
+pub use lib::migrate::*;
+pub use lib::preflight::*;
+pub use lib::profile::*;
+pub use lib::progress::*;
+pub use lib::redact::*;
+pub use lib::render::*;
+pub use lib::sbom::*;
 pub use lib::schema::*;
+pub use lib::schema_suite::*;
+pub use lib::secret::*;
+pub use lib::system_deps::*;
+pub use lib::trace::*;
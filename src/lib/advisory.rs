@@ -0,0 +1,499 @@
+// RustSec advisories for Ubuntu Config Scripts
+//
+// This module audits the locked dependencies against a local checkout of
+// the RustSec advisory database (https://github.com/rustsec/advisory-db)
+// without network access, including:
+// - Reading advisories (`crates/<name>/RUSTSEC-*.md` with a TOML header)
+// - Matching locked versions against the patched and unaffected ranges
+// - Severity from the CVSS v3 base score
+// - Findings with the chain of dependencies that pulls the crate in
+//
+// Withdrawn advisories are skipped. Informational advisories (unmaintained,
+// unsound, notice) are reported as warnings rather than vulnerabilities.
+
+use crate::lib::common::expand_tilde;
+use crate::lib::lockfile::DependencyGraph;
+use crate::lib::render::{Align, Column, Table};
+use anyhow::{anyhow, Context, Result};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Where `cargo audit` keeps its clone of the advisory database
+pub const DEFAULT_ADVISORY_DB: &str = "~/.cargo/advisory-db";
+
+/// Environment variable pointing at another advisory database checkout
+pub const ADVISORY_DB_ENV: &str = "UCS_ADVISORY_DB";
+
+/// Advisory database location: `UCS_ADVISORY_DB`, else `~/.cargo/advisory-db`
+pub fn advisory_db_path() -> Result<PathBuf> {
+    match std::env::var(ADVISORY_DB_ENV) {
+        Ok(path) if !path.is_empty() => Ok(PathBuf::from(expand_tilde(&path)?)),
+        _ => Ok(PathBuf::from(expand_tilde(DEFAULT_ADVISORY_DB)?)),
+    }
+}
+
+/// Qualitative severity rating of a CVSS score
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    None,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub const NAMES: [&'static str; 5] = ["none", "low", "medium", "high", "critical"];
+
+    /// Rating for a CVSS base score (0.0 to 10.0)
+    pub fn from_score(score: f64) -> Self {
+        if score >= 9.0 {
+            Severity::Critical
+        } else if score >= 7.0 {
+            Severity::High
+        } else if score >= 4.0 {
+            Severity::Medium
+        } else if score > 0.0 {
+            Severity::Low
+        } else {
+            Severity::None
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Severity::None),
+            "low" => Ok(Severity::Low),
+            "medium" | "moderate" => Ok(Severity::Medium),
+            "high" => Ok(Severity::High),
+            "critical" => Ok(Severity::Critical),
+            _ => Err(anyhow!(
+                "Invalid severity: {} (expected one of: {})",
+                s,
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Severity::None => "none",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Base score of a CVSS v3.0 or v3.1 vector (`CVSS:3.1/AV:N/AC:L/...`)
+pub fn cvss_base_score(vector: &str) -> Result<f64> {
+    let mut parts = vector.split('/');
+    match parts.next() {
+        Some("CVSS:3.0") | Some("CVSS:3.1") => {}
+        _ => return Err(anyhow!("Unsupported CVSS vector: {}", vector)),
+    }
+    let metrics: Vec<(&str, &str)> = parts.filter_map(|part| part.split_once(':')).collect();
+    let metric = |name: &str| {
+        metrics
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| *value)
+            .ok_or_else(|| anyhow!("CVSS vector has no {} metric: {}", name, vector))
+    };
+    let invalid = |name: &str, value: &str| anyhow!("Invalid CVSS {} value: {}", name, value);
+
+    let changed = match metric("S")? {
+        "U" => false,
+        "C" => true,
+        other => return Err(invalid("S", other)),
+    };
+    let attack_vector = match metric("AV")? {
+        "N" => 0.85,
+        "A" => 0.62,
+        "L" => 0.55,
+        "P" => 0.2,
+        other => return Err(invalid("AV", other)),
+    };
+    let attack_complexity = match metric("AC")? {
+        "L" => 0.77,
+        "H" => 0.44,
+        other => return Err(invalid("AC", other)),
+    };
+    let privileges = match (metric("PR")?, changed) {
+        ("N", _) => 0.85,
+        ("L", false) => 0.62,
+        ("L", true) => 0.68,
+        ("H", false) => 0.27,
+        ("H", true) => 0.5,
+        (other, _) => return Err(invalid("PR", other)),
+    };
+    let interaction = match metric("UI")? {
+        "N" => 0.85,
+        "R" => 0.62,
+        other => return Err(invalid("UI", other)),
+    };
+    let impact_of = |name: &str| match metric(name)? {
+        "H" => Ok(0.56),
+        "L" => Ok(0.22),
+        "N" => Ok(0.0),
+        other => Err(invalid(name, other)),
+    };
+    let base = 1.0 - (1.0 - impact_of("C")?) * (1.0 - impact_of("I")?) * (1.0 - impact_of("A")?);
+
+    let impact = if changed {
+        7.52 * (base - 0.029) - 3.25 * (base - 0.02f64).powi(15)
+    } else {
+        6.42 * base
+    };
+    if impact <= 0.0 {
+        return Ok(0.0);
+    }
+    let exploitability = 8.22 * attack_vector * attack_complexity * privileges * interaction;
+    let score = if changed {
+        1.08 * (impact + exploitability)
+    } else {
+        impact + exploitability
+    };
+    Ok(round_up(score.min(10.0)))
+}
+
+/// CVSS v3.1 round up to one decimal, avoiding floating point artifacts
+fn round_up(value: f64) -> f64 {
+    let scaled = (value * 100_000.0).round() as i64;
+    if scaled % 10_000 == 0 {
+        scaled as f64 / 100_000.0
+    } else {
+        (scaled / 10_000 + 1) as f64 / 10.0
+    }
+}
+
+#[derive(Deserialize)]
+struct RawAdvisoryFile {
+    advisory: RawAdvisory,
+    #[serde(default)]
+    versions: RawVersions,
+}
+
+#[derive(Deserialize)]
+struct RawAdvisory {
+    id: String,
+    package: String,
+    date: String,
+    title: Option<String>,
+    description: Option<String>,
+    url: Option<String>,
+    #[serde(default)]
+    aliases: Vec<String>,
+    cvss: Option<String>,
+    informational: Option<String>,
+    withdrawn: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct RawVersions {
+    #[serde(default)]
+    patched: Vec<String>,
+    #[serde(default)]
+    unaffected: Vec<String>,
+}
+
+/// A RustSec advisory against one crate
+#[derive(Debug, Clone)]
+pub struct Advisory {
+    pub id: String,
+    pub package: String,
+    pub date: String,
+    pub title: String,
+    pub description: String,
+    pub url: Option<String>,
+    pub aliases: Vec<String>,
+    pub cvss: Option<String>,
+    /// `unmaintained`, `unsound` or `notice` for informational advisories
+    pub informational: Option<String>,
+    pub withdrawn: Option<String>,
+    pub patched: Vec<VersionReq>,
+    pub unaffected: Vec<VersionReq>,
+}
+
+impl Advisory {
+    /// Parse an advisory: Markdown with a ```toml header, or plain TOML
+    pub fn parse(content: &str) -> Result<Self> {
+        let (header, body) = match content.trim_start().strip_prefix("```toml") {
+            Some(rest) => rest
+                .split_once("\n```")
+                .ok_or_else(|| anyhow!("Unterminated ```toml block"))?,
+            None => (content, ""),
+        };
+        let raw: RawAdvisoryFile = toml::from_str(header).context("Invalid advisory header")?;
+
+        // The Markdown body is `# Title` followed by the description
+        let body = body.trim();
+        let (title, description) = match body.strip_prefix("# ") {
+            Some(rest) => match rest.split_once('\n') {
+                Some((title, description)) => (title.trim(), description.trim()),
+                None => (rest.trim(), ""),
+            },
+            None => ("", body),
+        };
+
+        let ranges = |list: &[String]| {
+            list.iter()
+                .map(|range| {
+                    VersionReq::parse(range)
+                        .with_context(|| format!("Invalid version range: {}", range))
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(Self {
+            patched: ranges(&raw.versions.patched)?,
+            unaffected: ranges(&raw.versions.unaffected)?,
+            title: raw.advisory.title.unwrap_or_else(|| title.to_string()),
+            description: raw
+                .advisory
+                .description
+                .unwrap_or_else(|| description.to_string()),
+            id: raw.advisory.id,
+            package: raw.advisory.package,
+            date: raw.advisory.date,
+            url: raw.advisory.url,
+            aliases: raw.advisory.aliases,
+            cvss: raw.advisory.cvss,
+            informational: raw.advisory.informational,
+            withdrawn: raw.advisory.withdrawn,
+        })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid advisory: {}", path.display()))
+    }
+
+    pub fn is_informational(&self) -> bool {
+        self.informational.is_some()
+    }
+
+    /// CVSS v3 base score, when the advisory has a v3 vector
+    pub fn score(&self) -> Option<f64> {
+        self.cvss.as_deref().and_then(|v| cvss_base_score(v).ok())
+    }
+
+    pub fn severity(&self) -> Option<Severity> {
+        self.score().map(Severity::from_score)
+    }
+
+    /// Whether a version is neither patched nor unaffected
+    ///
+    /// Ranges follow Cargo's rules, so pre-releases only match ranges that
+    /// name a pre-release of the same version.
+    pub fn affects(&self, version: &Version) -> bool {
+        !self
+            .patched
+            .iter()
+            .chain(&self.unaffected)
+            .any(|range| range.matches(version))
+    }
+}
+
+/// A locked package matched by an advisory
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub id: String,
+    pub package: String,
+    pub version: String,
+    pub title: String,
+    pub severity: Option<Severity>,
+    pub score: Option<f64>,
+    pub informational: Option<String>,
+    pub patched: Vec<String>,
+    pub url: Option<String>,
+    pub aliases: Vec<String>,
+    /// `name version` entries from a root down to the affected package
+    pub path: Vec<String>,
+}
+
+impl Finding {
+    /// Severity column text: the rating, the informational kind or `unknown`
+    pub fn severity_label(&self) -> String {
+        match (&self.informational, self.severity) {
+            (Some(kind), _) => kind.clone(),
+            (None, Some(severity)) => severity.to_string(),
+            (None, None) => "unknown".to_string(),
+        }
+    }
+}
+
+/// Outcome of auditing a dependency graph
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AuditReport {
+    pub advisories: usize,
+    pub packages: usize,
+    pub findings: Vec<Finding>,
+}
+
+impl AuditReport {
+    /// Findings for security vulnerabilities
+    pub fn vulnerabilities(&self) -> Vec<&Finding> {
+        self.findings
+            .iter()
+            .filter(|f| f.informational.is_none())
+            .collect()
+    }
+
+    /// Findings for informational advisories (unmaintained, unsound, notice)
+    pub fn warnings(&self) -> Vec<&Finding> {
+        self.findings
+            .iter()
+            .filter(|f| f.informational.is_some())
+            .collect()
+    }
+
+    /// No vulnerabilities (warnings allowed)
+    pub fn is_clean(&self) -> bool {
+        self.vulnerabilities().is_empty()
+    }
+
+    pub fn table(&self) -> Table {
+        let mut table = Table::new(vec![
+            Column::new("ID"),
+            Column::new("Crate"),
+            Column::new("Version"),
+            Column::new("Severity"),
+            Column::new("Score").align(Align::Right),
+            Column::new("Title").wrap(),
+            Column::new("Patched"),
+            Column::new("Path").wrap(),
+        ]);
+        for finding in &self.findings {
+            table.add_row(vec![
+                finding.id.clone(),
+                finding.package.clone(),
+                finding.version.clone(),
+                finding.severity_label(),
+                finding
+                    .score
+                    .map(|s| format!("{:.1}", s))
+                    .unwrap_or_default(),
+                finding.title.clone(),
+                if finding.patched.is_empty() {
+                    "none".to_string()
+                } else {
+                    finding.patched.join(", ")
+                },
+                finding.path.join(" > "),
+            ]);
+        }
+        table
+    }
+}
+
+/// Advisories loaded from a local advisory-db checkout
+#[derive(Debug, Clone, Default)]
+pub struct AdvisoryDatabase {
+    advisories: Vec<Advisory>,
+}
+
+impl AdvisoryDatabase {
+    /// Load every advisory under `<path>/crates`
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let crates = path.join("crates");
+        if !crates.is_dir() {
+            return Err(anyhow!(
+                "No RustSec advisory database at {} (clone https://github.com/rustsec/advisory-db there or set {})",
+                path.display(),
+                ADVISORY_DB_ENV
+            ));
+        }
+
+        let mut advisories = Vec::new();
+        for entry in walkdir::WalkDir::new(&crates).min_depth(2).max_depth(2) {
+            let entry = entry.with_context(|| format!("Failed to read {}", crates.display()))?;
+            let is_advisory = entry
+                .path()
+                .extension()
+                .is_some_and(|ext| ext == "md" || ext == "toml");
+            if entry.file_type().is_file() && is_advisory {
+                advisories.push(Advisory::from_file(entry.path())?);
+            }
+        }
+        advisories.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(Self { advisories })
+    }
+
+    pub fn from_advisories(advisories: Vec<Advisory>) -> Self {
+        Self { advisories }
+    }
+
+    pub fn advisories(&self) -> &[Advisory] {
+        &self.advisories
+    }
+
+    /// Match every locked package against the advisories for its crate
+    ///
+    /// Only crates.io packages are checked; path and git dependencies share
+    /// names with published crates without being them.
+    pub fn audit(&self, graph: &DependencyGraph) -> AuditReport {
+        let mut findings = Vec::new();
+        for (index, package) in graph.packages().iter().enumerate() {
+            if !package.source.is_crates_io() {
+                continue;
+            }
+            let matching = self.advisories.iter().filter(|advisory| {
+                advisory.package == package.name
+                    && advisory.withdrawn.is_none()
+                    && advisory.affects(&package.version)
+            });
+            for advisory in matching {
+                let path = graph
+                    .path_to(index)
+                    .unwrap_or_else(|| vec![index])
+                    .into_iter()
+                    .map(|i| {
+                        let p = graph.package(i);
+                        format!("{} {}", p.name, p.version)
+                    })
+                    .collect();
+                findings.push(Finding {
+                    id: advisory.id.clone(),
+                    package: package.name.clone(),
+                    version: package.version.to_string(),
+                    title: advisory.title.clone(),
+                    severity: advisory.severity(),
+                    score: advisory.score(),
+                    informational: advisory.informational.clone(),
+                    patched: advisory.patched.iter().map(|r| r.to_string()).collect(),
+                    url: advisory.url.clone(),
+                    aliases: advisory.aliases.clone(),
+                    path,
+                });
+            }
+        }
+        // Worst first, informational last
+        findings.sort_by(|a, b| {
+            a.informational
+                .is_some()
+                .cmp(&b.informational.is_some())
+                .then_with(|| b.severity.cmp(&a.severity))
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        AuditReport {
+            advisories: self.advisories.len(),
+            packages: graph.packages().len(),
+            findings,
+        }
+    }
+}
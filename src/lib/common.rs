@@ -6,6 +6,7 @@
 // - Environment variable management
 // - User interaction utilities
 
+use crate::lib::redact::redact_command;
 use anyhow::{Context, Result};
 use log::debug;
 use std::collections::HashMap;
@@ -34,7 +35,7 @@ pub struct CommandOptions {
 
 /// Execute a command with proper error handling and logging
 pub async fn run_command(cmd: &[&str], options: Option<CommandOptions>) -> Result<CommandResult> {
-    debug!("Running command: {}", cmd.join(" "));
+    debug!("Running command: {}", redact_command(cmd));
 
     if cmd.is_empty() {
         return Err(anyhow::anyhow!("Command cannot be empty"));
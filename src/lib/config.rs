@@ -0,0 +1,586 @@
+// Layered configuration loading for Ubuntu Config Scripts
+//
+// This module discovers and merges configuration from several layers,
+// lowest precedence first:
+// - Built-in defaults
+// - System-wide file in /etc/ubuntu-config-scripts
+// - Per-user file in $XDG_CONFIG_HOME/ubuntu-config-scripts
+// - An explicit `--config` file
+// - The active profile (`profile` key, UCS_PROFILE or `--profile`)
+// - UCS_ environment variables (`__` separates nesting levels)
+// - Command line overrides
+// and records which layer set each effective value.
+
+use crate::lib::logger::log_warn;
+use crate::lib::migrate::{migrate_value, VERSION_KEY};
+use crate::lib::profile::{Profile, PROFILES_KEY, PROFILE_ENV, PROFILE_KEY};
+use crate::lib::render::{Column, Table};
+use crate::lib::schema::{suggest, Config, ConfigFormat};
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Directory name used below the system and user configuration roots
+pub const APP_DIR: &str = "ubuntu-config-scripts";
+
+/// System-wide configuration directory
+pub const SYSTEM_CONFIG_DIR: &str = "/etc/ubuntu-config-scripts";
+
+/// Prefix of environment variables that override configuration values
+pub const ENV_PREFIX: &str = "UCS_";
+
+/// Separator between nesting levels in environment variable names
+pub const ENV_SEPARATOR: &str = "__";
+
+/// File extensions probed for `config.*`, in order of preference
+pub const CONFIG_EXTENSIONS: [&str; 4] = ["toml", "yaml", "yml", "json"];
+
+/// Layer a configuration value came from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "layer", content = "origin", rename_all = "snake_case")]
+pub enum ConfigSource {
+    Default,
+    System(PathBuf),
+    User(PathBuf),
+    File(PathBuf),
+    Profile(String),
+    Env(String),
+    Cli(String),
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::System(path) => write!(f, "system ({})", path.display()),
+            ConfigSource::User(path) => write!(f, "user ({})", path.display()),
+            ConfigSource::File(path) => write!(f, "file ({})", path.display()),
+            ConfigSource::Profile(name) => write!(f, "profile ({})", name),
+            ConfigSource::Env(var) => write!(f, "env ({})", var),
+            ConfigSource::Cli(arg) => write!(f, "cli ({})", arg),
+        }
+    }
+}
+
+/// A single configuration layer before merging
+#[derive(Debug, Clone)]
+pub struct ConfigLayer {
+    pub source: ConfigSource,
+    pub value: Value,
+}
+
+/// Effective value of one configuration key and where it came from
+#[derive(Debug, Clone, Serialize)]
+pub struct ExplainEntry {
+    pub path: String,
+    pub value: Value,
+    pub source: ConfigSource,
+}
+
+/// Merged configuration plus per-key provenance
+#[derive(Debug, Clone)]
+pub struct LoadedConfig {
+    pub config: Config,
+    pub value: Value,
+    pub provenance: BTreeMap<String, ConfigSource>,
+}
+
+impl LoadedConfig {
+    /// Layer that set the value at a dotted path (e.g. `audio.volume_level`)
+    pub fn source_of(&self, path: &str) -> ConfigSource {
+        self.provenance
+            .get(path)
+            .cloned()
+            .unwrap_or(ConfigSource::Default)
+    }
+
+    /// Every effective leaf value with the layer that set it
+    pub fn explain(&self) -> Vec<ExplainEntry> {
+        leaf_paths(&self.value)
+            .into_iter()
+            .map(|(path, value)| ExplainEntry {
+                source: self.source_of(&path),
+                path,
+                value: value.clone(),
+            })
+            .collect()
+    }
+
+    /// `explain` as a table renderable in any output format
+    pub fn explain_table(&self) -> Table {
+        let mut table = Table::new(vec![
+            Column::new("Key"),
+            Column::new("Value").max_width(40),
+            Column::new("Source").wrap(),
+        ]);
+        for entry in self.explain() {
+            table.add_row(vec![
+                entry.path,
+                entry.value.to_string(),
+                entry.source.to_string(),
+            ]);
+        }
+        table
+    }
+}
+
+/// Builder that collects and merges configuration layers
+#[derive(Debug, Clone)]
+pub struct ConfigLoader {
+    system_dir: Option<PathBuf>,
+    user_dir: Option<PathBuf>,
+    file: Option<PathBuf>,
+    env: Vec<(String, String)>,
+    overrides: Vec<(String, String)>,
+    profile: Option<Option<String>>,
+    strict_profile: bool,
+}
+
+impl ConfigLoader {
+    /// Loader using the standard directories and the process environment
+    pub fn new() -> Self {
+        Self {
+            system_dir: Some(PathBuf::from(SYSTEM_CONFIG_DIR)),
+            user_dir: user_config_dir(),
+            file: None,
+            env: std::env::vars().collect(),
+            overrides: Vec::new(),
+            profile: None,
+            strict_profile: false,
+        }
+    }
+
+    /// Loader with no file or environment layers, only defaults
+    pub fn empty() -> Self {
+        Self {
+            system_dir: None,
+            user_dir: None,
+            file: None,
+            env: Vec::new(),
+            overrides: Vec::new(),
+            profile: None,
+            strict_profile: false,
+        }
+    }
+
+    pub fn system_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.system_dir = dir;
+        self
+    }
+
+    pub fn user_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.user_dir = dir;
+        self
+    }
+
+    /// Add an explicit configuration file (e.g. from `--config`)
+    pub fn file(mut self, path: Option<PathBuf>) -> Self {
+        self.file = path;
+        self
+    }
+
+    /// Replace the environment variables considered for `UCS_` overrides
+    pub fn env_vars<I: IntoIterator<Item = (String, String)>>(mut self, vars: I) -> Self {
+        self.env = vars.into_iter().collect();
+        self
+    }
+
+    /// Add a command line override for a dotted path (e.g. `audio.volume_level`)
+    pub fn set(mut self, path: &str, value: &str) -> Self {
+        self.overrides.push((path.to_string(), value.to_string()));
+        self
+    }
+
+    /// Choose the profile for this load regardless of files and UCS_PROFILE
+    ///
+    /// `None` loads the configuration without any profile.
+    pub fn profile(mut self, name: Option<&str>) -> Self {
+        self.profile = Some(name.map(String::from));
+        self
+    }
+
+    /// Fail on an unknown or malformed active profile
+    ///
+    /// Otherwise the problem is logged and the profile is skipped, so a typo
+    /// in UCS_PROFILE does not stop every script.
+    pub fn strict_profile(mut self, strict: bool) -> Self {
+        self.strict_profile = strict;
+        self
+    }
+
+    /// Add a command line override written as `path=value`
+    pub fn set_assignment(self, assignment: &str) -> Result<Self> {
+        let (path, value) = assignment
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Expected KEY=VALUE, got '{}'", assignment))?;
+        Ok(self.set(path.trim(), value))
+    }
+
+    /// Collect the layers in precedence order, lowest first
+    pub fn layers(&self) -> Result<Vec<ConfigLayer>> {
+        let mut layers = vec![ConfigLayer {
+            source: ConfigSource::Default,
+            value: serde_json::to_value(Config::default())
+                .context("Failed to serialize default configuration")?,
+        }];
+
+        if let Some(path) = self.system_dir.as_deref().and_then(find_config_file) {
+            layers.push(ConfigLayer {
+                value: read_config_value(&path)?,
+                source: ConfigSource::System(path),
+            });
+        }
+
+        if let Some(path) = self.user_dir.as_deref().and_then(find_config_file) {
+            layers.push(ConfigLayer {
+                value: read_config_value(&path)?,
+                source: ConfigSource::User(path),
+            });
+        }
+
+        if let Some(ref path) = self.file {
+            layers.push(ConfigLayer {
+                value: read_config_value(path)?,
+                source: ConfigSource::File(path.clone()),
+            });
+        }
+
+        let profile_index = layers.len();
+        let defaults = layers[0].value.clone();
+
+        let mut env: Vec<&(String, String)> = self.env.iter().collect();
+        env.sort();
+        for (name, raw) in env {
+            let path = if name == PROFILE_ENV {
+                Some(vec![PROFILE_KEY.to_string()])
+            } else {
+                env_var_path(name)
+            };
+            if let Some(path) = path {
+                let mut value = Value::Object(Default::default());
+                let typed = typed_override_value(&defaults, &path, raw);
+                set_path(&mut value, &path, typed)?;
+                layers.push(ConfigLayer {
+                    source: ConfigSource::Env(name.clone()),
+                    value,
+                });
+            }
+        }
+
+        for (path, raw) in &self.overrides {
+            let segments = split_path(path)?;
+            let mut value = Value::Object(Default::default());
+            let typed = typed_override_value(&defaults, &segments, raw);
+            set_path(&mut value, &segments, typed)?;
+            layers.push(ConfigLayer {
+                source: ConfigSource::Cli(format!("{}={}", path, raw)),
+                value,
+            });
+        }
+
+        if let Some(ref name) = self.profile {
+            let mut value = Value::Object(Default::default());
+            let selected = name.clone().map(Value::String).unwrap_or(Value::Null);
+            set_path(&mut value, &[PROFILE_KEY.to_string()], selected)?;
+            layers.push(ConfigLayer {
+                source: ConfigSource::Cli(format!(
+                    "--profile {}",
+                    name.as_deref().unwrap_or("(none)")
+                )),
+                value,
+            });
+        }
+
+        match profile_layer(&layers) {
+            Ok(Some(layer)) => layers.insert(profile_index, layer),
+            Ok(None) => {}
+            Err(e) if !self.strict_profile => {
+                log_warn(&format!("{:#}; continuing without a profile", e), "CONFIG")
+            }
+            Err(e) => return Err(e),
+        }
+
+        Ok(layers)
+    }
+
+    /// Merge all layers into a configuration
+    pub fn load(&self) -> Result<LoadedConfig> {
+        let mut value = Value::Object(Default::default());
+        let mut provenance = BTreeMap::new();
+
+        for layer in self.layers()? {
+            for (path, _) in leaf_paths(&layer.value) {
+                provenance.insert(path, layer.source.clone());
+            }
+            merge_values(&mut value, layer.value);
+        }
+
+        let config: Config = serde_json::from_value(value.clone())
+            .context("Merged configuration does not match the expected structure")?;
+
+        Ok(LoadedConfig {
+            config,
+            value,
+            provenance,
+        })
+    }
+}
+
+/// Overlay of the profile named by the merged `profile` key, if any
+///
+/// Profiles may be defined in any layer, so the name and definitions are
+/// looked up in all layers merged together.
+fn profile_layer(layers: &[ConfigLayer]) -> Result<Option<ConfigLayer>> {
+    let mut merged = Value::Object(Default::default());
+    for layer in layers {
+        merge_values(&mut merged, layer.value.clone());
+    }
+
+    let name = match merged.get(PROFILE_KEY) {
+        None | Some(Value::Null) => return Ok(None),
+        Some(Value::String(name)) if name.is_empty() => return Ok(None),
+        Some(Value::String(name)) => name.clone(),
+        Some(other) => {
+            return Err(anyhow!(
+                "{} must be a profile name, got {}",
+                PROFILE_KEY,
+                other
+            ))
+        }
+    };
+
+    let definition = match merged.get(PROFILES_KEY).and_then(|p| p.get(&name)) {
+        Some(definition) => definition.clone(),
+        None => {
+            let names: Vec<&str> = merged
+                .get(PROFILES_KEY)
+                .and_then(Value::as_object)
+                .map(|profiles| profiles.keys().map(String::as_str).collect())
+                .unwrap_or_default();
+            let hint = suggest(&name, &names)
+                .map(|s| format!(" (did you mean {}?)", s))
+                .unwrap_or_default();
+            return Err(anyhow!("Unknown profile: {}{}", name, hint));
+        }
+    };
+    let profile: Profile = serde_json::from_value(definition)
+        .with_context(|| format!("Profile {} is not a table of settings", name))?;
+
+    Ok(Some(ConfigLayer {
+        value: profile.overlay_value(),
+        source: ConfigSource::Profile(name),
+    }))
+}
+
+impl Default for ConfigLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Load the configuration from the standard layers for a script reading `section`
+///
+/// Scripts call this at startup. Invalid values in their own section are
+/// errors; invalid values in other sections are logged as warnings, so one
+/// bad setting does not stop unrelated scripts.
+pub fn load_config(section: &str) -> Result<Config> {
+    let loaded = ConfigLoader::new().load()?;
+    let (own, others) = loaded
+        .config
+        .validation_errors()
+        .partition_at(&format!("/{}", section));
+    for error in others.errors() {
+        log_warn(&format!("Ignoring invalid setting {}", error), "CONFIG");
+    }
+    own.into_result()?;
+    Ok(loaded.config)
+}
+
+/// Per-user configuration directory (`$XDG_CONFIG_HOME/ubuntu-config-scripts`)
+pub fn user_config_dir() -> Option<PathBuf> {
+    std::env::var("XDG_CONFIG_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home::home_dir().map(|home| home.join(".config")))
+        .map(|dir| dir.join(APP_DIR))
+}
+
+/// First `config.<ext>` file present in `dir`
+pub fn find_config_file(dir: &Path) -> Option<PathBuf> {
+    CONFIG_EXTENSIONS
+        .iter()
+        .map(|ext| dir.join(format!("config.{}", ext)))
+        .find(|path| path.is_file())
+}
+
+/// Read a JSON, TOML or YAML configuration file into an untyped value
+///
+/// Older layouts are migrated in memory; the version key itself is dropped
+/// because the merged configuration is always at the current version.
+pub fn read_config_value(path: &Path) -> Result<Value> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+    let value = ConfigFormat::from_path(path)
+        .parse(&content)
+        .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+    let mut value = migrate_value(value)
+        .with_context(|| format!("Failed to migrate config file: {}", path.display()))?
+        .value;
+    if let Value::Object(ref mut object) = value {
+        object.remove(VERSION_KEY);
+    }
+    Ok(value)
+}
+
+/// Map `UCS_AUDIO__VOLUME_LEVEL` to `["audio", "volume_level"]`
+///
+/// Variables without a `__` separator (e.g. `UCS_LOG`) are runtime switches
+/// rather than configuration values and are ignored.
+pub fn env_var_path(name: &str) -> Option<Vec<String>> {
+    let rest = name.strip_prefix(ENV_PREFIX)?;
+    if !rest.contains(ENV_SEPARATOR) {
+        return None;
+    }
+    let segments: Vec<String> = rest.split(ENV_SEPARATOR).map(str::to_lowercase).collect();
+    if segments.iter().any(String::is_empty) {
+        return None;
+    }
+    Some(segments)
+}
+
+/// Split a dotted path (`audio.volume_level`) into segments
+pub fn split_path(path: &str) -> Result<Vec<String>> {
+    let segments: Vec<String> = path.split('.').map(|s| s.trim().to_string()).collect();
+    if segments.iter().any(String::is_empty) {
+        return Err(anyhow!("Invalid configuration key: '{}'", path));
+    }
+    Ok(segments)
+}
+
+/// Interpret an override as JSON (numbers, booleans, arrays, null) or else a plain string
+pub fn parse_override_value(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+/// Interpret an override for the key at `path`, keeping the field's type
+///
+/// The JSON reading is used only when it fits the field in `defaults` (the
+/// default configuration as a value), so `550` stays a string for
+/// `nvidia.target_version`. Keys inside a profile are checked like the key
+/// they overlay; unknown keys take the JSON reading.
+pub fn typed_override_value(defaults: &Value, path: &[String], raw: &str) -> Value {
+    let parsed = parse_override_value(raw);
+    let field = match path {
+        [profiles, _, rest @ ..] if profiles == PROFILES_KEY => rest,
+        _ => path,
+    };
+    if parsed.is_string() || field.is_empty() || get_path(defaults, &field.join(".")).is_none() {
+        return parsed;
+    }
+    let mut value = defaults.clone();
+    let fits = set_path(&mut value, field, parsed.clone()).is_ok()
+        && serde_json::from_value::<Config>(value).is_ok();
+    if fits {
+        parsed
+    } else {
+        Value::String(raw.to_string())
+    }
+}
+
+/// Set `value` at `path` inside `root`, creating intermediate objects
+pub fn set_path(root: &mut Value, path: &[String], value: Value) -> Result<()> {
+    let (last, parents) = path
+        .split_last()
+        .ok_or_else(|| anyhow!("Configuration key cannot be empty"))?;
+
+    let mut current = root;
+    for segment in parents {
+        if !current.is_object() {
+            *current = Value::Object(Default::default());
+        }
+        current = current
+            .as_object_mut()
+            .expect("value was just made an object")
+            .entry(segment.clone())
+            .or_insert_with(|| Value::Object(Default::default()));
+    }
+
+    if !current.is_object() {
+        *current = Value::Object(Default::default());
+    }
+    current
+        .as_object_mut()
+        .expect("value was just made an object")
+        .insert(last.clone(), value);
+    Ok(())
+}
+
+/// Value at a dotted path, if present
+pub fn get_path<'a>(root: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(root, |current, segment| current.get(segment))
+}
+
+/// Remove and return the value at a dotted path, keeping the order of the remaining keys
+pub fn take_path(root: &mut Value, path: &str) -> Result<Option<Value>> {
+    let segments = split_path(path)?;
+    let (last, parents) = segments
+        .split_last()
+        .ok_or_else(|| anyhow!("Configuration key cannot be empty"))?;
+
+    let mut current = root;
+    for segment in parents {
+        match current.get_mut(segment.as_str()) {
+            Some(next) => current = next,
+            None => return Ok(None),
+        }
+    }
+    Ok(current
+        .as_object_mut()
+        .and_then(|object| object.shift_remove(last.as_str())))
+}
+
+/// Deep-merge `layer` into `base`; objects merge key by key, anything else replaces
+pub fn merge_values(base: &mut Value, layer: Value) {
+    match (base, layer) {
+        (Value::Object(base), Value::Object(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+/// Dotted paths of all leaf values (non-empty objects are descended into)
+pub fn leaf_paths(value: &Value) -> Vec<(String, &Value)> {
+    fn walk<'a>(prefix: &str, value: &'a Value, out: &mut Vec<(String, &'a Value)>) {
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                for (key, child) in map {
+                    let path = if prefix.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{}.{}", prefix, key)
+                    };
+                    walk(&path, child, out);
+                }
+            }
+            _ if !prefix.is_empty() => out.push((prefix.to_string(), value)),
+            _ => {}
+        }
+    }
+
+    let mut out = Vec::new();
+    walk("", value, &mut out);
+    out
+}
@@ -0,0 +1,671 @@
+// Configuration file editing for Ubuntu Config Scripts
+//
+// This module changes values in a single configuration file including:
+// - Dotted keys checked against the Config schema before anything is written
+// - TOML edits that keep comments, ordering and formatting
+// - YAML edits on block-style documents that keep comments and ordering
+// - JSON edits that keep key order
+// - Whole-file validation and `$EDITOR` round trips for `config edit`
+
+use crate::lib::config::{
+    find_config_file, get_path, leaf_paths, merge_values, parse_override_value, set_path,
+    split_path, take_path,
+};
+use crate::lib::migrate::{migrate_value, CONFIG_VERSION, VERSION_KEY};
+use crate::lib::profile::{PROFILES_KEY, PROFILE_KEY};
+use crate::lib::schema::{suggest, Config, ConfigFormat, ValidationErrors};
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item, TableLike};
+
+/// Indentation used for new YAML keys when the file has no nested keys yet
+const DEFAULT_YAML_INDENT: usize = 2;
+
+/// A configuration file edited in place
+#[derive(Debug, Clone)]
+pub struct ConfigDocument {
+    path: PathBuf,
+    format: ConfigFormat,
+    text: String,
+}
+
+impl ConfigDocument {
+    /// Read a configuration file; a missing file starts out at the current version
+    pub fn open(path: &Path) -> Result<Self> {
+        let format = ConfigFormat::from_path(path);
+        let text = if path.exists() {
+            std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read config file: {}", path.display()))?
+        } else {
+            match format {
+                ConfigFormat::Json => {
+                    format!("{{\n  \"{}\": {}\n}}\n", VERSION_KEY, CONFIG_VERSION)
+                }
+                ConfigFormat::Toml => format!("{} = {}\n", VERSION_KEY, CONFIG_VERSION),
+                ConfigFormat::Yaml => format!("{}: {}\n", VERSION_KEY, CONFIG_VERSION),
+            }
+        };
+        Ok(Self::from_text(path, &text))
+    }
+
+    /// Document for `path` with the given content (format chosen by extension)
+    pub fn from_text(path: &Path, text: &str) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            format: ConfigFormat::from_path(path),
+            text: text.to_string(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn format(&self) -> ConfigFormat {
+        self.format
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Parsed content; an empty file is an empty object
+    pub fn value(&self) -> Result<Value> {
+        if self.text.trim().is_empty() {
+            return Ok(Value::Object(Default::default()));
+        }
+        let value: Value = self
+            .format
+            .parse(&self.text)
+            .with_context(|| format!("Failed to parse config file: {}", self.path.display()))?;
+        Ok(match value {
+            Value::Null => Value::Object(Default::default()),
+            value => value,
+        })
+    }
+
+    /// Value set in this file for a dotted key
+    pub fn get(&self, key: &str) -> Result<Option<Value>> {
+        Ok(get_path(&self.value()?, key).cloned())
+    }
+
+    /// Set a dotted key without checking it against the schema
+    pub fn set(&mut self, key: &str, value: Value) -> Result<()> {
+        let segments = split_path(key)?;
+        self.text = match self.format {
+            ConfigFormat::Json => {
+                let mut root = self.value()?;
+                set_path(&mut root, &segments, value)?;
+                serde_json::to_string_pretty(&root)? + "\n"
+            }
+            ConfigFormat::Toml if value.is_null() => {
+                // TOML has no null; an unset key means "use the default"
+                self.unset(key)?;
+                return Ok(());
+            }
+            ConfigFormat::Toml => {
+                let mut document = self.toml_document()?;
+                toml_set(&mut document, &segments, &value)?;
+                document.to_string()
+            }
+            ConfigFormat::Yaml => yaml_set(&self.text, &segments, &value)?,
+        };
+        Ok(())
+    }
+
+    /// Remove a dotted key; returns whether it was present
+    pub fn unset(&mut self, key: &str) -> Result<bool> {
+        let segments = split_path(key)?;
+        let text = match self.format {
+            ConfigFormat::Json => {
+                let mut root = self.value()?;
+                match take_path(&mut root, key)? {
+                    Some(_) => Some(serde_json::to_string_pretty(&root)? + "\n"),
+                    None => None,
+                }
+            }
+            ConfigFormat::Toml => {
+                let mut document = self.toml_document()?;
+                toml_unset(&mut document, &segments).then(|| document.to_string())
+            }
+            ConfigFormat::Yaml => yaml_unset(&self.text, &segments),
+        };
+        match text {
+            Some(text) => {
+                self.text = text;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Edit the document until it holds `target`, touching only keys that differ
+    ///
+    /// Keys missing from `target` are removed and new or changed values are
+    /// set where they are, so comments and ordering elsewhere survive.
+    pub fn apply(&mut self, target: &Value) -> Result<()> {
+        let current = self.value()?;
+        self.apply_at(&mut Vec::new(), Some(&current), target)
+    }
+
+    fn apply_at(
+        &mut self,
+        path: &mut Vec<String>,
+        current: Option<&Value>,
+        target: &Value,
+    ) -> Result<()> {
+        match target {
+            Value::Object(fields) if !fields.is_empty() || path.is_empty() => {
+                let existing = current.and_then(Value::as_object);
+                match existing {
+                    Some(existing) => {
+                        for key in existing.keys().filter(|key| !fields.contains_key(*key)) {
+                            path.push(key.clone());
+                            self.unset(&path.join("."))?;
+                            path.pop();
+                        }
+                    }
+                    // A value that becomes a table is replaced key by key
+                    None if current.is_some() => {
+                        self.unset(&path.join("."))?;
+                    }
+                    None => {}
+                }
+                for (key, value) in fields {
+                    path.push(key.clone());
+                    self.apply_at(path, existing.and_then(|e| e.get(key)), value)?;
+                    path.pop();
+                }
+                Ok(())
+            }
+            target if current == Some(target) => Ok(()),
+            target => self.set(&path.join("."), target.clone()),
+        }
+    }
+
+    /// Set a key from command line text after checking it against the schema
+    ///
+    /// The value is read as JSON when possible (numbers, booleans, arrays) and
+    /// as a string otherwise. Nothing changes when the value is rejected.
+    pub fn assign(&mut self, key: &str, raw: &str) -> Result<Value> {
+        let value = typed_value(key, raw)?;
+        let original = self.text.clone();
+        self.set(key, value.clone())?;
+
+        let pointer = format!("/{}", split_path(key)?.join("/"));
+        let mut relevant = ValidationErrors::new();
+        let errors = match self.validate() {
+            Ok(errors) => errors,
+            Err(e) => {
+                self.text = original;
+                return Err(e);
+            }
+        };
+        for error in errors.errors() {
+            if error.path == pointer || error.path.starts_with(&format!("{}/", pointer)) {
+                relevant.push(error.clone());
+            }
+        }
+        if !relevant.is_empty() {
+            self.text = original;
+            relevant.into_result()?;
+        }
+        Ok(value)
+    }
+
+    /// Check the whole file as a configuration layer over the defaults
+    ///
+    /// Structural problems (unparsable text, wrong types) are returned as
+    /// errors; invalid values are returned as `ValidationErrors`.
+    pub fn validate(&self) -> Result<ValidationErrors> {
+        let mut layer = migrate_value(self.value()?)?.value;
+        if let Value::Object(ref mut object) = layer {
+            object.remove(VERSION_KEY);
+        }
+        let mut merged = serde_json::to_value(Config::default())?;
+        merge_values(&mut merged, layer);
+        let config: Config = serde_json::from_value(merged).with_context(|| {
+            format!(
+                "{} does not match the configuration schema",
+                self.path.display()
+            )
+        })?;
+        Ok(config.validation_errors())
+    }
+
+    /// Write the document, creating the directory if needed
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        std::fs::write(&self.path, &self.text)
+            .with_context(|| format!("Failed to write config file: {}", self.path.display()))
+    }
+
+    fn toml_document(&self) -> Result<DocumentMut> {
+        self.text
+            .parse::<DocumentMut>()
+            .with_context(|| format!("Failed to parse config file: {}", self.path.display()))
+    }
+}
+
+/// Configuration file to edit in a layer directory (`config.toml` when none exists)
+pub fn layer_file(dir: &Path) -> PathBuf {
+    find_config_file(dir).unwrap_or_else(|| dir.join("config.toml"))
+}
+
+/// Interpret command line text as the value of a dotted key, checking its type
+///
+/// Text that parses as JSON but does not fit the field (e.g. `550` for a
+/// string field) is retried as a plain string. Keys inside a profile
+/// (`profiles.<name>.obs.fps`) are checked like the key they overlay.
+pub fn typed_value(key: &str, raw: &str) -> Result<Value> {
+    let segments = split_path(key)?;
+    if key == VERSION_KEY {
+        return Err(anyhow!(
+            "{} is managed by `config migrate` and cannot be set",
+            VERSION_KEY
+        ));
+    }
+    if segments[0] == PROFILES_KEY {
+        return match segments.len() {
+            1 | 2 => Err(anyhow!(
+                "Set single keys of a profile (e.g. {}.<name>.obs.fps)",
+                PROFILES_KEY
+            )),
+            3 if segments[2] == "description" => Ok(Value::String(raw.to_string())),
+            _ if [VERSION_KEY, PROFILE_KEY, PROFILES_KEY].contains(&segments[2].as_str()) => {
+                Err(anyhow!("{} cannot be set by a profile", segments[2]))
+            }
+            _ => typed_value(&segments[2..].join("."), raw),
+        };
+    }
+
+    let defaults = serde_json::to_value(Config::default())?;
+    if get_path(&defaults, key).is_none() {
+        let keys: Vec<String> = leaf_paths(&defaults)
+            .into_iter()
+            .map(|(k, _)| k)
+            .filter(|k| !k.starts_with(&format!("{}.", PROFILES_KEY)))
+            .collect();
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        let hint = suggest(key, &keys)
+            .map(|s| format!(" (did you mean {}?)", s))
+            .unwrap_or_default();
+        return Err(anyhow!("Unknown configuration key: {}{}", key, hint));
+    }
+
+    let mut first_error = None;
+    for candidate in [parse_override_value(raw), Value::String(raw.to_string())] {
+        let mut value = defaults.clone();
+        set_path(&mut value, &segments, candidate.clone())?;
+        match serde_json::from_value::<Config>(value) {
+            Ok(_) => return Ok(candidate),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    Err(anyhow!(
+        "Invalid value for {}: {}",
+        key,
+        first_error.expect("at least one candidate was tried")
+    ))
+}
+
+/// Open `path` in `editor` and save the result once it validates
+///
+/// `editor` is run through `sh -c` so it may carry arguments (`code --wait`).
+/// When the edited file is invalid, `retry` receives the problems and decides
+/// whether the editor opens again; the file is left untouched otherwise.
+/// Returns whether the file changed.
+pub fn edit_file(
+    path: &Path,
+    editor: &str,
+    mut retry: impl FnMut(&str) -> Result<bool>,
+) -> Result<bool> {
+    let original = ConfigDocument::open(path)?;
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("toml");
+    let scratch = tempfile::Builder::new()
+        .prefix("ucs-config-")
+        .suffix(&format!(".{}", extension))
+        .tempfile()
+        .context("Failed to create temporary file")?;
+    std::fs::write(scratch.path(), original.text()).context("Failed to write temporary file")?;
+
+    loop {
+        let status = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!("{} \"$1\"", editor))
+            .arg("sh")
+            .arg(scratch.path())
+            .status()
+            .with_context(|| format!("Failed to run editor: {}", editor))?;
+        if !status.success() {
+            return Err(anyhow!("Editor exited with {}", status));
+        }
+
+        let text = std::fs::read_to_string(scratch.path()).context("Failed to read edited file")?;
+        let edited = ConfigDocument::from_text(path, &text);
+        let problems = match edited.validate() {
+            Ok(errors) if errors.is_empty() => None,
+            Ok(errors) => Some(errors.to_string()),
+            Err(e) => Some(format!("{:#}", e)),
+        };
+
+        match problems {
+            None if text == original.text() && path.exists() => return Ok(false),
+            None => {
+                edited.save()?;
+                return Ok(true);
+            }
+            Some(problems) => {
+                if !retry(&problems)? {
+                    return Err(anyhow!(
+                        "{} was not saved: the edited configuration is invalid",
+                        path.display()
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Editor for `config edit`: `$VISUAL`, then `$EDITOR`, then `vi`
+pub fn editor_command() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+fn toml_set(document: &mut DocumentMut, segments: &[String], value: &Value) -> Result<()> {
+    let (last, parents) = segments
+        .split_last()
+        .ok_or_else(|| anyhow!("Configuration key cannot be empty"))?;
+
+    let mut table: &mut dyn TableLike = document.as_table_mut();
+    for segment in parents {
+        if !table.contains_key(segment) {
+            // Implicit tables only get a header once they hold values
+            let mut new_table = toml_edit::Table::new();
+            new_table.set_implicit(true);
+            table.insert(segment, Item::Table(new_table));
+        }
+        table = table
+            .get_mut(segment)
+            .and_then(Item::as_table_like_mut)
+            .ok_or_else(|| anyhow!("{} is not a table", segment))?;
+    }
+
+    let mut new_value = toml_value(value)?;
+    match table.get_mut(last) {
+        Some(Item::Value(existing)) => {
+            // Keep the spacing and any trailing comment of the old value
+            *new_value.decor_mut() = existing.decor().clone();
+            *existing = new_value;
+        }
+        Some(item) => *item = Item::Value(new_value),
+        None => {
+            table.insert(last, Item::Value(new_value));
+        }
+    }
+    Ok(())
+}
+
+fn toml_unset(document: &mut DocumentMut, segments: &[String]) -> bool {
+    let Some((last, parents)) = segments.split_last() else {
+        return false;
+    };
+    let mut table: &mut dyn TableLike = document.as_table_mut();
+    for segment in parents {
+        match table.get_mut(segment).and_then(Item::as_table_like_mut) {
+            Some(next) => table = next,
+            None => return false,
+        }
+    }
+    table.remove(last).is_some()
+}
+
+fn toml_value(value: &Value) -> Result<toml_edit::Value> {
+    Ok(match value {
+        Value::Bool(b) => (*b).into(),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => i.into(),
+            None => n
+                .as_f64()
+                .ok_or_else(|| anyhow!("Number out of range for TOML: {}", n))?
+                .into(),
+        },
+        Value::String(s) => s.as_str().into(),
+        Value::Array(items) => {
+            let mut array = toml_edit::Array::new();
+            for item in items {
+                array.push(toml_value(item)?);
+            }
+            array.into()
+        }
+        Value::Object(map) => {
+            let mut table = toml_edit::InlineTable::new();
+            for (key, item) in map {
+                table.insert(key, toml_value(item)?);
+            }
+            table.into()
+        }
+        Value::Null => return Err(anyhow!("TOML cannot store null values")),
+    })
+}
+
+// YAML edits work on the text of block-style documents so comments, blank
+// lines and key order survive. Values are written as plain scalars or, for
+// lists and maps, in flow style (`[a, b]`).
+
+fn yaml_indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn yaml_is_content(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && !trimmed.starts_with('#') && trimmed != "---" && trimmed != "..."
+}
+
+/// Key of a `key: value` line and the byte offset just after its colon
+fn yaml_key(line: &str) -> Option<(String, usize)> {
+    let indent = yaml_indent(line);
+    let rest = &line[indent..];
+    if rest.starts_with('-') || !yaml_is_content(line) {
+        return None;
+    }
+
+    let (key, after_key) = match rest.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let close = rest[1..].find(quote)? + 1;
+            (rest[1..close].to_string(), close + 1)
+        }
+        _ => {
+            let colon = rest
+                .char_indices()
+                .find(|&(i, c)| {
+                    c == ':' && rest[i + 1..].chars().next().is_none_or(char::is_whitespace)
+                })?
+                .0;
+            (rest[..colon].trim_end().to_string(), colon)
+        }
+    };
+    let colon = after_key + rest[after_key..].find(':')?;
+    if !rest[after_key..colon].trim().is_empty() {
+        return None;
+    }
+    Some((key, indent + colon + 1))
+}
+
+/// Trailing ` # comment` of a value, with its leading whitespace
+fn yaml_comment(value: &str) -> &str {
+    let (mut single, mut double) = (false, false);
+    let mut previous = ' ';
+    for (i, c) in value.char_indices() {
+        match c {
+            '\'' if !double => single = !single,
+            '"' if !single => double = !double,
+            '#' if !single && !double && previous.is_whitespace() => {
+                return &value[value[..i].trim_end().len()..];
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    ""
+}
+
+/// End (exclusive) of the block started by the key on line `index`
+fn yaml_block_end(lines: &[String], index: usize) -> usize {
+    let base = yaml_indent(&lines[index]);
+    let mut end = index + 1;
+    for (offset, line) in lines[index + 1..].iter().enumerate() {
+        if !yaml_is_content(line) {
+            continue;
+        }
+        let indent = yaml_indent(line);
+        // Block sequences may sit at the same indentation as their key
+        let sequence_item = indent == base && line.trim_start().starts_with('-');
+        if indent <= base && !sequence_item {
+            break;
+        }
+        end = index + 1 + offset + 1;
+    }
+    end
+}
+
+/// Line of the direct child `key` within `lines[start..end]`
+fn yaml_find_child(lines: &[String], start: usize, end: usize, key: &str) -> Option<usize> {
+    let child_indent = lines[start..end]
+        .iter()
+        .find(|line| yaml_is_content(line))
+        .map(|line| yaml_indent(line))?;
+    (start..end).find(|&i| {
+        yaml_indent(&lines[i]) == child_indent && yaml_key(&lines[i]).is_some_and(|(k, _)| k == key)
+    })
+}
+
+fn yaml_scalar(value: &Value) -> Result<String> {
+    match value {
+        Value::Array(_) | Value::Object(_) => Ok(serde_json::to_string(value)?),
+        _ => {
+            let text = serde_norway::to_string(value)?;
+            let text = text.trim_end();
+            if text.contains('\n') {
+                // Multi-line strings are written as JSON (double-quoted YAML)
+                Ok(serde_json::to_string(value)?)
+            } else {
+                Ok(text.to_string())
+            }
+        }
+    }
+}
+
+fn yaml_join(lines: Vec<String>) -> String {
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+}
+
+fn yaml_set(text: &str, segments: &[String], value: &Value) -> Result<String> {
+    let mut lines: Vec<String> = text.lines().map(String::from).collect();
+    let unit = lines
+        .iter()
+        .filter(|line| yaml_is_content(line))
+        .map(|line| yaml_indent(line))
+        .filter(|&indent| indent > 0)
+        .min()
+        .unwrap_or(DEFAULT_YAML_INDENT);
+
+    let (mut start, mut end) = (0, lines.len());
+    let mut parent_indent: Option<usize> = None;
+    for (depth, segment) in segments.iter().enumerate() {
+        let Some(index) = yaml_find_child(&lines, start, end, segment) else {
+            // Add the missing keys at the end of the deepest existing block
+            let mut indent = lines[start..end]
+                .iter()
+                .find(|line| yaml_is_content(line))
+                .map(|line| yaml_indent(line))
+                .unwrap_or_else(|| parent_indent.map_or(0, |p| p + unit));
+            let remaining = &segments[depth..];
+            let mut added = Vec::new();
+            for (i, key) in remaining.iter().enumerate() {
+                if i + 1 == remaining.len() {
+                    added.push(format!(
+                        "{}{}: {}",
+                        " ".repeat(indent),
+                        key,
+                        yaml_scalar(value)?
+                    ));
+                } else {
+                    added.push(format!("{}{}:", " ".repeat(indent), key));
+                    indent += unit;
+                }
+            }
+            let insert_at = (start..end)
+                .rev()
+                .find(|&i| yaml_is_content(&lines[i]))
+                .map_or(end, |i| i + 1);
+            lines.splice(insert_at..insert_at, added);
+            return Ok(yaml_join(lines));
+        };
+
+        let block_end = yaml_block_end(&lines, index);
+        let (_, value_start) = yaml_key(&lines[index]).expect("found lines are keys");
+        let rest = &lines[index][value_start..];
+        let comment = yaml_comment(rest);
+
+        if depth + 1 == segments.len() {
+            let line = format!(
+                "{} {}{}",
+                &lines[index][..value_start],
+                yaml_scalar(value)?,
+                comment
+            );
+            lines.splice(index..block_end, [line]);
+            return Ok(yaml_join(lines));
+        }
+
+        if !rest[..rest.len() - comment.len()].trim().is_empty() {
+            return Err(anyhow!(
+                "Cannot set {}: {} is not a block mapping",
+                segments.join("."),
+                segments[..=depth].join(".")
+            ));
+        }
+        parent_indent = Some(yaml_indent(&lines[index]));
+        start = index + 1;
+        end = block_end;
+    }
+    Err(anyhow!("Configuration key cannot be empty"))
+}
+
+fn yaml_unset(text: &str, segments: &[String]) -> Option<String> {
+    let mut lines: Vec<String> = text.lines().map(String::from).collect();
+    let (mut start, mut end) = (0, lines.len());
+    let mut parents = Vec::new();
+    for (depth, segment) in segments.iter().enumerate() {
+        let index = yaml_find_child(&lines, start, end, segment)?;
+        let block_end = yaml_block_end(&lines, index);
+        if depth + 1 == segments.len() {
+            lines.drain(index..block_end);
+            // A key left without children would read as null, so drop it too
+            for parent in parents.into_iter().rev() {
+                if yaml_block_end(&lines, parent) > parent + 1 {
+                    break;
+                }
+                lines.remove(parent);
+            }
+            return Some(yaml_join(lines));
+        }
+        parents.push(index);
+        start = index + 1;
+        end = block_end;
+    }
+    None
+}
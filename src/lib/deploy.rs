@@ -3,11 +3,30 @@
 // This module handles building and deploying scripts as binaries
 
 use crate::lib::common::*;
+use crate::lib::deps_manager::generate_sbom;
+use crate::lib::lockfile::MANIFEST;
 use crate::lib::logger::*;
-use anyhow::Result;
+use crate::lib::preflight::Preflight;
+use crate::lib::schema::DeployConfig;
+use crate::lib::trace::task_scope;
+use anyhow::{anyhow, Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Cargo output directory checked before building
+pub const BUILD_DIR: &str = "target";
+
+/// Free space a release build of every binary needs
+pub const MIN_BUILD_FREE_BYTES: u64 = 2 * 1024 * 1024 * 1024;
 
 /// Build all binaries for deployment
 pub async fn build_all() -> Result<()> {
+    // The timer span stays open across the build, so it must follow the task
+    task_scope(build_release()).await
+}
+
+async fn build_release() -> Result<()> {
+    Preflight::build_dir(BUILD_DIR, MIN_BUILD_FREE_BYTES).check()?;
+
     let timer = PerformanceTimer::new("build all binaries");
 
     log_info("Building binaries", "DEPLOY");
@@ -23,8 +42,112 @@ pub async fn build_all() -> Result<()> {
     Ok(())
 }
 
+/// Directory packages are assembled in
+pub const PACKAGE_DIR: &str = "target/package";
+
+/// Directory `cargo build --release` writes the binaries to
+pub const RELEASE_DIR: &str = "target/release";
+
+/// Names of the `[[bin]]` targets in the project manifest
+pub fn binary_names<P: AsRef<Path>>(project_root: P) -> Result<Vec<String>> {
+    let path = project_root.as_ref().join(MANIFEST);
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let manifest: toml::Value =
+        toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))?;
+    Ok(manifest
+        .get("bin")
+        .and_then(|b| b.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|bin| bin.get("name").and_then(|n| n.as_str()))
+        .map(String::from)
+        .collect())
+}
+
 /// Create deployment package
-pub async fn create_package() -> Result<()> {
-    log_info("Package creation not yet implemented", "DEPLOY");
-    Ok(())
+///
+/// Copies the release binaries into `target/package/<name>-<version>/bin`,
+/// writes the SBOMs next to them when `deploy.sbom` is set and archives the
+/// directory as a `.tar.gz`, whose path is returned. Run `build_all` first.
+pub async fn create_package(deploy: &DeployConfig) -> Result<PathBuf> {
+    task_scope(package_release(deploy)).await
+}
+
+async fn package_release(deploy: &DeployConfig) -> Result<PathBuf> {
+    let timer = PerformanceTimer::new("create package");
+
+    let binaries = if deploy.binaries.is_empty() {
+        binary_names(".")?
+    } else {
+        deploy.binaries.clone()
+    };
+    let missing: Vec<&str> = binaries
+        .iter()
+        .filter(|name| !Path::new(RELEASE_DIR).join(name).is_file())
+        .map(|name| name.as_str())
+        .collect();
+    if !missing.is_empty() {
+        timer.fail("Release binaries missing");
+        return Err(anyhow!(
+            "Release binaries not built: {} (run build_all first)",
+            missing.join(", ")
+        ));
+    }
+
+    let sbom = generate_sbom(".", deploy.sbom_system_packages)?;
+    let package_name = format!("{}-{}", sbom.name, sbom.version);
+    let package_dir = Path::new(PACKAGE_DIR).join(&package_name);
+    if package_dir.exists() {
+        std::fs::remove_dir_all(&package_dir)
+            .with_context(|| format!("Failed to clear {}", package_dir.display()))?;
+    }
+    let bin_dir = package_dir.join("bin");
+    std::fs::create_dir_all(&bin_dir)
+        .with_context(|| format!("Failed to create {}", bin_dir.display()))?;
+
+    log_info(
+        &format!(
+            "Packaging {} binaries into {}",
+            binaries.len(),
+            package_dir.display()
+        ),
+        "DEPLOY",
+    );
+    for name in &binaries {
+        std::fs::copy(Path::new(RELEASE_DIR).join(name), bin_dir.join(name))
+            .with_context(|| format!("Failed to copy {}", name))?;
+    }
+    if deploy.sbom {
+        for path in sbom.write_all(&package_dir)? {
+            log_info(&format!("Wrote {}", path.display()), "DEPLOY");
+        }
+    }
+
+    let archive = Path::new(PACKAGE_DIR).join(format!("{}.tar.gz", package_name));
+    let archive_arg = archive.to_string_lossy().to_string();
+    let result = run_command(
+        &[
+            "tar",
+            "-czf",
+            &archive_arg,
+            "-C",
+            PACKAGE_DIR,
+            &package_name,
+        ],
+        None,
+    )
+    .await?;
+    if !result.success {
+        timer.fail(&format!("tar failed: {}", result.stderr));
+        return Err(anyhow!(
+            "Failed to archive {}: {}",
+            package_name,
+            result.stderr
+        ));
+    }
+
+    log_success(&format!("Created {}", archive.display()), "DEPLOY");
+    timer.finish();
+    Ok(archive)
 }
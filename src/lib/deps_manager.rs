@@ -3,26 +3,94 @@
 // This module handles dependency checking and management for the scripts,
 // including Cargo dependencies, system dependencies, and build tools
 
+use crate::lib::advisory::{advisory_db_path, AdvisoryDatabase, AuditReport};
 use crate::lib::common::*;
+use crate::lib::license::{LicensePolicy, LicenseReport, LicenseSources, DENY_TOML};
+use crate::lib::lockfile::{nested_workspaces, workspace_manifests, DependencyGraph};
 use crate::lib::logger::*;
+use crate::lib::sbom::{installed_system_packages, Sbom};
+use crate::lib::system_deps::{
+    PackageManager, RequirementReport, RequirementStatus, SystemRequirements,
+};
+use crate::lib::trace::task_scope;
 use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command as StdCommand;
 
+/// Where a manifest dependency comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum DependencySource {
+    /// crates.io, or the named alternative registry
+    Registry { registry: Option<String> },
+    /// `git = "..."`, with the branch, tag or rev if one is given
+    Git {
+        url: String,
+        reference: Option<String>,
+    },
+    /// `path = "..."`, relative to the project root
+    Path { path: String },
+}
+
+impl Default for DependencySource {
+    fn default() -> Self {
+        DependencySource::Registry { registry: None }
+    }
+}
+
+impl std::fmt::Display for DependencySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DependencySource::Registry { registry: None } => f.write_str("crates.io"),
+            DependencySource::Registry {
+                registry: Some(registry),
+            } => write!(f, "registry {}", registry),
+            DependencySource::Git {
+                url,
+                reference: Some(reference),
+            } => write!(f, "git {}#{}", url, reference),
+            DependencySource::Git { url, .. } => write!(f, "git {}", url),
+            DependencySource::Path { path } => write!(f, "path {}", path),
+        }
+    }
+}
+
 /// Dependency information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// `name` is the key used in the manifest; for renamed dependencies
+/// (`alias = { package = "real" }`) `package` holds the real crate name.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Dependency {
     pub name: String,
     pub version: String,
+    /// Table it is declared in: `dependencies`, `dev-dependencies` or `build-dependencies`
     pub source: String,
+    /// Needed by every build (not a dev-dependency and not optional)
     pub required: bool,
+    pub package: String,
+    pub kind: DependencySource,
+    /// Package name of the workspace member declaring it
+    pub member: String,
+    /// That member's Cargo.toml, relative to the project root
+    pub manifest: String,
+    /// `cfg(...)` or target triple of a `[target.*]` table
+    pub target: Option<String>,
+    pub optional: bool,
+    /// Features enabled on the dependency
+    pub features: Vec<String>,
+    /// Features of the member that enable an optional dependency
+    pub gated_by: Vec<String>,
+    /// Inherited from `[workspace.dependencies]` with `workspace = true`
+    pub workspace: bool,
 }
 
 /// Update result for a dependency
-#[derive(Debug, Clone)]
+///
+/// `from_version` is empty for packages cargo adds, and `to_version` is
+/// `None` for packages it removes or failed to update.
+#[derive(Debug, Clone, Serialize)]
 pub struct UpdateResult {
     pub name: String,
     pub updated: bool,
@@ -31,89 +99,311 @@ pub struct UpdateResult {
     pub error: Option<String>,
 }
 
-/// Check if all required system dependencies are available
+/// Check if the core system requirements (service manager, build toolchain) are available
+///
+/// Returns the commands checked; the list comes from the `core = true`
+/// entries of the system dependency manifest (`config/system-deps.toml` or
+/// `UCS_SYSTEM_DEPS`).
 pub async fn check_system_dependencies() -> Result<Vec<String>> {
     let timer = PerformanceTimer::new("system dependency check");
-    let mut missing = Vec::new();
-
-    // Core system dependencies
-    let required_commands = vec![
-        "systemctl",
-        "ps",
-        "git",
-        "make",
-        "cargo",
-        "rustc",
-    ];
-
-    for cmd in &required_commands {
-        if !command_exists(cmd).await {
-            missing.push(cmd.to_string());
+    let requirements = SystemRequirements::load()?.core();
+    let report = requirements.check();
+
+    for check in &report.checks {
+        for cmd in &check.missing_commands {
             log_warn(&format!("Missing required command: {}", cmd), "DEPS");
-        } else {
-            log_debug(&format!("Found command: {}", cmd), "DEPS");
+        }
+        if check.status == RequirementStatus::Outdated {
+            log_warn(
+                &format!(
+                    "{} {} is older than {}",
+                    check.name,
+                    check.version.as_deref().unwrap_or_default(),
+                    check.min_version.as_deref().unwrap_or_default()
+                ),
+                "DEPS",
+            );
         }
     }
 
     timer.finish();
-    
+
+    let missing = report.missing_commands();
     if !missing.is_empty() {
         return Err(anyhow!(
             "Missing required commands: {}",
             missing.join(", ")
         ));
     }
-    
-    Ok(required_commands.into_iter().map(String::from).collect())
+
+    Ok(requirements.commands().into_iter().map(String::from).collect())
 }
 
-/// Scan Cargo dependencies from Cargo.toml
+/// Check one script's system requirements, logging an install plan for what is unmet
+///
+/// Never fails: an unreadable manifest is logged and yields an empty report.
+pub fn check_script_requirements(script: &str) -> RequirementReport {
+    let requirements = match SystemRequirements::load() {
+        Ok(requirements) => requirements.for_script(script),
+        Err(e) => {
+            log_warn(
+                &format!("Skipping system requirement check: {:#}", e),
+                "DEPS",
+            );
+            return RequirementReport::default();
+        }
+    };
+    let report = requirements.check();
+    if report.is_ok() {
+        log_debug(
+            &format!("{} system requirements met", report.checks.len()),
+            "DEPS",
+        );
+        return report;
+    }
+
+    for check in report.unmet() {
+        let detail = if check.status == RequirementStatus::Outdated {
+            format!(
+                "{} is older than {}",
+                check.version.as_deref().unwrap_or_default(),
+                check.min_version.as_deref().unwrap_or_default()
+            )
+        } else {
+            format!("missing {}", check.missing_commands.join(", "))
+        };
+        log_warn(&format!("{} needs {}: {}", script, check.name, detail), "DEPS");
+    }
+    match PackageManager::detect() {
+        Some(manager) => {
+            let plan = requirements.install_plan(&report, manager);
+            if let Some(command) = plan.command() {
+                log_info(&format!("Install with: {}", command.join(" ")), "DEPS");
+            }
+            if !plan.unavailable.is_empty() {
+                log_warn(
+                    &format!(
+                        "No {} package for: {}",
+                        manager,
+                        plan.unavailable.join(", ")
+                    ),
+                    "DEPS",
+                );
+            }
+        }
+        None => log_warn("No supported package manager found", "DEPS"),
+    }
+    report
+}
+
+/// Dependency tables a manifest can declare, with their `required` default
+const DEPENDENCY_TABLES: [(&str, bool); 3] = [
+    ("dependencies", true),
+    ("build-dependencies", true),
+    ("dev-dependencies", false),
+];
+
+/// Scan the dependencies of every manifest in a project
+///
+/// Covers the root workspace and its members, plus separate workspaces
+/// below the root (such as `ruchy/`), including build-dependencies,
+/// `[target.*]` tables, renamed, git, path and optional dependencies, and
+/// entries inherited with `workspace = true`.
 pub fn scan_cargo_dependencies(project_root: &str) -> Result<Vec<Dependency>> {
-    let cargo_path = Path::new(project_root).join("Cargo.toml");
-    
-    if !cargo_path.exists() {
+    let project_root = Path::new(project_root);
+    if !project_root.join("Cargo.toml").exists() {
         return Ok(Vec::new());
     }
 
-    let content = fs::read_to_string(&cargo_path)
-        .with_context(|| format!("Failed to read {}", cargo_path.display()))?;
+    let mut workspaces = vec![project_root.to_path_buf()];
+    workspaces.extend(nested_workspaces(project_root)?);
+    let mut scanned: Vec<PathBuf> = Vec::new();
+    let mut deps = Vec::new();
+    for workspace in &workspaces {
+        let manifests = workspace_manifests(workspace)?;
+        let Some((_, root)) = manifests.first() else {
+            continue;
+        };
+        let inherited = root
+            .get("workspace")
+            .and_then(|w| w.get("dependencies"))
+            .and_then(|d| d.as_table());
+        for (path, manifest) in &manifests {
+            if scanned.contains(path) {
+                continue;
+            }
+            scanned.push(path.clone());
+            deps.extend(
+                scan_manifest(manifest, path, project_root, workspace, inherited)
+                    .with_context(|| format!("Failed to scan {}", path.display()))?,
+            );
+        }
+    }
 
-    // Parse TOML to extract dependencies
-    let toml_value: toml::Value = toml::from_str(&content)
-        .context("Failed to parse Cargo.toml")?;
+    log_info(
+        &format!(
+            "Found {} Cargo dependencies in {} manifests",
+            deps.len(),
+            scanned.len()
+        ),
+        "DEPS",
+    );
+    Ok(deps)
+}
 
-    let mut deps = Vec::new();
+/// Dependencies declared by one manifest
+fn scan_manifest(
+    manifest: &toml::Value,
+    manifest_path: &Path,
+    project_root: &Path,
+    workspace_root: &Path,
+    inherited: Option<&toml::map::Map<String, toml::Value>>,
+) -> Result<Vec<Dependency>> {
+    let Some(member) = manifest
+        .get("package")
+        .and_then(|p| p.get("name"))
+        .and_then(|n| n.as_str())
+    else {
+        // A virtual workspace manifest declares no dependencies of its own
+        return Ok(Vec::new());
+    };
 
-    // Extract regular dependencies
-    if let Some(dependencies) = toml_value.get("dependencies").and_then(|d| d.as_table()) {
-        for (name, value) in dependencies {
-            let version = extract_version(value);
-            deps.push(Dependency {
-                name: name.clone(),
-                version,
-                source: "dependencies".to_string(),
-                required: true,
-            });
-        }
+    let mut sections: Vec<(Option<String>, &toml::Value)> = vec![(None, manifest)];
+    if let Some(targets) = manifest.get("target").and_then(|t| t.as_table()) {
+        sections.extend(targets.iter().map(|(cfg, table)| (Some(cfg.clone()), table)));
     }
+    let features = manifest.get("features").and_then(|f| f.as_table());
+    let manifest_dir = manifest_path.parent().unwrap_or(project_root);
+    let relative_manifest = manifest_path
+        .strip_prefix(project_root)
+        .unwrap_or(manifest_path)
+        .display()
+        .to_string();
 
-    // Extract dev dependencies
-    if let Some(dev_deps) = toml_value.get("dev-dependencies").and_then(|d| d.as_table()) {
-        for (name, value) in dev_deps {
-            let version = extract_version(value);
-            deps.push(Dependency {
-                name: name.clone(),
-                version,
-                source: "dev-dependencies".to_string(),
-                required: false,
-            });
+    let mut deps = Vec::new();
+    for (target, section) in sections {
+        for (table, required) in DEPENDENCY_TABLES {
+            let Some(entries) = section.get(table).and_then(|t| t.as_table()) else {
+                continue;
+            };
+            for (name, value) in entries {
+                let is_workspace = value
+                    .get("workspace")
+                    .and_then(|w| w.as_bool())
+                    .unwrap_or(false);
+                // Paths in `[workspace.dependencies]` are relative to the workspace root
+                let (entry, base) = if is_workspace {
+                    let entry = inherited.and_then(|d| d.get(name)).ok_or_else(|| {
+                        anyhow!(
+                            "{} inherits {} from the workspace, but {} does not define it",
+                            member,
+                            name,
+                            workspace_root.join("Cargo.toml").display()
+                        )
+                    })?;
+                    (entry, workspace_root)
+                } else {
+                    (value, manifest_dir)
+                };
+
+                let optional = value
+                    .get("optional")
+                    .and_then(|o| o.as_bool())
+                    .unwrap_or(false);
+                let mut dep_features = string_list(entry.get("features"));
+                if is_workspace {
+                    dep_features.extend(string_list(value.get("features")));
+                }
+                deps.push(Dependency {
+                    name: name.clone(),
+                    version: extract_version(entry),
+                    source: table.to_string(),
+                    required: required && !optional,
+                    package: entry
+                        .get("package")
+                        .and_then(|p| p.as_str())
+                        .unwrap_or(name)
+                        .to_string(),
+                    kind: dependency_source(entry, base, project_root),
+                    member: member.to_string(),
+                    manifest: relative_manifest.clone(),
+                    target: target.clone(),
+                    optional,
+                    features: dep_features,
+                    gated_by: if optional {
+                        gating_features(features, name)
+                    } else {
+                        Vec::new()
+                    },
+                    workspace: is_workspace,
+                });
+            }
         }
     }
-
-    log_info(&format!("Found {} Cargo dependencies", deps.len()), "DEPS");
     Ok(deps)
 }
 
+fn string_list(value: Option<&toml::Value>) -> Vec<String> {
+    value
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|v| v.as_str())
+        .map(String::from)
+        .collect()
+}
+
+/// Registry, git or path source of a dependency entry
+fn dependency_source(entry: &toml::Value, base: &Path, project_root: &Path) -> DependencySource {
+    let field = |key: &str| entry.get(key).and_then(|v| v.as_str()).map(String::from);
+    if let Some(url) = field("git") {
+        let reference = field("rev").or_else(|| field("tag")).or_else(|| field("branch"));
+        return DependencySource::Git { url, reference };
+    }
+    if let Some(path) = field("path") {
+        let joined = base.join(&path);
+        let path = joined
+            .strip_prefix(project_root)
+            .map(|p| p.display().to_string())
+            .unwrap_or(path);
+        return DependencySource::Path { path };
+    }
+    DependencySource::Registry {
+        registry: field("registry"),
+    }
+}
+
+/// Features that turn on an optional dependency
+///
+/// `dep:name` and `name/feature` entries enable it (`name?/feature` does
+/// not); without any `dep:name` entry cargo also creates an implicit
+/// feature called `name`.
+fn gating_features(
+    features: Option<&toml::map::Map<String, toml::Value>>,
+    name: &str,
+) -> Vec<String> {
+    let mut gating = Vec::new();
+    let mut explicit = false;
+    for (feature, entries) in features.into_iter().flatten() {
+        for entry in string_list(Some(entries)) {
+            let enabled = match entry.strip_prefix("dep:") {
+                Some(dep) => {
+                    explicit |= dep == name;
+                    dep
+                }
+                None => entry.split_once('/').map_or(entry.as_str(), |(dep, _)| dep),
+            };
+            if enabled == name && !gating.contains(feature) {
+                gating.push(feature.clone());
+            }
+        }
+    }
+    if !explicit && !gating.iter().any(|f| f == name) {
+        gating.insert(0, name.to_string());
+    }
+    gating
+}
+
 /// Extract version from TOML dependency value
 fn extract_version(value: &toml::Value) -> String {
     match value {
@@ -134,7 +424,7 @@ pub fn check_outdated_cargo() -> Result<Vec<Dependency>> {
 
     // Run cargo outdated if available
     let output = StdCommand::new("cargo")
-        .args(&["outdated", "--format", "json"])
+        .args(["outdated", "--format", "json"])
         .output();
 
     match output {
@@ -156,6 +446,8 @@ pub fn check_outdated_cargo() -> Result<Vec<Dependency>> {
                                 version: format!("{} -> {}", project, latest),
                                 source: "outdated".to_string(),
                                 required: true,
+                                package: name.to_string(),
+                                ..Default::default()
                             });
                         }
                     }
@@ -173,161 +465,334 @@ pub fn check_outdated_cargo() -> Result<Vec<Dependency>> {
     Ok(Vec::new())
 }
 
-/// Update Cargo dependencies
-pub fn update_cargo_dependencies(dry_run: bool) -> Result<Vec<UpdateResult>> {
-    let results = Vec::new();
-    
-    log_info("Updating Cargo dependencies...", "DEPS");
+/// `cargo update` for a whole project or selected packages
+#[derive(Debug, Clone)]
+pub struct CargoUpdate {
+    project_root: PathBuf,
+    packages: Vec<String>,
+    precise: Option<String>,
+    dry_run: bool,
+}
 
-    if dry_run {
-        log_info("[DRY RUN] Would run: cargo update", "DEPS");
-        return Ok(results);
+impl CargoUpdate {
+    pub fn new<P: AsRef<Path>>(project_root: P) -> Self {
+        Self {
+            project_root: project_root.as_ref().to_path_buf(),
+            packages: Vec::new(),
+            precise: None,
+            dry_run: false,
+        }
     }
 
-    let output = StdCommand::new("cargo")
-        .arg("update")
-        .output()
-        .context("Failed to run cargo update")?;
+    /// Only update this package (`-p`); every package when none are given
+    pub fn package(mut self, name: &str) -> Self {
+        self.packages.push(name.to_string());
+        self
+    }
 
-    if output.status.success() {
-        log_success("Cargo dependencies updated successfully", "DEPS");
-        
-        // Parse output to determine what was updated
-        let output_str = String::from_utf8_lossy(&output.stderr);
-        for line in output_str.lines() {
-            if line.contains("Updating") {
-                log_info(line, "DEPS");
+    /// Update the single selected package to exactly this version (`--precise`)
+    pub fn precise(mut self, version: &str) -> Self {
+        self.precise = Some(version.to_string());
+        self
+    }
+
+    /// Report what would change without writing Cargo.lock
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// The `cargo update` arguments
+    pub fn args(&self) -> Vec<String> {
+        let mut args = vec!["update".to_string()];
+        for package in &self.packages {
+            args.push("-p".to_string());
+            args.push(package.clone());
+        }
+        if let Some(ref version) = self.precise {
+            args.push("--precise".to_string());
+            args.push(version.clone());
+        }
+        if self.dry_run {
+            args.push("--dry-run".to_string());
+        }
+        args
+    }
+
+    /// Run cargo and report every package it changed (or would change)
+    ///
+    /// A failing cargo is not an error: each selected package (or `*` for
+    /// all) is reported with cargo's message and its locked version.
+    pub fn run(&self) -> Result<Vec<UpdateResult>> {
+        if self.precise.is_some() && self.packages.len() != 1 {
+            return Err(anyhow!("--precise needs exactly one package"));
+        }
+        let args = self.args();
+        log_info(&format!("Running: cargo {}", args.join(" ")), "DEPS");
+
+        let output = StdCommand::new("cargo")
+            .args(&args)
+            .current_dir(&self.project_root)
+            .output()
+            .context("Failed to run cargo update")?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if output.status.success() {
+            return Ok(parse_update_output(&stderr, self.dry_run));
+        }
+
+        let error = stderr
+            .lines()
+            .find_map(|line| line.strip_prefix("error: "))
+            .unwrap_or_else(|| stderr.trim())
+            .to_string();
+        let graph = DependencyGraph::load(&self.project_root).ok();
+        let names = if self.packages.is_empty() {
+            vec!["*".to_string()]
+        } else {
+            self.packages.clone()
+        };
+        Ok(names
+            .into_iter()
+            .map(|name| {
+                let from_version = graph
+                    .as_ref()
+                    .map(|graph| {
+                        graph
+                            .find(&name)
+                            .iter()
+                            .map(|&i| graph.package(i).version.to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    })
+                    .unwrap_or_default();
+                UpdateResult {
+                    name,
+                    updated: false,
+                    from_version,
+                    to_version: None,
+                    error: Some(error.clone()),
+                }
+            })
+            .collect())
+    }
+}
+
+/// Read cargo's `Updating`, `Downgrading`, `Adding` and `Removing` lines
+///
+/// Index and git fetch lines (`Updating crates.io index`) have no version
+/// and are skipped. Every result is marked updated unless `dry_run` is set.
+pub fn parse_update_output(output: &str, dry_run: bool) -> Vec<UpdateResult> {
+    let version = |word: Option<&str>| -> Option<String> {
+        let version = word?.strip_prefix('v')?;
+        semver::Version::parse(version).ok()?;
+        Some(version.to_string())
+    };
+
+    let mut results = Vec::new();
+    for line in output.lines() {
+        let mut words = line.split_whitespace();
+        let (Some(action), Some(name)) = (words.next(), words.next()) else {
+            continue;
+        };
+        let Some(first) = version(words.next()) else {
+            continue;
+        };
+        let (from_version, to_version) = match action {
+            "Updating" | "Downgrading" => {
+                if words.next() != Some("->") {
+                    continue;
+                }
+                match version(words.next()) {
+                    Some(to) => (first, Some(to)),
+                    None => continue,
+                }
             }
+            "Adding" => (String::new(), Some(first)),
+            "Removing" => (first, None),
+            _ => continue,
+        };
+        results.push(UpdateResult {
+            name: name.to_string(),
+            updated: !dry_run,
+            from_version,
+            to_version,
+            error: None,
+        });
+    }
+    results
+}
+
+/// Update Cargo dependencies
+pub fn update_cargo_dependencies(dry_run: bool) -> Result<Vec<UpdateResult>> {
+    log_info("Updating Cargo dependencies...", "DEPS");
+    let results = CargoUpdate::new(".").dry_run(dry_run).run()?;
+
+    for result in &results {
+        if let Some(ref error) = result.error {
+            log_error(&format!("Failed to update dependencies: {}", error), "DEPS");
+            continue;
         }
-    } else {
-        let error = String::from_utf8_lossy(&output.stderr);
-        log_error(&format!("Failed to update dependencies: {}", error), "DEPS");
+        let change = match result.to_version {
+            Some(ref to) if result.from_version.is_empty() => format!("added {}", to),
+            Some(ref to) => format!("{} -> {}", result.from_version, to),
+            None => format!("removed {}", result.from_version),
+        };
+        let prefix = if dry_run { "[DRY RUN] " } else { "" };
+        log_info(&format!("{}{}: {}", prefix, result.name, change), "DEPS");
+    }
+    if results.iter().all(|r| r.error.is_none()) && !dry_run {
+        log_success(
+            &format!("Cargo dependencies updated ({} changes)", results.len()),
+            "DEPS",
+        );
     }
 
     Ok(results)
 }
 
+/// Audit a project's Cargo.lock against a local RustSec advisory database
+pub fn audit_project<P: AsRef<Path>, D: AsRef<Path>>(
+    project_root: P,
+    advisory_db: D,
+) -> Result<AuditReport> {
+    let database = AdvisoryDatabase::open(advisory_db)?;
+    let graph = DependencyGraph::load(project_root)?;
+    Ok(database.audit(&graph))
+}
+
 /// Run security audit on dependencies
+///
+/// Reads the advisory database from `UCS_ADVISORY_DB` or
+/// `~/.cargo/advisory-db`; a missing database is an error, not a pass.
 pub fn audit_dependencies() -> Result<bool> {
     log_info("Running security audit...", "DEPS");
-
-    // Check if cargo-audit is installed
-    let output = StdCommand::new("cargo")
-        .args(&["audit", "--version"])
-        .output();
-
-    if output.is_err() || !output.unwrap().status.success() {
-        log_warn("cargo-audit not installed, skipping security audit", "DEPS");
-        log_info("Install with: cargo install cargo-audit", "DEPS");
-        return Ok(true);
+    let report = audit_project(".", advisory_db_path()?)?;
+
+    for finding in report.warnings() {
+        log_warn(
+            &format!(
+                "{} {} {}: {} ({})",
+                finding.package,
+                finding.version,
+                finding.severity_label(),
+                finding.title,
+                finding.id
+            ),
+            "DEPS",
+        );
+    }
+    let vulnerabilities = report.vulnerabilities();
+    for finding in &vulnerabilities {
+        log_error(
+            &format!(
+                "{} {} ({} severity): {} ({}, via {})",
+                finding.package,
+                finding.version,
+                finding.severity_label(),
+                finding.title,
+                finding.id,
+                finding.path.join(" > ")
+            ),
+            "DEPS",
+        );
     }
 
-    // Run the audit
-    let output = StdCommand::new("cargo")
-        .arg("audit")
-        .output()
-        .context("Failed to run cargo audit")?;
-
-    if output.status.success() {
-        log_success("Security audit passed", "DEPS");
+    if vulnerabilities.is_empty() {
+        log_success(
+            &format!(
+                "Security audit passed ({} packages, {} advisories)",
+                report.packages, report.advisories
+            ),
+            "DEPS",
+        );
         Ok(true)
     } else {
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        log_error(&format!("Security audit found issues:\n{}", output_str), "DEPS");
+        log_error(
+            &format!("Security audit found {} vulnerabilities", vulnerabilities.len()),
+            "DEPS",
+        );
         Ok(false)
     }
 }
 
-/// Check license compatibility
+/// Check a project's locked crates against the license policy in a deny.toml
+pub fn license_report<P: AsRef<Path>, D: AsRef<Path>>(
+    project_root: P,
+    deny_toml: D,
+) -> Result<LicenseReport> {
+    let policy = LicensePolicy::from_file(deny_toml)?;
+    let graph = DependencyGraph::load(&project_root)?;
+    let sources = LicenseSources::new(&project_root)?;
+    Ok(policy.check(&graph, &sources))
+}
+
+/// Check license compatibility against ./deny.toml
+///
+/// Returns the license expression of every crate whose metadata was found.
 pub fn check_licenses() -> Result<HashMap<String, String>> {
     log_info("Checking dependency licenses...", "DEPS");
-    let mut licenses = HashMap::new();
-
-    // Use cargo-license if available
-    let output = StdCommand::new("cargo")
-        .args(&["license", "--json"])
-        .output();
+    let report = license_report(".", DENY_TOML)?;
 
-    match output {
-        Ok(output) if output.status.success() => {
-            let json_str = String::from_utf8_lossy(&output.stdout);
-            if let Ok(json) = serde_json::from_str::<Vec<serde_json::Value>>(&json_str) {
-                for item in json {
-                    if let (Some(name), Some(license)) = (
-                        item["name"].as_str(),
-                        item["license"].as_str(),
-                    ) {
-                        licenses.insert(name.to_string(), license.to_string());
-                    }
-                }
-            }
-        }
-        _ => {
-            log_warn("cargo-license not available, skipping license check", "DEPS");
-            log_info("Install with: cargo install cargo-license", "DEPS");
-        }
+    for entry in report.rejected() {
+        log_error(
+            &format!("{} {}: {}", entry.name, entry.version, entry.reason),
+            "DEPS",
+        );
     }
-
-    // Check for problematic licenses
-    let problematic = ["GPL", "AGPL", "LGPL"];
-    for (name, license) in &licenses {
-        for prob in &problematic {
-            if license.contains(prob) {
-                log_warn(
-                    &format!("{} uses {} license which may have compatibility issues", name, license),
-                    "DEPS",
-                );
-            }
-        }
+    let unknown = report.unknown().len();
+    if unknown > 0 {
+        log_warn(
+            &format!("{} crates are not in the local registry cache", unknown),
+            "DEPS",
+        );
+    }
+    if report.is_ok() {
+        log_success("All dependency licenses are allowed", "DEPS");
     }
 
-    Ok(licenses)
+    Ok(report
+        .crates
+        .into_iter()
+        .filter_map(|entry| entry.license.map(|license| (entry.name, license)))
+        .collect())
+}
+
+/// Bill of materials for a project's workspace members
+///
+/// With `include_system`, the Debian packages providing the commands in the
+/// system dependency manifest are listed as well, as installed on this host.
+pub fn generate_sbom<P: AsRef<Path>>(project_root: P, include_system: bool) -> Result<Sbom> {
+    let graph = DependencyGraph::load(&project_root)?;
+    let sources = LicenseSources::new(&project_root)?;
+    let sbom = Sbom::from_graph(&graph, &sources)?;
+    if !include_system {
+        return Ok(sbom);
+    }
+    let requirements = SystemRequirements::load()?;
+    let packages = installed_system_packages(&requirements.commands());
+    if packages.is_empty() {
+        log_warn("No dpkg package provides the required commands", "DEPS");
+    }
+    Ok(sbom.system_packages(&packages))
 }
 
-/// Generate dependency tree
+/// Generate dependency tree from Cargo.lock
 pub fn dependency_tree() -> Result<String> {
     log_info("Generating dependency tree...", "DEPS");
-
-    let output = StdCommand::new("cargo")
-        .args(&["tree"])
-        .output()
-        .context("Failed to run cargo tree")?;
-
-    if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
-    } else {
-        Err(anyhow!(
-            "Failed to generate dependency tree: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ))
-    }
+    let graph = DependencyGraph::load(".")?;
+    Ok(graph.tree(None))
 }
 
-/// Find duplicate dependencies
+/// Find packages locked at more than one version
 pub fn find_duplicate_dependencies() -> Result<HashSet<String>> {
     log_info("Finding duplicate dependencies...", "DEPS");
-    let mut duplicates = HashSet::new();
+    let graph = DependencyGraph::load(".")?;
+    let duplicates: HashSet<String> = graph.duplicates().keys().map(|s| s.to_string()).collect();
 
-    let output = StdCommand::new("cargo")
-        .args(&["tree", "--duplicates"])
-        .output()
-        .context("Failed to run cargo tree")?;
-
-    if output.status.success() {
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        for line in output_str.lines() {
-            if line.contains(" v") && !line.starts_with(' ') {
-                if let Some(name) = line.split_whitespace().next() {
-                    duplicates.insert(name.to_string());
-                }
-            }
-        }
-        
-        if duplicates.is_empty() {
-            log_success("No duplicate dependencies found", "DEPS");
-        } else {
-            log_warn(&format!("Found {} duplicate dependencies", duplicates.len()), "DEPS");
-        }
+    if duplicates.is_empty() {
+        log_success("No duplicate dependencies found", "DEPS");
+    } else {
+        log_warn(&format!("Found {} duplicate dependencies", duplicates.len()), "DEPS");
     }
 
     Ok(duplicates)
@@ -358,7 +823,7 @@ pub fn verify_lockfile() -> Result<bool> {
     log_info("Verifying Cargo.lock...", "DEPS");
 
     let output = StdCommand::new("cargo")
-        .args(&["verify-project"])
+        .args(["verify-project"])
         .output()
         .context("Failed to run cargo verify-project")?;
 
@@ -380,44 +845,31 @@ pub async fn install_system_dependencies(deps: &[String]) -> Result<()> {
 
     log_info(&format!("Installing {} dependencies...", deps.len()), "DEPS");
 
-    // Detect package manager
-    let package_manager = if command_exists("apt").await {
-        "apt"
-    } else if command_exists("dnf").await {
-        "dnf"
-    } else if command_exists("yum").await {
-        "yum"
-    } else if command_exists("pacman").await {
-        "pacman"
-    } else {
-        return Err(anyhow!("No supported package manager found"));
-    };
-
-    log_info(&format!("Using package manager: {}", package_manager), "DEPS");
-
-    // Map commands to package names
-    let package_map: HashMap<&str, &str> = [
-        ("git", "git"),
-        ("make", "make"),
-        ("cargo", "cargo"),
-        ("rustc", "rustc"),
-    ]
-    .iter()
-    .cloned()
-    .collect();
+    let manager = PackageManager::detect()
+        .ok_or_else(|| anyhow!("No supported package manager found"))?;
+    log_info(&format!("Using package manager: {}", manager), "DEPS");
 
+    // Map commands to package names through the manifest
+    let requirements = SystemRequirements::load()?;
+    let mut packages: Vec<String> = Vec::new();
     for dep in deps {
-        let package_name = package_map
-            .get(dep.as_str())
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| dep.clone());
-        
-        let install_cmd = match package_manager {
-            "apt" => vec!["sudo", "apt", "install", "-y", &package_name],
-            "dnf" | "yum" => vec!["sudo", package_manager, "install", "-y", &package_name],
-            "pacman" => vec!["sudo", "pacman", "-S", "--noconfirm", &package_name],
-            _ => continue,
+        let provided = match requirements.providing(dep) {
+            Some(requirement) => requirement.packages_for(manager).to_vec(),
+            None => vec![dep.clone()],
         };
+        if provided.is_empty() {
+            log_warn(&format!("No {} package provides {}", manager, dep), "DEPS");
+        }
+        for package in provided {
+            if !packages.contains(&package) {
+                packages.push(package);
+            }
+        }
+    }
+
+    for package_name in &packages {
+        let install_cmd = manager.install_command(std::slice::from_ref(package_name));
+        let install_cmd: Vec<&str> = install_cmd.iter().map(|s| s.as_str()).collect();
 
         log_info(&format!("Installing {}...", package_name), "DEPS");
         let result = run_command(&install_cmd, None).await?;
@@ -438,7 +890,7 @@ pub fn install_cargo_tools(tools: &[&str]) -> Result<()> {
         log_info(&format!("Installing cargo-{}...", tool), "DEPS");
         
         let output = StdCommand::new("cargo")
-            .args(&["install", &format!("cargo-{}", tool)])
+            .args(["install", &format!("cargo-{}", tool)])
             .output()
             .context(format!("Failed to install cargo-{}", tool))?;
 
@@ -461,6 +913,10 @@ pub fn install_cargo_tools(tools: &[&str]) -> Result<()> {
 
 /// Check all dependencies (system and Cargo)
 pub async fn check_all_dependencies() -> Result<()> {
+    task_scope(check_all()).await
+}
+
+async fn check_all() -> Result<()> {
     let timer = PerformanceTimer::new("full dependency check");
 
     // Check system dependencies
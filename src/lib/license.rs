@@ -0,0 +1,675 @@
+// License policy for Ubuntu Config Scripts
+//
+// This module checks the licenses of locked dependencies against the
+// `[licenses]` section of `deny.toml` without cargo-deny, including:
+// - SPDX expressions (`AND`, `OR`, `WITH`, parentheses and the legacy `/`)
+// - `allow`, `deny`, per-crate `exceptions` and `clarify` entries
+// - License fields read from the `Cargo.toml` files in the local registry
+//   source cache and the workspace manifests
+// - A pass/fail report per crate
+//
+// Crates that are not in the source cache (e.g. other platforms' crates
+// that were never downloaded) are reported as unknown, not rejected.
+
+use crate::lib::lockfile::{workspace_manifests, DependencyGraph, Package};
+use crate::lib::render::{Column, Table};
+use anyhow::{anyhow, Context, Result};
+use semver::VersionReq;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// cargo-deny configuration, relative to the project root
+pub const DENY_TOML: &str = "deny.toml";
+
+/// A single license, optionally with an exception (`Apache-2.0 WITH LLVM-exception`)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LicenseReq {
+    pub id: String,
+    pub exception: Option<String>,
+}
+
+impl LicenseReq {
+    /// Parse an expression that must name exactly one license
+    pub fn parse(text: &str) -> Result<Self> {
+        match LicenseExpr::parse(text)? {
+            LicenseExpr::License(req) => Ok(req),
+            _ => Err(anyhow!("Expected a single license, found '{}'", text)),
+        }
+    }
+}
+
+impl fmt::Display for LicenseReq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.exception {
+            Some(ref exception) => write!(f, "{} WITH {}", self.id, exception),
+            None => f.write_str(&self.id),
+        }
+    }
+}
+
+/// Parsed SPDX license expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LicenseExpr {
+    License(LicenseReq),
+    And(Vec<LicenseExpr>),
+    Or(Vec<LicenseExpr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    With,
+    Id(String),
+}
+
+impl LicenseExpr {
+    /// Parse an SPDX expression; `WITH` binds tighter than `AND`, `AND` tighter than `OR`
+    ///
+    /// Operators are accepted in any case, and `/` is read as `OR` as older
+    /// crates (`MIT/Apache-2.0`) use it.
+    pub fn parse(text: &str) -> Result<Self> {
+        let tokens = tokenize(text)?;
+        let mut position = 0;
+        let expr = parse_or(&tokens, &mut position)
+            .with_context(|| format!("Invalid license expression: {}", text))?;
+        if position < tokens.len() {
+            return Err(anyhow!(
+                "Invalid license expression: {} (unexpected '{}')",
+                text,
+                token_text(&tokens[position])
+            ));
+        }
+        Ok(expr)
+    }
+
+    /// Every license named in the expression, in order
+    pub fn licenses(&self) -> Vec<&LicenseReq> {
+        match self {
+            LicenseExpr::License(req) => vec![req],
+            LicenseExpr::And(terms) | LicenseExpr::Or(terms) => {
+                terms.iter().flat_map(|t| t.licenses()).collect()
+            }
+        }
+    }
+
+    /// Whether the expression holds when `accepted` decides each license
+    pub fn evaluate<F: Fn(&LicenseReq) -> bool + Copy>(&self, accepted: F) -> bool {
+        match self {
+            LicenseExpr::License(req) => accepted(req),
+            LicenseExpr::And(terms) => terms.iter().all(|t| t.evaluate(accepted)),
+            LicenseExpr::Or(terms) => terms.iter().any(|t| t.evaluate(accepted)),
+        }
+    }
+}
+
+impl fmt::Display for LicenseExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LicenseExpr::License(req) => write!(f, "{}", req),
+            LicenseExpr::Or(terms) => {
+                let terms: Vec<String> = terms.iter().map(|t| t.to_string()).collect();
+                f.write_str(&terms.join(" OR "))
+            }
+            LicenseExpr::And(terms) => {
+                let terms: Vec<String> = terms
+                    .iter()
+                    .map(|t| match t {
+                        LicenseExpr::Or(_) => format!("({})", t),
+                        _ => t.to_string(),
+                    })
+                    .collect();
+                f.write_str(&terms.join(" AND "))
+            }
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let spaced = text
+        .replace('(', " ( ")
+        .replace(')', " ) ")
+        .replace('/', " / ");
+    spaced
+        .split_whitespace()
+        .map(|word| match word.to_uppercase().as_str() {
+            "(" => Ok(Token::Open),
+            ")" => Ok(Token::Close),
+            "AND" => Ok(Token::And),
+            "OR" | "/" => Ok(Token::Or),
+            "WITH" => Ok(Token::With),
+            _ if word
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '+' | ':')) =>
+            {
+                Ok(Token::Id(word.to_string()))
+            }
+            _ => Err(anyhow!(
+                "Invalid license identifier '{}' in: {}",
+                word,
+                text
+            )),
+        })
+        .collect()
+}
+
+fn token_text(token: &Token) -> &str {
+    match token {
+        Token::Open => "(",
+        Token::Close => ")",
+        Token::And => "AND",
+        Token::Or => "OR",
+        Token::With => "WITH",
+        Token::Id(id) => id,
+    }
+}
+
+fn parse_or(tokens: &[Token], position: &mut usize) -> Result<LicenseExpr> {
+    let mut terms = vec![parse_and(tokens, position)?];
+    while tokens.get(*position) == Some(&Token::Or) {
+        *position += 1;
+        terms.push(parse_and(tokens, position)?);
+    }
+    Ok(if terms.len() == 1 {
+        terms.remove(0)
+    } else {
+        LicenseExpr::Or(terms)
+    })
+}
+
+fn parse_and(tokens: &[Token], position: &mut usize) -> Result<LicenseExpr> {
+    let mut terms = vec![parse_primary(tokens, position)?];
+    while tokens.get(*position) == Some(&Token::And) {
+        *position += 1;
+        terms.push(parse_primary(tokens, position)?);
+    }
+    Ok(if terms.len() == 1 {
+        terms.remove(0)
+    } else {
+        LicenseExpr::And(terms)
+    })
+}
+
+fn parse_primary(tokens: &[Token], position: &mut usize) -> Result<LicenseExpr> {
+    match tokens.get(*position) {
+        Some(Token::Open) => {
+            *position += 1;
+            let expr = parse_or(tokens, position)?;
+            if tokens.get(*position) != Some(&Token::Close) {
+                return Err(anyhow!("missing ')'"));
+            }
+            *position += 1;
+            Ok(expr)
+        }
+        Some(Token::Id(id)) => {
+            *position += 1;
+            let exception = if tokens.get(*position) == Some(&Token::With) {
+                *position += 1;
+                match tokens.get(*position) {
+                    Some(Token::Id(exception)) => {
+                        *position += 1;
+                        Some(exception.clone())
+                    }
+                    _ => return Err(anyhow!("WITH must be followed by an exception")),
+                }
+            } else {
+                None
+            };
+            Ok(LicenseExpr::License(LicenseReq {
+                id: id.clone(),
+                exception,
+            }))
+        }
+        Some(token) => Err(anyhow!("unexpected '{}'", token_text(token))),
+        None => Err(anyhow!("unexpected end of expression")),
+    }
+}
+
+/// Licenses one crate may use on top of the allow list
+#[derive(Debug, Clone)]
+pub struct LicenseException {
+    pub name: String,
+    pub version: Option<VersionReq>,
+    pub allow: Vec<LicenseReq>,
+}
+
+/// Expression to use for a crate whose metadata is missing or wrong
+#[derive(Debug, Clone)]
+pub struct LicenseClarification {
+    pub name: String,
+    pub version: Option<VersionReq>,
+    pub expression: LicenseExpr,
+}
+
+#[derive(Deserialize, Default)]
+struct RawDenyToml {
+    #[serde(default)]
+    licenses: RawLicenses,
+}
+
+#[derive(Deserialize, Default)]
+struct RawLicenses {
+    #[serde(default)]
+    allow: Vec<String>,
+    #[serde(default)]
+    deny: Vec<String>,
+    #[serde(default)]
+    exceptions: Vec<RawCrateEntry>,
+    #[serde(default)]
+    clarify: Vec<RawCrateEntry>,
+}
+
+/// `exceptions` and `clarify` entries (`crate` may be `name@version`)
+#[derive(Deserialize)]
+struct RawCrateEntry {
+    #[serde(rename = "crate", alias = "name")]
+    krate: String,
+    version: Option<String>,
+    #[serde(default)]
+    allow: Vec<String>,
+    expression: Option<String>,
+}
+
+impl RawCrateEntry {
+    fn name_and_version(&self) -> Result<(String, Option<VersionReq>)> {
+        let (name, version) = match self.krate.split_once('@') {
+            Some((name, version)) => (name, Some(format!("={}", version))),
+            None => (self.krate.as_str(), self.version.clone()),
+        };
+        let version = version
+            .map(|v| {
+                VersionReq::parse(&v)
+                    .with_context(|| format!("Invalid version for {}: {}", name, v))
+            })
+            .transpose()?;
+        Ok((name.to_string(), version))
+    }
+}
+
+fn matches_crate(name: &str, version: &Option<VersionReq>, package: &Package) -> bool {
+    name == package.name
+        && version
+            .as_ref()
+            .is_none_or(|req| req.matches(&package.version))
+}
+
+/// The `[licenses]` section of deny.toml
+#[derive(Debug, Clone, Default)]
+pub struct LicensePolicy {
+    pub allow: Vec<LicenseReq>,
+    pub deny: Vec<LicenseReq>,
+    pub exceptions: Vec<LicenseException>,
+    pub clarify: Vec<LicenseClarification>,
+}
+
+impl LicensePolicy {
+    pub fn parse(content: &str) -> Result<Self> {
+        let raw: RawDenyToml = toml::from_str(content).context("Failed to parse deny.toml")?;
+        let list = |entries: &[String]| {
+            entries
+                .iter()
+                .map(|entry| LicenseReq::parse(entry))
+                .collect::<Result<Vec<_>>>()
+        };
+
+        let mut exceptions = Vec::new();
+        for entry in &raw.licenses.exceptions {
+            let (name, version) = entry.name_and_version()?;
+            exceptions.push(LicenseException {
+                name,
+                version,
+                allow: list(&entry.allow)?,
+            });
+        }
+        let mut clarify = Vec::new();
+        for entry in &raw.licenses.clarify {
+            let (name, version) = entry.name_and_version()?;
+            let expression = entry
+                .expression
+                .as_deref()
+                .ok_or_else(|| anyhow!("Clarification for {} has no expression", name))?;
+            clarify.push(LicenseClarification {
+                name,
+                version,
+                expression: LicenseExpr::parse(expression)?,
+            });
+        }
+
+        Ok(Self {
+            allow: list(&raw.licenses.allow)?,
+            deny: list(&raw.licenses.deny)?,
+            exceptions,
+            clarify,
+        })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid license policy: {}", path.display()))
+    }
+
+    fn exception_allows(&self, package: &Package, req: &LicenseReq) -> bool {
+        self.exceptions.iter().any(|exception| {
+            matches_crate(&exception.name, &exception.version, package)
+                && exception.allow.contains(req)
+        })
+    }
+
+    /// Whether the policy accepts one license for a package
+    pub fn accepts(&self, package: &Package, req: &LicenseReq) -> bool {
+        !self.deny.contains(req)
+            && (self.allow.contains(req) || self.exception_allows(package, req))
+    }
+
+    /// Clarified expression for a package, if deny.toml has one
+    pub fn clarification(&self, package: &Package) -> Option<&LicenseExpr> {
+        self.clarify
+            .iter()
+            .find(|c| matches_crate(&c.name, &c.version, package))
+            .map(|c| &c.expression)
+    }
+
+    /// Decide a package's expression: allowed with the licenses that satisfy it, or why not
+    pub fn evaluate(&self, package: &Package, expr: &LicenseExpr) -> (LicenseStatus, String) {
+        let licenses = expr.licenses();
+        if expr.evaluate(|req| self.accepts(package, req)) {
+            let accepted: Vec<String> = licenses
+                .iter()
+                .filter(|req| self.accepts(package, req))
+                .map(|req| {
+                    if self.allow.contains(req) {
+                        req.to_string()
+                    } else {
+                        format!("{} (exception)", req)
+                    }
+                })
+                .collect();
+            return (LicenseStatus::Allowed, accepted.join(", "));
+        }
+
+        let denied: Vec<String> = licenses
+            .iter()
+            .filter(|req| self.deny.contains(req))
+            .map(|req| req.to_string())
+            .collect();
+        if !denied.is_empty() {
+            return (
+                LicenseStatus::Rejected,
+                format!("denied: {}", denied.join(", ")),
+            );
+        }
+        let missing: Vec<String> = licenses
+            .iter()
+            .filter(|req| !self.accepts(package, req))
+            .map(|req| req.to_string())
+            .collect();
+        (
+            LicenseStatus::Rejected,
+            format!("not allowed: {}", missing.join(", ")),
+        )
+    }
+
+    /// Check every locked package
+    pub fn check(&self, graph: &DependencyGraph, sources: &LicenseSources) -> LicenseReport {
+        let mut crates: Vec<CrateLicense> = graph
+            .packages()
+            .iter()
+            .map(|package| self.check_package(package, sources))
+            .collect();
+        crates.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.version.cmp(&b.version)));
+        LicenseReport { crates }
+    }
+
+    fn check_package(&self, package: &Package, sources: &LicenseSources) -> CrateLicense {
+        let entry = |license: Option<String>, status, reason: String| CrateLicense {
+            name: package.name.clone(),
+            version: package.version.to_string(),
+            license,
+            status,
+            reason,
+        };
+
+        if let Some(expr) = self.clarification(package) {
+            let (status, reason) = self.evaluate(package, expr);
+            return entry(
+                Some(expr.to_string()),
+                status,
+                format!("{} (clarified in deny.toml)", reason),
+            );
+        }
+        match sources.license_of(package) {
+            LicenseField::Expression(text) => match LicenseExpr::parse(&text) {
+                Ok(expr) => {
+                    let (status, reason) = self.evaluate(package, &expr);
+                    entry(Some(text), status, reason)
+                }
+                Err(e) => entry(Some(text), LicenseStatus::Rejected, e.to_string()),
+            },
+            LicenseField::File(file) => entry(
+                None,
+                LicenseStatus::Rejected,
+                format!(
+                    "no SPDX expression, only license-file = \"{}\" (add a [[licenses.clarify]] entry)",
+                    file
+                ),
+            ),
+            LicenseField::Missing => entry(
+                None,
+                LicenseStatus::Rejected,
+                "no license in Cargo.toml".to_string(),
+            ),
+            LicenseField::NotFound => entry(
+                None,
+                LicenseStatus::Unknown,
+                "Cargo.toml not found in the local registry cache".to_string(),
+            ),
+        }
+    }
+}
+
+/// License metadata found for a package
+#[derive(Debug, Clone, PartialEq)]
+pub enum LicenseField {
+    /// `license = "..."`
+    Expression(String),
+    /// Only `license-file = "..."`
+    File(String),
+    /// Manifest found, but neither field is set
+    Missing,
+    /// No manifest for the package was found
+    NotFound,
+}
+
+/// Where package manifests are looked up
+#[derive(Debug, Clone, Default)]
+pub struct LicenseSources {
+    registry_dirs: Vec<PathBuf>,
+    manifests: Vec<(PathBuf, toml::Value)>,
+}
+
+impl LicenseSources {
+    /// Workspace manifests of the project and the Cargo home source cache
+    pub fn new<P: AsRef<Path>>(project_root: P) -> Result<Self> {
+        let mut registry_dirs = Vec::new();
+        if let Ok(cargo_home) = home::cargo_home() {
+            let src = cargo_home.join("registry").join("src");
+            if let Ok(entries) = std::fs::read_dir(&src) {
+                registry_dirs = entries
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.path())
+                    .filter(|p| p.is_dir())
+                    .collect();
+                registry_dirs.sort();
+            }
+        }
+        Ok(Self {
+            registry_dirs,
+            manifests: workspace_manifests(project_root)?,
+        })
+    }
+
+    /// Look up registry crates in these directories (each holds `<name>-<version>/`)
+    pub fn registry_dirs(mut self, dirs: Vec<PathBuf>) -> Self {
+        self.registry_dirs = dirs;
+        self
+    }
+
+    /// Workspace manifests, the root manifest first
+    pub fn manifests(&self) -> &[(PathBuf, toml::Value)] {
+        &self.manifests
+    }
+
+    /// Manifest of a path package from the workspace, or of a registry crate from the cache
+    pub fn manifest_of(&self, package: &Package) -> Option<toml::Value> {
+        if package.source.is_path() {
+            return self
+                .manifests
+                .iter()
+                .find(|(_, manifest)| {
+                    manifest
+                        .get("package")
+                        .and_then(|p| p.get("name"))
+                        .and_then(|n| n.as_str())
+                        == Some(package.name.as_str())
+                })
+                .map(|(_, manifest)| manifest.clone());
+        }
+
+        let dir_name = format!("{}-{}", package.name, package.version);
+        self.registry_dirs.iter().find_map(|dir| {
+            let path = dir.join(&dir_name).join("Cargo.toml");
+            std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| toml::from_str::<toml::Value>(&content).ok())
+        })
+    }
+
+    /// License field of a path package from the workspace, or of a registry crate from the cache
+    pub fn license_of(&self, package: &Package) -> LicenseField {
+        match self.manifest_of(package) {
+            Some(manifest) => self.manifest_license(&manifest),
+            None => LicenseField::NotFound,
+        }
+    }
+
+    /// `license`, then `license-file`, following `workspace = true` to the root manifest
+    fn manifest_license(&self, manifest: &toml::Value) -> LicenseField {
+        let package = manifest.get("package");
+        let field = |key: &str| -> Option<String> {
+            let value = package.and_then(|p| p.get(key))?;
+            if let Some(text) = value.as_str() {
+                return Some(text.to_string());
+            }
+            // `license.workspace = true` inherits from `[workspace.package]`
+            self.manifests.first().and_then(|(_, root)| {
+                root.get("workspace")
+                    .and_then(|w| w.get("package"))
+                    .and_then(|p| p.get(key))
+                    .and_then(|v| v.as_str())
+                    .map(String::from)
+            })
+        };
+        if let Some(license) = field("license") {
+            LicenseField::Expression(license)
+        } else if let Some(file) = field("license-file") {
+            LicenseField::File(file)
+        } else {
+            LicenseField::Missing
+        }
+    }
+}
+
+/// Outcome of the license check for one crate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LicenseStatus {
+    Allowed,
+    Rejected,
+    Unknown,
+}
+
+impl fmt::Display for LicenseStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LicenseStatus::Allowed => "pass",
+            LicenseStatus::Rejected => "FAIL",
+            LicenseStatus::Unknown => "unknown",
+        };
+        f.write_str(name)
+    }
+}
+
+/// License check result for one locked crate
+#[derive(Debug, Clone, Serialize)]
+pub struct CrateLicense {
+    pub name: String,
+    pub version: String,
+    pub license: Option<String>,
+    pub status: LicenseStatus,
+    pub reason: String,
+}
+
+/// License check results for every locked crate
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LicenseReport {
+    pub crates: Vec<CrateLicense>,
+}
+
+impl LicenseReport {
+    pub fn rejected(&self) -> Vec<&CrateLicense> {
+        self.with_status(LicenseStatus::Rejected)
+    }
+
+    pub fn unknown(&self) -> Vec<&CrateLicense> {
+        self.with_status(LicenseStatus::Unknown)
+    }
+
+    pub fn with_status(&self, status: LicenseStatus) -> Vec<&CrateLicense> {
+        self.crates.iter().filter(|c| c.status == status).collect()
+    }
+
+    /// No crate was rejected (unknown crates are allowed)
+    pub fn is_ok(&self) -> bool {
+        self.rejected().is_empty()
+    }
+
+    /// Crates that fail the check: rejected ones, plus unknown ones with `deny_unknown`
+    pub fn failures(&self, deny_unknown: bool) -> Vec<&CrateLicense> {
+        self.crates
+            .iter()
+            .filter(|c| {
+                c.status == LicenseStatus::Rejected
+                    || (deny_unknown && c.status == LicenseStatus::Unknown)
+            })
+            .collect()
+    }
+
+    /// Table of the given crates (every crate when `failures_only` is false)
+    pub fn table(&self, failures_only: bool) -> Table {
+        let mut table = Table::new(vec![
+            Column::new("Crate"),
+            Column::new("Version"),
+            Column::new("License").wrap(),
+            Column::new("Result"),
+            Column::new("Reason").wrap(),
+        ]);
+        for entry in &self.crates {
+            if failures_only && entry.status != LicenseStatus::Rejected {
+                continue;
+            }
+            table.add_row(vec![
+                entry.name.clone(),
+                entry.version.clone(),
+                entry.license.clone().unwrap_or_else(|| "-".to_string()),
+                entry.status.to_string(),
+                entry.reason.clone(),
+            ]);
+        }
+        table
+    }
+}
@@ -0,0 +1,735 @@
+// Cargo.lock dependency graph for Ubuntu Config Scripts
+//
+// This module reads Cargo.lock and the root manifest without invoking cargo,
+// including:
+// - Packages with their versions, sources and checksums
+// - Resolved dependency edges (`name`, `name version` and
+//   `name version (source)` references)
+// - Roots taken from the manifest's package or workspace members
+//   (including `members` globs and `exclude`)
+// - Queries for duplicate versions, reverse dependencies ("why is X here")
+//   and depth from the roots
+// - Text tree, DOT and JSON output
+
+use crate::lib::schema::suggest;
+use anyhow::{anyhow, Context, Result};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Lockfile name, relative to the project root
+pub const LOCKFILE: &str = "Cargo.lock";
+
+/// Manifest name, relative to the project root
+pub const MANIFEST: &str = "Cargo.toml";
+
+/// Source of packages published on crates.io
+pub const CRATES_IO_SOURCE: &str = "registry+https://github.com/rust-lang/crates.io-index";
+
+/// Where a locked package comes from
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PackageSource {
+    /// Workspace member or path dependency (no `source` in Cargo.lock)
+    Path,
+    /// `registry+<url>` or `sparse+<url>`
+    Registry(String),
+    /// `git+<url>#<commit>`
+    Git { url: String, rev: Option<String> },
+    /// Any other source string, kept verbatim
+    Other(String),
+}
+
+impl PackageSource {
+    /// Parse the `source` field of a Cargo.lock package
+    pub fn parse(source: Option<&str>) -> Self {
+        let Some(source) = source else {
+            return PackageSource::Path;
+        };
+        if source.starts_with("registry+") || source.starts_with("sparse+") {
+            PackageSource::Registry(source.to_string())
+        } else if let Some(rest) = source.strip_prefix("git+") {
+            match rest.split_once('#') {
+                Some((url, rev)) => PackageSource::Git {
+                    url: url.to_string(),
+                    rev: Some(rev.to_string()),
+                },
+                None => PackageSource::Git {
+                    url: rest.to_string(),
+                    rev: None,
+                },
+            }
+        } else {
+            PackageSource::Other(source.to_string())
+        }
+    }
+
+    pub fn is_path(&self) -> bool {
+        matches!(self, PackageSource::Path)
+    }
+
+    pub fn is_crates_io(&self) -> bool {
+        matches!(self, PackageSource::Registry(url) if url == CRATES_IO_SOURCE)
+    }
+
+    /// Short form shown after the version (`cargo tree` style); empty for crates.io
+    pub fn label(&self) -> String {
+        match self {
+            PackageSource::Path => "(path)".to_string(),
+            PackageSource::Registry(_) if self.is_crates_io() => String::new(),
+            PackageSource::Registry(url) => format!("({})", url),
+            PackageSource::Git {
+                url,
+                rev: Some(rev),
+            } => format!("({}#{})", url, rev),
+            PackageSource::Git { url, rev: None } => format!("({})", url),
+            PackageSource::Other(source) => format!("({})", source),
+        }
+    }
+}
+
+/// Cargo.lock form of the source (`path` for packages without one)
+impl fmt::Display for PackageSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PackageSource::Path => f.write_str("path"),
+            PackageSource::Registry(source) | PackageSource::Other(source) => f.write_str(source),
+            PackageSource::Git {
+                url,
+                rev: Some(rev),
+            } => write!(f, "git+{}#{}", url, rev),
+            PackageSource::Git { url, rev: None } => write!(f, "git+{}", url),
+        }
+    }
+}
+
+impl Serialize for PackageSource {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// A locked package; `dependencies` are indexes into the graph
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Package {
+    pub name: String,
+    pub version: Version,
+    pub source: PackageSource,
+    pub checksum: Option<String>,
+    pub dependencies: Vec<usize>,
+}
+
+impl Package {
+    /// `name vX.Y.Z` followed by the source label when it is not crates.io
+    pub fn display_name(&self) -> String {
+        let label = self.source.label();
+        if label.is_empty() {
+            format!("{} v{}", self.name, self.version)
+        } else {
+            format!("{} v{} {}", self.name, self.version, label)
+        }
+    }
+}
+
+/// Output format for the whole graph
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum GraphFormat {
+    #[default]
+    Tree,
+    Dot,
+    Json,
+}
+
+impl GraphFormat {
+    pub const NAMES: [&'static str; 3] = ["tree", "dot", "json"];
+}
+
+impl FromStr for GraphFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "tree" | "text" => Ok(GraphFormat::Tree),
+            "dot" | "graphviz" => Ok(GraphFormat::Dot),
+            "json" => Ok(GraphFormat::Json),
+            _ => Err(anyhow!(
+                "Invalid graph format: {} (expected one of: {})",
+                s,
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for GraphFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            GraphFormat::Tree => "tree",
+            GraphFormat::Dot => "dot",
+            GraphFormat::Json => "json",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Deserialize)]
+struct RawLockfile {
+    #[serde(default)]
+    package: Vec<RawPackage>,
+}
+
+#[derive(Deserialize)]
+struct RawPackage {
+    name: String,
+    version: String,
+    source: Option<String>,
+    checksum: Option<String>,
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+/// Packages from Cargo.lock and the edges between them
+#[derive(Debug, Clone, Default)]
+pub struct DependencyGraph {
+    packages: Vec<Package>,
+    roots: Vec<usize>,
+}
+
+impl DependencyGraph {
+    /// Parse the contents of a Cargo.lock (format versions 1 to 4)
+    ///
+    /// Roots are the path packages nothing else depends on; `load` replaces
+    /// them with the manifest's package or workspace members.
+    pub fn parse(content: &str) -> Result<Self> {
+        let raw: RawLockfile = toml::from_str(content).context("Failed to parse Cargo.lock")?;
+
+        let mut packages = Vec::with_capacity(raw.package.len());
+        for package in &raw.package {
+            let version = Version::parse(&package.version).with_context(|| {
+                format!("Invalid version for {}: {}", package.name, package.version)
+            })?;
+            packages.push(Package {
+                name: package.name.clone(),
+                version,
+                source: PackageSource::parse(package.source.as_deref()),
+                checksum: package.checksum.clone(),
+                dependencies: Vec::new(),
+            });
+        }
+
+        let mut by_name: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, package) in raw.package.iter().enumerate() {
+            by_name.entry(&package.name).or_default().push(index);
+        }
+
+        let mut edges = Vec::with_capacity(packages.len());
+        for package in &raw.package {
+            let mut dependencies = package
+                .dependencies
+                .iter()
+                .map(|reference| {
+                    resolve_reference(reference, &packages, &by_name).with_context(|| {
+                        format!(
+                            "Cannot resolve dependency '{}' of {} {}",
+                            reference, package.name, package.version
+                        )
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            dependencies.sort_by(|&a, &b| compare(&packages[a], &packages[b]));
+            dependencies.dedup();
+            edges.push(dependencies);
+        }
+        for (package, dependencies) in packages.iter_mut().zip(edges) {
+            package.dependencies = dependencies;
+        }
+
+        let depended_on: BTreeSet<usize> = packages
+            .iter()
+            .flat_map(|p| p.dependencies.iter().copied())
+            .collect();
+        let mut roots: Vec<usize> = (0..packages.len())
+            .filter(|i| packages[*i].source.is_path() && !depended_on.contains(i))
+            .collect();
+        roots.sort_by(|&a, &b| compare(&packages[a], &packages[b]));
+
+        Ok(Self { packages, roots })
+    }
+
+    /// Read a Cargo.lock file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&content)
+    }
+
+    /// Read `Cargo.lock` in a project and take the roots from its `Cargo.toml`
+    pub fn load<P: AsRef<Path>>(project_root: P) -> Result<Self> {
+        let project_root = project_root.as_ref();
+        let mut graph = Self::from_file(project_root.join(LOCKFILE))?;
+        let names = manifest_roots(project_root)?;
+        if !names.is_empty() {
+            let roots: Vec<usize> = names
+                .iter()
+                .filter_map(|name| graph.local_package(name))
+                .collect();
+            if !roots.is_empty() {
+                graph.roots = roots;
+            }
+        }
+        Ok(graph)
+    }
+
+    pub fn packages(&self) -> &[Package] {
+        &self.packages
+    }
+
+    pub fn package(&self, index: usize) -> &Package {
+        &self.packages[index]
+    }
+
+    /// Indexes of the root packages (workspace members)
+    pub fn roots(&self) -> &[usize] {
+        &self.roots
+    }
+
+    fn local_package(&self, name: &str) -> Option<usize> {
+        self.packages
+            .iter()
+            .position(|p| p.name == name && p.source.is_path())
+    }
+
+    /// Every locked version of a package, oldest first
+    pub fn find(&self, name: &str) -> Vec<usize> {
+        let mut found: Vec<usize> = (0..self.packages.len())
+            .filter(|&i| self.packages[i].name == name)
+            .collect();
+        found.sort_by(|&a, &b| compare(&self.packages[a], &self.packages[b]));
+        found
+    }
+
+    /// Like `find`, but an unknown name is an error with a suggestion
+    pub fn require(&self, name: &str) -> Result<Vec<usize>> {
+        let found = self.find(name);
+        if !found.is_empty() {
+            return Ok(found);
+        }
+        let names: BTreeSet<&str> = self.packages.iter().map(|p| p.name.as_str()).collect();
+        let names: Vec<&str> = names.into_iter().collect();
+        Err(match suggest(name, &names) {
+            Some(suggestion) => anyhow!(
+                "Package not found in Cargo.lock: {} (did you mean {}?)",
+                name,
+                suggestion
+            ),
+            None => anyhow!("Package not found in Cargo.lock: {}", name),
+        })
+    }
+
+    /// Packages locked at more than one version, with those versions oldest first
+    pub fn duplicates(&self) -> BTreeMap<&str, Vec<&Package>> {
+        let mut by_name: BTreeMap<&str, Vec<&Package>> = BTreeMap::new();
+        for package in &self.packages {
+            by_name.entry(&package.name).or_default().push(package);
+        }
+        by_name.retain(|_, versions| versions.len() > 1);
+        for versions in by_name.values_mut() {
+            versions.sort_by(|a, b| compare(a, b));
+        }
+        by_name
+    }
+
+    /// Packages that depend directly on `index`
+    pub fn dependents(&self, index: usize) -> Vec<usize> {
+        let mut dependents: Vec<usize> = (0..self.packages.len())
+            .filter(|&i| self.packages[i].dependencies.contains(&index))
+            .collect();
+        dependents.sort_by(|&a, &b| compare(&self.packages[a], &self.packages[b]));
+        dependents
+    }
+
+    /// Shortest distance of every package from a root (`None` when unreachable)
+    pub fn depths(&self) -> Vec<Option<usize>> {
+        let mut depths = vec![None; self.packages.len()];
+        let mut queue = VecDeque::new();
+        for &root in &self.roots {
+            depths[root] = Some(0);
+            queue.push_back(root);
+        }
+        while let Some(index) = queue.pop_front() {
+            let depth = depths[index].unwrap_or_default();
+            for &dependency in &self.packages[index].dependencies {
+                if depths[dependency].is_none() {
+                    depths[dependency] = Some(depth + 1);
+                    queue.push_back(dependency);
+                }
+            }
+        }
+        depths
+    }
+
+    /// Shortest distance of one package from a root
+    pub fn depth(&self, index: usize) -> Option<usize> {
+        self.depths()[index]
+    }
+
+    /// Shortest chain from a root to `index`, root first
+    pub fn path_to(&self, index: usize) -> Option<Vec<usize>> {
+        let mut previous: Vec<Option<usize>> = vec![None; self.packages.len()];
+        let mut seen = vec![false; self.packages.len()];
+        let mut queue = VecDeque::new();
+        for &root in &self.roots {
+            seen[root] = true;
+            queue.push_back(root);
+        }
+        while let Some(current) = queue.pop_front() {
+            if current == index {
+                let mut path = vec![current];
+                let mut node = current;
+                while let Some(parent) = previous[node] {
+                    path.push(parent);
+                    node = parent;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for &dependency in &self.packages[current].dependencies {
+                if !seen[dependency] {
+                    seen[dependency] = true;
+                    previous[dependency] = Some(current);
+                    queue.push_back(dependency);
+                }
+            }
+        }
+        None
+    }
+
+    /// Why a package is in the lockfile: one shortest chain per locked version
+    pub fn why(&self, name: &str) -> Result<Vec<Vec<usize>>> {
+        Ok(self
+            .require(name)?
+            .into_iter()
+            .map(|index| self.path_to(index).unwrap_or_else(|| vec![index]))
+            .collect())
+    }
+
+    /// `cargo tree` style listing from every root; repeated subtrees end in `(*)`
+    pub fn tree(&self, max_depth: Option<usize>) -> String {
+        let mut writer = TreeWriter {
+            graph: self,
+            max_depth,
+            expanded: BTreeSet::new(),
+            output: String::new(),
+        };
+        for (n, &root) in self.roots.iter().enumerate() {
+            if n > 0 {
+                writer.output.push('\n');
+            }
+            writer.output.push_str(&self.packages[root].display_name());
+            writer.output.push('\n');
+            writer.expanded.insert(root);
+            writer.children(root, "", 1);
+        }
+        writer.output
+    }
+
+    /// Graphviz digraph; roots are drawn as boxes and duplicates in red
+    pub fn to_dot(&self) -> String {
+        let duplicates = self.duplicates();
+        let mut dot = String::from("digraph dependencies {\n    node [shape=ellipse];\n");
+        for (index, package) in self.packages.iter().enumerate() {
+            let mut attributes = vec![format!(
+                "label=\"{} {}\"",
+                dot_escape(&package.name),
+                package.version
+            )];
+            if self.roots.contains(&index) {
+                attributes.push("shape=box".to_string());
+            }
+            if duplicates.contains_key(package.name.as_str()) {
+                attributes.push("color=red".to_string());
+            }
+            dot.push_str(&format!("    n{} [{}];\n", index, attributes.join(", ")));
+        }
+        for (index, package) in self.packages.iter().enumerate() {
+            for dependency in &package.dependencies {
+                dot.push_str(&format!("    n{} -> n{};\n", index, dependency));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Cargo.lock style id: `name version`, plus `(source)` when that is ambiguous
+    pub fn id(&self, index: usize) -> String {
+        let package = &self.packages[index];
+        let ambiguous = self
+            .packages
+            .iter()
+            .filter(|p| p.name == package.name && p.version == package.version)
+            .count()
+            > 1;
+        if ambiguous {
+            format!("{} {} ({})", package.name, package.version, package.source)
+        } else {
+            format!("{} {}", package.name, package.version)
+        }
+    }
+
+    /// Packages with depth and dependencies given as ids
+    pub fn to_json(&self) -> Value {
+        let depths = self.depths();
+        let id = |index: usize| self.id(index);
+        let packages: Vec<Value> = self
+            .packages
+            .iter()
+            .enumerate()
+            .map(|(index, package)| {
+                json!({
+                    "id": id(index),
+                    "name": package.name,
+                    "version": package.version.to_string(),
+                    "source": package.source.to_string(),
+                    "checksum": package.checksum,
+                    "depth": depths[index],
+                    "dependencies": package.dependencies.iter().map(|&d| id(d)).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        json!({
+            "roots": self.roots.iter().map(|&r| id(r)).collect::<Vec<_>>(),
+            "packages": packages,
+        })
+    }
+
+    pub fn render(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Tree => self.tree(None),
+            GraphFormat::Dot => self.to_dot(),
+            GraphFormat::Json => {
+                let mut json = serde_json::to_string_pretty(&self.to_json()).unwrap_or_default();
+                json.push('\n');
+                json
+            }
+        }
+    }
+}
+
+/// State while printing `DependencyGraph::tree`
+struct TreeWriter<'a> {
+    graph: &'a DependencyGraph,
+    max_depth: Option<usize>,
+    expanded: BTreeSet<usize>,
+    output: String,
+}
+
+impl TreeWriter<'_> {
+    fn children(&mut self, index: usize, prefix: &str, depth: usize) {
+        if self.max_depth.is_some_and(|max| depth > max) {
+            return;
+        }
+        let dependencies = &self.graph.packages[index].dependencies;
+        for (n, &dependency) in dependencies.iter().enumerate() {
+            let last = n + 1 == dependencies.len();
+            let package = &self.graph.packages[dependency];
+            let repeated = !package.dependencies.is_empty() && self.expanded.contains(&dependency);
+            self.output.push_str(prefix);
+            self.output.push_str(if last { "└── " } else { "├── " });
+            self.output.push_str(&package.display_name());
+            if repeated {
+                self.output.push_str(" (*)");
+            }
+            self.output.push('\n');
+            if !repeated {
+                self.expanded.insert(dependency);
+                let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+                self.children(dependency, &child_prefix, depth + 1);
+            }
+        }
+    }
+}
+
+/// Name, then version, then source
+fn compare(a: &Package, b: &Package) -> std::cmp::Ordering {
+    a.name
+        .cmp(&b.name)
+        .then_with(|| a.version.cmp(&b.version))
+        .then_with(|| a.source.to_string().cmp(&b.source.to_string()))
+}
+
+/// Resolve a `dependencies` entry; the version and source only appear when the name is ambiguous
+fn resolve_reference(
+    reference: &str,
+    packages: &[Package],
+    by_name: &HashMap<&str, Vec<usize>>,
+) -> Result<usize> {
+    let mut parts = reference.splitn(3, ' ');
+    let name = parts.next().unwrap_or_default();
+    let version = parts.next();
+    let source = parts
+        .next()
+        .map(|s| s.trim_start_matches('(').trim_end_matches(')'));
+
+    let candidates: Vec<usize> = by_name
+        .get(name)
+        .into_iter()
+        .flatten()
+        .copied()
+        .filter(|&i| version.is_none_or(|v| packages[i].version.to_string() == v))
+        .filter(|&i| {
+            source.is_none_or(|s| {
+                PackageSource::parse(Some(s)) == packages[i].source
+                    || packages[i].source.to_string().starts_with(s)
+            })
+        })
+        .collect();
+    match candidates.as_slice() {
+        [index] => Ok(*index),
+        [] => Err(anyhow!("no locked package matches")),
+        _ => Err(anyhow!("{} locked packages match", candidates.len())),
+    }
+}
+
+/// The project manifest followed by its workspace members' manifests
+///
+/// Members may be globs (`crates/*`); paths listed in `workspace.exclude`
+/// and directories without a Cargo.toml are skipped.
+pub fn workspace_manifests<P: AsRef<Path>>(project_root: P) -> Result<Vec<(PathBuf, toml::Value)>> {
+    let project_root = project_root.as_ref();
+    let path = project_root.join(MANIFEST);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let manifest = read_manifest(&path)?;
+
+    let workspace = manifest.get("workspace");
+    let list = |key: &str| -> Vec<String> {
+        workspace
+            .and_then(|w| w.get(key))
+            .and_then(|m| m.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|m| m.as_str())
+            .map(|m| m.trim_end_matches('/').to_string())
+            .collect()
+    };
+    let excluded: Vec<PathBuf> = list("exclude")
+        .iter()
+        .map(|e| project_root.join(e))
+        .collect();
+    let mut member_dirs = Vec::new();
+    for member in list("members") {
+        member_dirs.extend(expand_member(project_root, &member));
+    }
+
+    let mut manifests = vec![(path, manifest)];
+    for dir in member_dirs {
+        if excluded.contains(&dir) || !dir.join(MANIFEST).is_file() {
+            continue;
+        }
+        for entry in workspace_manifests(&dir)? {
+            if !manifests.iter().any(|(path, _)| *path == entry.0) {
+                manifests.push(entry);
+            }
+        }
+    }
+    Ok(manifests)
+}
+
+/// Directories below the project root holding a separate workspace
+///
+/// These are crates whose manifest has its own `[workspace]` table, so the
+/// root workspace does not see them (e.g. a crate with its own Cargo.lock).
+/// `target` and hidden directories are not searched.
+pub fn nested_workspaces<P: AsRef<Path>>(project_root: P) -> Result<Vec<PathBuf>> {
+    let project_root = project_root.as_ref();
+    let mut found = Vec::new();
+    let walker = walkdir::WalkDir::new(project_root)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            entry.depth() == 0
+                || !(entry.file_type().is_dir() && (name == "target" || name.starts_with('.')))
+        });
+    for entry in walker.filter_map(|e| e.ok()) {
+        if entry.file_name() != MANIFEST || entry.depth() < 2 {
+            continue;
+        }
+        let manifest = read_manifest(entry.path())?;
+        if manifest.get("workspace").is_some() {
+            if let Some(dir) = entry.path().parent() {
+                found.push(dir.to_path_buf());
+            }
+        }
+    }
+    Ok(found)
+}
+
+fn read_manifest(path: &Path) -> Result<toml::Value> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Directories a `members` entry names, expanding `*` and `?` per path component
+fn expand_member(project_root: &Path, member: &str) -> Vec<PathBuf> {
+    let mut dirs = vec![project_root.to_path_buf()];
+    for component in member.split('/').filter(|c| !c.is_empty() && *c != ".") {
+        if !component.contains(['*', '?']) {
+            dirs = dirs.into_iter().map(|dir| dir.join(component)).collect();
+            continue;
+        }
+        let pattern = component
+            .split('*')
+            .map(|part| {
+                part.split('?')
+                    .map(regex::escape)
+                    .collect::<Vec<_>>()
+                    .join(".")
+            })
+            .collect::<Vec<_>>()
+            .join(".*");
+        let Ok(re) = regex::Regex::new(&format!("^{}$", pattern)) else {
+            return Vec::new();
+        };
+        let mut matched = Vec::new();
+        for dir in &dirs {
+            let Ok(entries) = std::fs::read_dir(dir) else {
+                continue;
+            };
+            let mut children: Vec<PathBuf> = entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().is_dir() && re.is_match(&e.file_name().to_string_lossy()))
+                .map(|e| e.path())
+                .collect();
+            children.sort();
+            matched.extend(children);
+        }
+        dirs = matched;
+    }
+    dirs
+}
+
+/// Package names of the workspace manifests
+fn manifest_roots(project_root: &Path) -> Result<Vec<String>> {
+    Ok(workspace_manifests(project_root)?
+        .iter()
+        .filter_map(|(_, manifest)| {
+            manifest
+                .get("package")
+                .and_then(|p| p.get("name"))
+                .and_then(|n| n.as_str())
+                .map(String::from)
+        })
+        .collect())
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
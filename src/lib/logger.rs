@@ -3,14 +3,21 @@
 // This module provides comprehensive logging functionality including:
 // - Structured logging with context
 // - Multiple log levels
-// - Component-based logging
+// - Component-based logging with per-component filters
+// - Runtime reconfiguration of the active filter
+// - Secret redaction for every record
+// - Span-tagged records from nested contexts
 // - Performance monitoring
 
-use anyhow::Result;
+use crate::lib::progress::ProgressBar;
+use crate::lib::redact::{redact, redact_command, redactor};
+use crate::lib::render::{OutputFormat, Table};
+use crate::lib::trace::{self, Span};
+use anyhow::{anyhow, Result};
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::RwLock;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{OnceLock, RwLock};
 use std::time::Instant;
 
 /// Log levels for different types of messages
@@ -23,17 +30,22 @@ pub enum LogLevel {
 }
 
 /// Performance timer for measuring execution time
+///
+/// The timed operation is a span, so it shows up in exported traces.
 pub struct PerformanceTimer {
     start: Instant,
     operation: String,
+    _span: Span,
 }
 
 impl PerformanceTimer {
     pub fn new(operation: &str) -> Self {
+        let span = Span::enter(operation);
         info!("⏱️  Starting: {}", operation);
         Self {
             start: Instant::now(),
             operation: operation.to_string(),
+            _span: span,
         }
     }
 
@@ -52,28 +64,31 @@ impl PerformanceTimer {
 }
 
 /// Log debug message with component context
+///
+/// The component is used as the log target, so it can be filtered on its own
+/// (e.g. `UCS_LOG=info,DEPS=debug`).
 pub fn log_debug(message: &str, component: &str) {
-    debug!("[{}] {}", component, message);
+    debug!(target: component, "{}", message);
 }
 
 /// Log info message with component context
 pub fn log_info(message: &str, component: &str) {
-    info!("[{}] {}", component, message);
+    info!(target: component, "{}", message);
 }
 
 /// Log warning message with component context
 pub fn log_warn(message: &str, component: &str) {
-    warn!("[{}] {}", component, message);
+    warn!(target: component, "{}", message);
 }
 
 /// Log error message with component context
 pub fn log_error(message: &str, component: &str) {
-    error!("[{}] {}", component, message);
+    error!(target: component, "{}", message);
 }
 
 /// Log command execution
 pub fn log_command(cmd: &[&str], component: &str) {
-    log_debug(&format!("Executing: {}", cmd.join(" ")), component);
+    log_debug(&format!("Executing: {}", redact_command(cmd)), component);
 }
 
 /// Log file operation
@@ -98,7 +113,7 @@ pub fn log_script_complete(script_name: &str) {
 
 /// Log success message with component context
 pub fn log_success(message: &str, component: &str) {
-    info!("✅ [{}] {}", component, message);
+    info!(target: component, "✅ {}", message);
 }
 
 /// Log script failure
@@ -106,27 +121,247 @@ pub fn log_script_error(script_name: &str, error: &str) {
     error!("❌ Script failed: {} - {}", script_name, error);
 }
 
+/// Environment variable holding the log filter spec
+pub const LOG_ENV: &str = "UCS_LOG";
+
+/// Per-component log filter, e.g. `info,DEPS=debug,AUDIO=trace`
+///
+/// A bare level sets the default; `NAME=level` sets the level for the
+/// component (log target) `NAME` and any `NAME::` sub-target.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFilter {
+    pub default: log::LevelFilter,
+    pub components: BTreeMap<String, log::LevelFilter>,
+}
+
+impl LogFilter {
+    pub fn new(default: log::LevelFilter) -> Self {
+        Self {
+            default,
+            components: BTreeMap::new(),
+        }
+    }
+
+    /// Parse a filter spec on top of an `info` default
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut filter = Self::new(log::LevelFilter::Info);
+        filter.apply(spec)?;
+        Ok(filter)
+    }
+
+    /// Apply the directives of `spec` on top of this filter
+    pub fn apply(&mut self, spec: &str) -> Result<()> {
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((name, level)) => {
+                    let name = name.trim();
+                    if name.is_empty() {
+                        return Err(anyhow!("Missing component name in '{}'", directive));
+                    }
+                    self.components
+                        .insert(name.to_string(), parse_level_filter(level)?);
+                }
+                None => match parse_level_filter(directive) {
+                    Ok(level) => self.default = level,
+                    // A bare name enables everything for that component
+                    Err(_) => {
+                        self.components
+                            .insert(directive.to_string(), log::LevelFilter::Trace);
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Effective level for a log target
+    pub fn level_for(&self, target: &str) -> log::LevelFilter {
+        self.components
+            .iter()
+            .filter(|(name, _)| {
+                target == name.as_str()
+                    || (target.starts_with(name.as_str()) && target[name.len()..].starts_with("::"))
+            })
+            .max_by_key(|(name, _)| name.len())
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default)
+    }
+
+    /// Most verbose level enabled for any target
+    pub fn max_level(&self) -> log::LevelFilter {
+        self.components
+            .values()
+            .copied()
+            .fold(self.default, std::cmp::max)
+    }
+
+    pub fn enabled(&self, target: &str, level: log::Level) -> bool {
+        level <= self.level_for(target)
+    }
+}
+
+impl std::str::FromStr for LogFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl std::fmt::Display for LogFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = vec![self.default.to_string().to_lowercase()];
+        for (name, level) in &self.components {
+            parts.push(format!("{}={}", name, level.to_string().to_lowercase()));
+        }
+        f.write_str(&parts.join(","))
+    }
+}
+
+/// Parse a level name (`off`, `error`, `warn`, `info`, `debug`, `trace`)
+pub fn parse_level_filter(level: &str) -> Result<log::LevelFilter> {
+    level
+        .trim()
+        .parse::<log::LevelFilter>()
+        .map_err(|_| anyhow!("Invalid log level: {}", level.trim()))
+}
+
+impl From<LogLevel> for log::LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Debug => log::LevelFilter::Debug,
+            LogLevel::Info => log::LevelFilter::Info,
+            LogLevel::Warn => log::LevelFilter::Warn,
+            LogLevel::Error => log::LevelFilter::Error,
+        }
+    }
+}
+
+/// Process-wide logger whose filter can be replaced at runtime
+struct ReloadableLogger {
+    filter: RwLock<LogFilter>,
+    writer: env_logger::Logger,
+}
+
+impl log::Log for ReloadableLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.filter
+            .read()
+            .map(|filter| filter.enabled(metadata.target(), metadata.level()))
+            .unwrap_or(true)
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        // Every record passes through redaction before reaching the writer
+        let message = redact(&record.args().to_string());
+        trace::record_log(record.level().as_str(), record.target(), &message);
+
+        // Records emitted inside a LogContext carry its span
+        let span = trace::current_span()
+            .map(|span| match span.parent {
+                Some(parent) => format!("[{} span={} parent={}] ", span.name, span.id, parent),
+                None => format!("[{} span={}] ", span.name, span.id),
+            })
+            .unwrap_or_default();
+
+        self.writer.log(
+            &log::Record::builder()
+                .args(format_args!("{}{}", span, message))
+                .metadata(record.metadata().clone())
+                .module_path(record.module_path())
+                .file(record.file())
+                .line(record.line())
+                .build(),
+        );
+    }
+
+    fn flush(&self) {
+        self.writer.flush();
+    }
+}
+
+static LOGGER: OnceLock<ReloadableLogger> = OnceLock::new();
+static INSTALLED: OnceLock<bool> = OnceLock::new();
+
+/// Install the logger with `default` as base level and `UCS_LOG` (or
+/// `RUST_LOG`) applied on top
+///
+/// Safe to call more than once: later calls leave the active filter alone
+/// and succeed. Fails only if a different logger was installed.
+pub fn try_init_logger(default: log::LevelFilter) -> Result<(), log::SetLoggerError> {
+    let mut filter = LogFilter::new(default);
+    let spec = std::env::var(LOG_ENV).or_else(|_| std::env::var("RUST_LOG"));
+    let spec_error = spec.ok().and_then(|spec| filter.apply(&spec).err());
+
+    let logger = LOGGER.get_or_init(|| ReloadableLogger {
+        filter: RwLock::new(filter.clone()),
+        writer: env_logger::Builder::new()
+            .filter_level(log::LevelFilter::Trace)
+            .build(),
+    });
+
+    let installed = *INSTALLED.get_or_init(|| {
+        if log::set_logger(logger).is_err() {
+            return false;
+        }
+        log::set_max_level(filter.max_level());
+        if let Some(e) = spec_error {
+            warn!("Ignoring invalid {} directive: {}", LOG_ENV, e);
+        }
+        true
+    });
+
+    if installed {
+        Ok(())
+    } else {
+        // Another logger owns the process; report it the way `log` does
+        log::set_logger(logger)
+    }
+}
+
 /// Initialize logging with appropriate level
 pub fn init_logger() -> Result<(), log::SetLoggerError> {
-    env_logger::Builder::from_default_env()
-        .filter_level(log::LevelFilter::Info)
-        .init();
-    Ok(())
+    try_init_logger(log::LevelFilter::Info)
 }
 
 /// Initialize logger with specific level
 pub fn init_logger_with_level(level: LogLevel) -> Result<(), log::SetLoggerError> {
-    let log_level = match level {
-        LogLevel::Debug => log::LevelFilter::Debug,
-        LogLevel::Info => log::LevelFilter::Info,
-        LogLevel::Warn => log::LevelFilter::Warn,
-        LogLevel::Error => log::LevelFilter::Error,
-    };
+    try_init_logger(level.into())
+}
+
+/// Replace the active filter, e.g. after a config reload
+pub fn set_log_filter(filter: LogFilter) {
+    log::set_max_level(filter.max_level());
+    if let Some(logger) = LOGGER.get() {
+        if let Ok(mut active) = logger.filter.write() {
+            *active = filter;
+        }
+    }
+}
 
-    env_logger::Builder::from_default_env()
-        .filter_level(log_level)
-        .init();
-    Ok(())
+/// Change the default level while keeping per-component directives
+pub fn set_log_level(level: log::LevelFilter) {
+    let mut filter = current_log_filter();
+    filter.default = level;
+    set_log_filter(filter);
+}
+
+/// Change the level of a single component
+pub fn set_component_level(component: &str, level: log::LevelFilter) {
+    let mut filter = current_log_filter();
+    filter.components.insert(component.to_string(), level);
+    set_log_filter(filter);
+}
+
+/// Filter currently in effect
+pub fn current_log_filter() -> LogFilter {
+    LOGGER
+        .get()
+        .and_then(|logger| logger.filter.read().ok().map(|f| f.clone()))
+        .unwrap_or_else(|| LogFilter::new(log::max_level()))
 }
 
 /// Structured log entry
@@ -145,85 +380,113 @@ impl LogEntry {
             timestamp: chrono::Local::now().to_rfc3339(),
             level: level.to_string(),
             component: component.to_string(),
-            message: message.to_string(),
+            message: redact(message),
             metadata: None,
         }
     }
 
+    /// Attach metadata; values of sensitive keys (e.g. `GH_RUNNER_TOKEN`) are masked
     pub fn with_metadata(mut self, metadata: HashMap<String, String>) -> Self {
-        self.metadata = Some(metadata);
+        self.metadata = Some(redactor().redact_env(&metadata));
         self
     }
 
     pub fn log(&self) {
+        let target = self.component.as_str();
         match self.level.as_str() {
-            "DEBUG" => debug!("[{}] {}", self.component, self.message),
-            "INFO" => info!("[{}] {}", self.component, self.message),
-            "WARN" => warn!("[{}] {}", self.component, self.message),
-            "ERROR" => error!("[{}] {}", self.component, self.message),
-            _ => info!("[{}] {}", self.component, self.message),
+            "DEBUG" => debug!(target: target, "{}", self.message),
+            "INFO" => info!(target: target, "{}", self.message),
+            "WARN" => warn!(target: target, "{}", self.message),
+            "ERROR" => error!(target: target, "{}", self.message),
+            _ => info!(target: target, "{}", self.message),
         }
     }
 }
 
 /// Progress tracker for long-running operations
+///
+/// Renders an interactive bar when stderr is a terminal and throttled log
+/// lines otherwise; see `progress` for nested and concurrent bars.
 pub struct ProgressTracker {
-    total: usize,
-    current: usize,
-    message: String,
-    start: Instant,
+    bar: ProgressBar,
 }
 
 impl ProgressTracker {
     pub fn new(total: usize, message: &str) -> Self {
-        info!("📊 Starting: {} (0/{})", message, total);
         Self {
-            total,
-            current: 0,
-            message: message.to_string(),
-            start: Instant::now(),
+            bar: ProgressBar::new(total as u64, message),
+        }
+    }
+
+    /// Track a byte count, e.g. a download or disk scan
+    pub fn bytes(total: u64, message: &str) -> Self {
+        Self {
+            bar: ProgressBar::bytes(total, message),
         }
     }
 
     pub fn update(&mut self, current: usize) {
-        self.current = current;
-        let percent = (current as f64 / self.total as f64 * 100.0) as u32;
-        info!(
-            "📊 Progress: {} ({}/{}) - {}%",
-            self.message, self.current, self.total, percent
-        );
+        self.bar.set_position(current as u64);
     }
 
     pub fn increment(&mut self) {
-        self.update(self.current + 1);
+        self.bar.inc(1);
+    }
+
+    /// Advance by an arbitrary amount (items or bytes)
+    pub fn advance(&mut self, delta: u64) {
+        self.bar.inc(delta);
+    }
+
+    /// Underlying bar, for attaching nested progress
+    pub fn bar(&self) -> &ProgressBar {
+        &self.bar
     }
 
     pub fn finish(self) {
-        let duration = self.start.elapsed();
-        info!(
-            "✅ Completed: {} ({}/{}) in {:?}",
-            self.message, self.total, self.total, duration
-        );
+        self.bar.finish();
     }
 }
 
 /// Context manager for nested logging contexts
+///
+/// Each context is a span whose parent is the enclosing context of the same
+/// task (see `trace::task_scope`) or thread; every record logged while it is
+/// active is tagged with it.
 pub struct LogContext {
     context: String,
     start: Instant,
+    span: Span,
 }
 
 impl LogContext {
     pub fn new(context: &str) -> Self {
+        let span = Span::enter(context);
         info!("➡️  Entering context: {}", context);
         Self {
             context: context.to_string(),
             start: Instant::now(),
+            span,
         }
     }
 
+    pub fn span_id(&self) -> u64 {
+        self.span.id()
+    }
+
+    pub fn parent_id(&self) -> Option<u64> {
+        self.span.parent()
+    }
+
+    /// Log `message` in this context
+    ///
+    /// The logger already tags the record with the innermost span, so the
+    /// context name is only added when an inner context is active.
     pub fn log(&self, level: LogLevel, message: &str) {
-        let prefixed = format!("[{}] {}", self.context, message);
+        let prefixed = match trace::current_span() {
+            Some(span) if span.id == self.span.id() => message.to_string(),
+            _ => format!("[{}] {}", self.context, message),
+        };
         match level {
             LogLevel::Debug => debug!("{}", prefixed),
             LogLevel::Info => info!("{}", prefixed),
@@ -302,67 +565,11 @@ pub fn log_result<T, E: std::fmt::Display>(
 }
 
 /// Create a formatted table for logging
+///
+/// Column widths follow terminal display width, so UTF-8 device names line
+/// up; see `render::Table` for other formats, alignment and wrapping.
 pub fn format_table(headers: Vec<&str>, rows: Vec<Vec<String>>) -> String {
-    let mut output = String::new();
-    
-    // Calculate column widths
-    let mut widths = headers.iter().map(|h| h.len()).collect::<Vec<_>>();
-    for row in &rows {
-        for (i, cell) in row.iter().enumerate() {
-            if i < widths.len() {
-                widths[i] = widths[i].max(cell.len());
-            }
-        }
-    }
-    
-    // Print headers
-    output.push_str("┌");
-    for (i, width) in widths.iter().enumerate() {
-        output.push_str(&"─".repeat(width + 2));
-        if i < widths.len() - 1 {
-            output.push_str("┬");
-        }
-    }
-    output.push_str("┐\n");
-    
-    output.push_str("│");
-    for (i, header) in headers.iter().enumerate() {
-        output.push_str(&format!(" {:width$} ", header, width = widths[i]));
-        output.push('│');
-    }
-    output.push('\n');
-    
-    // Print separator
-    output.push_str("├");
-    for (i, width) in widths.iter().enumerate() {
-        output.push_str(&"─".repeat(width + 2));
-        if i < widths.len() - 1 {
-            output.push_str("┼");
-        }
-    }
-    output.push_str("┤\n");
-    
-    // Print rows
-    for row in rows {
-        output.push('│');
-        for (i, cell) in row.iter().enumerate() {
-            if i < widths.len() {
-                output.push_str(&format!(" {:width$} ", cell, width = widths[i]));
-                output.push('│');
-            }
-        }
-        output.push('\n');
-    }
-    
-    // Print bottom border
-    output.push_str("└");
-    for (i, width) in widths.iter().enumerate() {
-        output.push_str(&"─".repeat(width + 2));
-        if i < widths.len() - 1 {
-            output.push_str("┴");
-        }
-    }
-    output.push_str("┘");
-    
-    output
+    Table::from_headers(&headers)
+        .rows(rows)
+        .render(OutputFormat::Table)
 }
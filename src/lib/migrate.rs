@@ -0,0 +1,359 @@
+// Configuration migrations for Ubuntu Config Scripts
+//
+// This module upgrades configuration written for older layouts including:
+// - A `version` field identifying the layout of each file
+// - A registry of migration steps (renamed keys, moved sections, removed keys, new defaults)
+// - A report of every change applied, including the values removed
+// - The same renames, moves and removals inside profile overlays
+// - In-place file upgrades that keep the original format, comments and key
+//   order, and write a backup
+
+use crate::lib::config::{get_path, merge_values, set_path, split_path, take_path};
+use crate::lib::config_edit::ConfigDocument;
+use crate::lib::profile::PROFILES_KEY;
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Layout version written by this release
+pub const CONFIG_VERSION: u32 = 1;
+
+/// Top-level key holding the layout version; files without it are version 0
+pub const VERSION_KEY: &str = "version";
+
+/// One change made while migrating, with dotted key paths
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MigrationChange {
+    Renamed { from: String, to: String },
+    Moved { from: String, to: String },
+    Removed { path: String, value: Value },
+    Added { path: String, value: Value },
+    Versioned { from: u32, to: u32 },
+}
+
+impl fmt::Display for MigrationChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationChange::Renamed { from, to } => write!(f, "renamed {} -> {}", from, to),
+            MigrationChange::Moved { from, to } => write!(f, "moved {} -> {}", from, to),
+            MigrationChange::Removed { path, value } => {
+                write!(f, "removed {} (was {})", path, value)
+            }
+            MigrationChange::Added { path, value } => write!(f, "added {} = {}", path, value),
+            MigrationChange::Versioned { from, to } => {
+                write!(f, "version {} -> {}", from, to)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Step {
+    Rename { from: String, to: String },
+    Move { from: String, to: String },
+    Remove { path: String },
+    Add { path: String, value: Value },
+}
+
+/// Steps that upgrade a configuration to `version`
+#[derive(Debug, Clone)]
+pub struct Migration {
+    pub version: u32,
+    pub description: String,
+    steps: Vec<Step>,
+}
+
+impl Migration {
+    pub fn new(version: u32, description: &str) -> Self {
+        Self {
+            version,
+            description: description.to_string(),
+            steps: Vec::new(),
+        }
+    }
+
+    /// Rename a key within its section (e.g. `audio.volume` -> `audio.volume_level`)
+    pub fn rename(mut self, from: &str, to: &str) -> Self {
+        self.steps.push(Step::Rename {
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+        self
+    }
+
+    /// Move a key or whole section elsewhere (e.g. `logging` -> `system.logging`)
+    pub fn move_to(mut self, from: &str, to: &str) -> Self {
+        self.steps.push(Step::Move {
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+        self
+    }
+
+    /// Drop a key that no longer has any meaning
+    pub fn remove(mut self, path: &str) -> Self {
+        self.steps.push(Step::Remove {
+            path: path.to_string(),
+        });
+        self
+    }
+
+    /// Add a key with a value when the file does not set it
+    pub fn add(mut self, path: &str, value: Value) -> Self {
+        self.steps.push(Step::Add {
+            path: path.to_string(),
+            value,
+        });
+        self
+    }
+
+    fn apply(&self, value: &mut Value, changes: &mut Vec<MigrationChange>) -> Result<()> {
+        for step in &self.steps {
+            step.apply(value, "", changes)?;
+            // Profiles overlay the same keys, so their copies move along
+            if let Some(Value::Object(profiles)) = value.get_mut(PROFILES_KEY) {
+                for (name, overlay) in profiles.iter_mut() {
+                    let prefix = format!("{}.{}.", PROFILES_KEY, name);
+                    if !matches!(step, Step::Add { .. }) {
+                        step.apply(overlay, &prefix, changes)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Step {
+    /// Apply to `value`, reporting paths with `prefix` in front
+    fn apply(
+        &self,
+        value: &mut Value,
+        prefix: &str,
+        changes: &mut Vec<MigrationChange>,
+    ) -> Result<()> {
+        let full = |path: &str| format!("{}{}", prefix, path);
+        match self {
+            Step::Rename { from, to } => {
+                if relocate(value, from, to)? {
+                    changes.push(MigrationChange::Renamed {
+                        from: full(from),
+                        to: full(to),
+                    });
+                }
+            }
+            Step::Move { from, to } => {
+                if relocate(value, from, to)? {
+                    changes.push(MigrationChange::Moved {
+                        from: full(from),
+                        to: full(to),
+                    });
+                }
+            }
+            Step::Remove { path } => {
+                if let Some(removed) = take_path(value, path)? {
+                    changes.push(MigrationChange::Removed {
+                        path: full(path),
+                        value: removed,
+                    });
+                }
+            }
+            Step::Add { path, value: added } => {
+                if get_path(value, path).is_none() {
+                    set_path(value, &split_path(path)?, added.clone())?;
+                    changes.push(MigrationChange::Added {
+                        path: full(path),
+                        value: added.clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Move the value at `from` to `to`; existing values at `to` take precedence
+fn relocate(root: &mut Value, from: &str, to: &str) -> Result<bool> {
+    let Some(mut moved) = take_path(root, from)? else {
+        return Ok(false);
+    };
+    if let Some(existing) = get_path(root, to) {
+        merge_values(&mut moved, existing.clone());
+    }
+    set_path(root, &split_path(to)?, moved)?;
+    Ok(true)
+}
+
+/// Result of migrating one configuration value
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub changes: Vec<MigrationChange>,
+    #[serde(skip)]
+    pub value: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup: Option<PathBuf>,
+}
+
+impl MigrationReport {
+    pub fn is_changed(&self) -> bool {
+        !self.changes.is_empty()
+    }
+}
+
+/// Ordered registry of migrations
+#[derive(Debug, Clone)]
+pub struct Migrations {
+    migrations: Vec<Migration>,
+}
+
+impl Migrations {
+    /// Registry without any migrations
+    pub fn empty() -> Self {
+        Self {
+            migrations: Vec::new(),
+        }
+    }
+
+    /// Migrations for every layout this release can read
+    pub fn builtin() -> Self {
+        // Version 1 is the first versioned layout; unversioned files already match it
+        Self::empty().migration(Migration::new(
+            1,
+            "Introduce the version field for unversioned files",
+        ))
+    }
+
+    pub fn migration(mut self, migration: Migration) -> Self {
+        self.migrations.push(migration);
+        self.migrations.sort_by_key(|m| m.version);
+        self
+    }
+
+    /// Newest version the registry can produce
+    pub fn current_version(&self) -> u32 {
+        self.migrations.last().map(|m| m.version).unwrap_or(0)
+    }
+
+    /// Upgrade a configuration value to the newest version
+    pub fn migrate(&self, mut value: Value) -> Result<MigrationReport> {
+        let from_version = config_version(&value)?;
+        let to_version = self.current_version();
+        if from_version > to_version {
+            return Err(anyhow!(
+                "Configuration version {} is newer than the supported version {}",
+                from_version,
+                to_version
+            ));
+        }
+
+        let mut changes = Vec::new();
+        for migration in self.migrations.iter().filter(|m| m.version > from_version) {
+            migration.apply(&mut value, &mut changes).with_context(|| {
+                format!(
+                    "Migration to version {} failed: {}",
+                    migration.version, migration.description
+                )
+            })?;
+        }
+
+        if from_version != to_version {
+            if let Value::Object(ref mut object) = value {
+                object.insert(VERSION_KEY.to_string(), to_version.into());
+            }
+            changes.push(MigrationChange::Versioned {
+                from: from_version,
+                to: to_version,
+            });
+        }
+
+        Ok(MigrationReport {
+            from_version,
+            to_version,
+            changes,
+            value,
+            backup: None,
+        })
+    }
+}
+
+impl Default for Migrations {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+/// Layout version of a configuration value (0 when unversioned)
+pub fn config_version(value: &Value) -> Result<u32> {
+    match value.get(VERSION_KEY) {
+        None | Some(Value::Null) => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| anyhow!("Invalid configuration version: {}", version)),
+    }
+}
+
+/// Upgrade a configuration value with the built-in migrations
+pub fn migrate_value(value: Value) -> Result<MigrationReport> {
+    Migrations::builtin().migrate(value)
+}
+
+/// Upgrade a configuration file in place, keeping its format
+///
+/// Only the keys a migration touches are edited, so comments and ordering
+/// survive in TOML and YAML files. The original file is copied to
+/// `<file>.v<old version>.bak` (see `backup_path`) before it is rewritten. With `dry_run` the
+/// report is returned and nothing is written.
+pub fn migrate_file(
+    path: &Path,
+    migrations: &Migrations,
+    dry_run: bool,
+) -> Result<MigrationReport> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+    let mut document = ConfigDocument::from_text(path, &content);
+
+    let mut report = migrations
+        .migrate(document.value()?)
+        .with_context(|| format!("Failed to migrate config file: {}", path.display()))?;
+    if dry_run || !report.is_changed() {
+        return Ok(report);
+    }
+
+    let backup = backup_path(path, report.from_version);
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&backup)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .with_context(|| format!("Failed to write backup: {}", backup.display()))?;
+    document.apply(&report.value)?;
+    document.save()?;
+    report.backup = Some(backup);
+    Ok(report)
+}
+
+/// Backup location for a file migrated from `version`
+///
+/// `<file>.v<version>.bak`, or `<file>.v<version>.<n>.bak` with the first
+/// free `n` when earlier backups exist, so no backup is ever overwritten.
+pub fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let file_name = path.file_name().unwrap_or_default();
+    (0..)
+        .map(|n| {
+            let mut name = file_name.to_os_string();
+            match n {
+                0 => name.push(format!(".v{}.bak", version)),
+                n => name.push(format!(".v{}.{}.bak", version, n)),
+            }
+            path.with_file_name(name)
+        })
+        .find(|candidate| !candidate.exists())
+        .expect("some backup name is free")
+}
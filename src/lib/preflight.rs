@@ -0,0 +1,436 @@
+// Filesystem preflight checks for Ubuntu Config Scripts
+//
+// This module checks a target path before a script writes to it including:
+// - Real access checks with faccessat(2) for the effective user
+// - Free space from statvfs(3) against a required size
+// - Read-only and noexec mounts and the filesystem type, from
+//   /proc/self/mountinfo
+// - A `Preflight` builder that reports every unmet requirement at once, with
+//   presets for swap files and build directories
+
+use crate::lib::progress::format_bytes;
+use anyhow::{anyhow, Context, Result};
+use nix::errno::Errno;
+use nix::fcntl::AtFlags;
+use nix::sys::statvfs::statvfs;
+use nix::unistd::{faccessat, AccessFlags};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Mount table of the current process
+pub const MOUNTINFO_PATH: &str = "/proc/self/mountinfo";
+
+/// Filesystems the kernel cannot swap to
+pub const SWAPFILE_UNSUPPORTED_FILESYSTEMS: [&str; 10] = [
+    "tmpfs", "ramfs", "overlay", "squashfs", "nfs", "nfs4", "cifs", "smb3", "fuse", "fuseblk",
+];
+
+/// One line of /proc/self/mountinfo
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MountInfo {
+    pub mount_id: u32,
+    pub parent_id: u32,
+    /// Directory of the mounted filesystem that appears at `mount_point`
+    pub root: PathBuf,
+    pub mount_point: PathBuf,
+    /// Per-mount options (`rw`, `noexec`, `nosuid`, ...)
+    pub mount_options: Vec<String>,
+    pub fs_type: String,
+    pub source: String,
+    /// Options of the filesystem itself
+    pub super_options: Vec<String>,
+}
+
+impl MountInfo {
+    /// Parse one mountinfo line (see proc(5))
+    pub fn parse(line: &str) -> Result<Self> {
+        let invalid = || anyhow!("Invalid mountinfo line: {}", line);
+        let (mount, filesystem) = line.split_once(" - ").ok_or_else(invalid)?;
+
+        let fields: Vec<&str> = mount.split_whitespace().collect();
+        if fields.len() < 6 {
+            return Err(invalid());
+        }
+        let filesystem: Vec<&str> = filesystem.split_whitespace().collect();
+        if filesystem.len() < 2 {
+            return Err(invalid());
+        }
+
+        let options = |text: &str| text.split(',').map(String::from).collect::<Vec<_>>();
+        Ok(Self {
+            mount_id: fields[0].parse().map_err(|_| invalid())?,
+            parent_id: fields[1].parse().map_err(|_| invalid())?,
+            root: PathBuf::from(unescape_octal(fields[3])),
+            mount_point: PathBuf::from(unescape_octal(fields[4])),
+            mount_options: options(fields[5]),
+            fs_type: filesystem[0].to_string(),
+            source: unescape_octal(filesystem[1]),
+            super_options: filesystem.get(2).map(|o| options(o)).unwrap_or_default(),
+        })
+    }
+
+    /// Whether nothing on this mount can be written
+    pub fn is_read_only(&self) -> bool {
+        self.mount_options.iter().any(|o| o == "ro") || self.super_options.iter().any(|o| o == "ro")
+    }
+
+    /// Whether programs on this mount cannot be executed
+    pub fn is_noexec(&self) -> bool {
+        self.mount_options.iter().any(|o| o == "noexec")
+    }
+}
+
+/// Decode the `\040`-style escapes mountinfo uses for spaces and tabs
+fn unescape_octal(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = bytes
+            .get(i + 1..i + 4)
+            .filter(|digits| bytes[i] == b'\\' && digits.iter().all(|d| (b'0'..=b'7').contains(d)));
+        match escape
+            .and_then(|digits| u8::from_str_radix(std::str::from_utf8(digits).ok()?, 8).ok())
+        {
+            Some(byte) => {
+                out.push(byte);
+                i += 4;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Parse the whole mount table, skipping blank lines
+pub fn parse_mountinfo(content: &str) -> Result<Vec<MountInfo>> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(MountInfo::parse)
+        .collect()
+}
+
+/// Mount containing an absolute, canonical `path`
+///
+/// The longest matching mount point wins; among equal ones the last mounted
+/// (later in the table) shadows the others.
+pub fn mount_for<'a>(mounts: &'a [MountInfo], path: &Path) -> Option<&'a MountInfo> {
+    // max_by_key keeps the last of equal elements
+    mounts
+        .iter()
+        .filter(|mount| path.starts_with(&mount.mount_point))
+        .max_by_key(|mount| mount.mount_point.components().count())
+}
+
+/// Mount holding `path`, or the directory it would be created in
+pub fn find_mount(path: &Path) -> Result<MountInfo> {
+    let content = std::fs::read_to_string(MOUNTINFO_PATH)
+        .with_context(|| format!("Failed to read {}", MOUNTINFO_PATH))?;
+    let mounts = parse_mountinfo(&content)?;
+    let target = canonical_target(path)?;
+    mount_for(&mounts, &target)
+        .cloned()
+        .ok_or_else(|| anyhow!("No mount found for {}", target.display()))
+}
+
+/// Filesystem type (e.g. `ext4`, `btrfs`, `tmpfs`) holding `path`
+pub fn filesystem_type(path: &Path) -> Result<String> {
+    Ok(find_mount(path)?.fs_type)
+}
+
+/// `path` itself when it exists, else its nearest existing ancestor
+pub fn existing_ancestor(path: &Path) -> Option<PathBuf> {
+    absolute(path)
+        .ok()?
+        .ancestors()
+        .find(|candidate| candidate.exists())
+        .map(Path::to_path_buf)
+}
+
+fn absolute(path: &Path) -> Result<PathBuf> {
+    if path.is_absolute() {
+        Ok(path.to_path_buf())
+    } else {
+        Ok(std::env::current_dir()?.join(path))
+    }
+}
+
+fn canonical_target(path: &Path) -> Result<PathBuf> {
+    let existing = existing_ancestor(path)
+        .ok_or_else(|| anyhow!("No existing parent directory for {}", path.display()))?;
+    existing
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", existing.display()))
+}
+
+/// Bytes available to unprivileged users on the filesystem holding `path`
+pub fn available_space(path: &Path) -> Result<u64> {
+    let target = canonical_target(path)?;
+    let stats = statvfs(&target)
+        .with_context(|| format!("Failed to query free space for {}", target.display()))?;
+    Ok(stats.blocks_available() as u64 * stats.fragment_size() as u64)
+}
+
+/// Check that the effective user can create or modify `path`
+///
+/// An existing path needs write access; a missing one needs write and
+/// search access on its nearest existing ancestor directory.
+pub fn check_writable(path: &Path) -> Result<()> {
+    let target = existing_ancestor(path)
+        .ok_or_else(|| anyhow!("No existing parent directory for {}", path.display()))?;
+    if target == absolute(path)? {
+        check_access(&target, AccessFlags::W_OK)
+    } else if !target.is_dir() {
+        Err(anyhow!(
+            "Cannot create {}: {} is not a directory",
+            path.display(),
+            target.display()
+        ))
+    } else {
+        check_access(&target, AccessFlags::W_OK | AccessFlags::X_OK)
+            .with_context(|| format!("Cannot create {}", path.display()))
+    }
+}
+
+/// Check that the effective user can read `path`
+pub fn check_readable(path: &Path) -> Result<()> {
+    check_access(path, AccessFlags::R_OK)
+}
+
+fn check_access(path: &Path, mode: AccessFlags) -> Result<()> {
+    faccessat(None, path, mode, AtFlags::AT_EACCESS).map_err(|errno| {
+        let reason = match errno {
+            Errno::EACCES | Errno::EPERM => "permission denied".to_string(),
+            Errno::EROFS => "read-only filesystem".to_string(),
+            Errno::ENOENT => "does not exist".to_string(),
+            other => other.desc().to_string(),
+        };
+        anyhow!("{}: {}", path.display(), reason)
+    })
+}
+
+/// Requirements on a path checked before a script writes to it
+#[derive(Debug, Clone)]
+pub struct Preflight {
+    path: PathBuf,
+    writable: bool,
+    readable: bool,
+    executable: bool,
+    free_space: Option<u64>,
+    allowed_filesystems: Vec<String>,
+    rejected_filesystems: Vec<String>,
+    mountinfo: Option<String>,
+}
+
+/// Outcome of a preflight check
+#[derive(Debug, Clone, Serialize)]
+pub struct PreflightReport {
+    pub path: PathBuf,
+    pub mount: Option<MountInfo>,
+    pub available_bytes: Option<u64>,
+    pub failures: Vec<String>,
+}
+
+impl PreflightReport {
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Error listing every failure, or the report when all checks passed
+    pub fn into_result(self) -> Result<Self> {
+        if self.is_ok() {
+            return Ok(self);
+        }
+        let reasons: Vec<String> = self.failures.iter().map(|f| format!("  - {}", f)).collect();
+        Err(anyhow!(
+            "Preflight checks failed for {}:\n{}",
+            self.path.display(),
+            reasons.join("\n")
+        ))
+    }
+}
+
+impl Preflight {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            writable: false,
+            readable: false,
+            executable: false,
+            free_space: None,
+            allowed_filesystems: Vec::new(),
+            rejected_filesystems: Vec::new(),
+            mountinfo: None,
+        }
+    }
+
+    /// Checks for creating a swap file of `size_bytes` at `path`
+    pub fn swapfile(path: impl Into<PathBuf>, size_bytes: u64) -> Self {
+        Self::new(path)
+            .writable()
+            .free_space(size_bytes)
+            .reject_filesystems(&SWAPFILE_UNSUPPORTED_FILESYSTEMS)
+    }
+
+    /// Checks for a build or install directory that needs `size_bytes` free
+    pub fn build_dir(path: impl Into<PathBuf>, size_bytes: u64) -> Self {
+        Self::new(path)
+            .writable()
+            .executable()
+            .free_space(size_bytes)
+    }
+
+    /// Require write access and a writable mount
+    pub fn writable(mut self) -> Self {
+        self.writable = true;
+        self
+    }
+
+    /// Require read access
+    pub fn readable(mut self) -> Self {
+        self.readable = true;
+        self
+    }
+
+    /// Require a mount that allows executing programs (not `noexec`)
+    pub fn executable(mut self) -> Self {
+        self.executable = true;
+        self
+    }
+
+    /// Require at least `bytes` available on the filesystem
+    pub fn free_space(mut self, bytes: u64) -> Self {
+        self.free_space = Some(bytes);
+        self
+    }
+
+    /// Only accept these filesystem types
+    pub fn filesystems(mut self, types: &[&str]) -> Self {
+        self.allowed_filesystems = types.iter().map(|t| t.to_string()).collect();
+        self
+    }
+
+    /// Refuse these filesystem types
+    pub fn reject_filesystems(mut self, types: &[&str]) -> Self {
+        self.rejected_filesystems = types.iter().map(|t| t.to_string()).collect();
+        self
+    }
+
+    /// Use this mount table instead of /proc/self/mountinfo
+    pub fn mountinfo(mut self, content: &str) -> Self {
+        self.mountinfo = Some(content.to_string());
+        self
+    }
+
+    /// Run every check and collect the failures
+    pub fn report(&self) -> PreflightReport {
+        let mut failures = Vec::new();
+        let needs_mount = self.writable
+            || self.executable
+            || !self.allowed_filesystems.is_empty()
+            || !self.rejected_filesystems.is_empty();
+
+        let mount = if needs_mount {
+            match self.lookup_mount() {
+                Ok(mount) => Some(mount),
+                Err(e) => {
+                    failures.push(format!("{:#}", e));
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        if let Some(ref mount) = mount {
+            let on = format!(
+                "{} ({} on {})",
+                mount.mount_point.display(),
+                mount.fs_type,
+                mount.source
+            );
+            if self.writable && mount.is_read_only() {
+                failures.push(format!("{} is mounted read-only", on));
+            }
+            if self.executable && mount.is_noexec() {
+                failures.push(format!(
+                    "{} is mounted noexec, so programs there cannot run",
+                    on
+                ));
+            }
+            if !self.allowed_filesystems.is_empty()
+                && !self.allowed_filesystems.contains(&mount.fs_type)
+            {
+                failures.push(format!(
+                    "{} filesystem is not supported here (expected {})",
+                    mount.fs_type,
+                    self.allowed_filesystems.join(", ")
+                ));
+            }
+            if self.rejected_filesystems.contains(&mount.fs_type) {
+                failures.push(format!(
+                    "{} filesystem is not supported here",
+                    mount.fs_type
+                ));
+            }
+        }
+
+        let read_only = mount.as_ref().is_some_and(MountInfo::is_read_only);
+        if self.writable && !read_only {
+            if let Err(e) = check_writable(&self.path) {
+                failures.push(format!("not writable: {:#}", e));
+            }
+        }
+        if self.readable {
+            if let Err(e) = check_readable(&self.path) {
+                failures.push(format!("not readable: {:#}", e));
+            }
+        }
+
+        let mut available_bytes = None;
+        if let Some(required) = self.free_space {
+            match available_space(&self.path) {
+                Ok(available) => {
+                    available_bytes = Some(available);
+                    if available < required {
+                        failures.push(format!(
+                            "needs {} free but only {} is available",
+                            format_bytes(required),
+                            format_bytes(available)
+                        ));
+                    }
+                }
+                Err(e) => failures.push(format!("{:#}", e)),
+            }
+        }
+
+        PreflightReport {
+            path: self.path.clone(),
+            mount,
+            available_bytes,
+            failures,
+        }
+    }
+
+    /// Run every check, failing with all unmet requirements
+    pub fn check(&self) -> Result<PreflightReport> {
+        self.report().into_result()
+    }
+
+    fn lookup_mount(&self) -> Result<MountInfo> {
+        match self.mountinfo {
+            Some(ref content) => {
+                let mounts = parse_mountinfo(content)?;
+                let target = canonical_target(&self.path)?;
+                mount_for(&mounts, &target)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("No mount found for {}", target.display()))
+            }
+            None => find_mount(&self.path),
+        }
+    }
+}
@@ -0,0 +1,286 @@
+// Named configuration profiles for Ubuntu Config Scripts
+//
+// This module lets one machine switch between setups including:
+// - Profiles that overlay any section (`[profiles.streaming.obs]`)
+// - Built-in `streaming`, `rust-dev` and `laptop` profiles
+// - The active profile, recorded in the `profile` key of a configuration
+//   file or chosen per run with UCS_PROFILE
+// - Profile validation against the Config schema
+// - Differences between the effective values of two profiles
+
+use crate::lib::config::{get_path, leaf_paths, merge_values, set_path, split_path};
+use crate::lib::migrate::VERSION_KEY;
+use crate::lib::render::{Column, Table};
+use crate::lib::schema::{
+    suggest, Config, Constraint, ValidateFields, ValidationError, ValidationErrors,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Key holding the name of the active profile
+pub const PROFILE_KEY: &str = "profile";
+
+/// Key holding the profile definitions
+pub const PROFILES_KEY: &str = "profiles";
+
+/// Environment variable that selects a profile for one run
+pub const PROFILE_ENV: &str = "UCS_PROFILE";
+
+/// Profile names usable as a single dotted-path segment
+pub const PROFILE_NAME_PATTERN: &str = "^[A-Za-z0-9_-]+$";
+
+/// Top-level keys a profile cannot overlay
+const RESERVED_KEYS: [&str; 3] = [VERSION_KEY, PROFILE_KEY, PROFILES_KEY];
+
+/// A named set of values laid over the configuration while active
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Profile {
+    /// What the profile is for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Configuration values, by section, that this profile changes
+    #[serde(flatten)]
+    pub overlay: Map<String, Value>,
+}
+
+impl Profile {
+    pub fn new(description: &str) -> Self {
+        Self {
+            description: Some(description.to_string()),
+            overlay: Map::new(),
+        }
+    }
+
+    /// Overlay a value at a dotted path (e.g. `obs.fps`)
+    pub fn set(mut self, path: &str, value: Value) -> Self {
+        let mut overlay = Value::Object(std::mem::take(&mut self.overlay));
+        let segments: Vec<String> = path.split('.').map(String::from).collect();
+        set_path(&mut overlay, &segments, value).expect("profile keys are objects");
+        if let Value::Object(map) = overlay {
+            self.overlay = map;
+        }
+        self
+    }
+
+    /// Dotted keys this profile changes
+    pub fn keys(&self) -> Vec<String> {
+        leaf_paths(&self.overlay_value())
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    /// The overlay as a configuration layer, without the reserved keys
+    pub fn overlay_value(&self) -> Value {
+        Value::Object(
+            self.overlay
+                .iter()
+                .filter(|(key, _)| !RESERVED_KEYS.contains(&key.as_str()))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        )
+    }
+
+    /// Check the overlay against the Config schema
+    ///
+    /// Paths are relative to the profile (e.g. `/obs/fps`). Rules are only
+    /// checked once every overlaid value has the right type.
+    pub fn validation_errors(&self) -> ValidationErrors {
+        let mut errors = ValidationErrors::new();
+        let defaults = default_value();
+        let known: Vec<String> = config_keys(&defaults);
+        let known: Vec<&str> = known.iter().map(String::as_str).collect();
+
+        let overlay = Value::Object(self.overlay.clone());
+        for (key, value) in leaf_paths(&overlay) {
+            let pointer = format!("/{}", key.replace('.', "/"));
+            let section = key.split('.').next().unwrap_or_default();
+            if RESERVED_KEYS.contains(&section) {
+                errors.push(
+                    ValidationError::new(
+                        &format!("/{}", section),
+                        value.clone(),
+                        Constraint::NoAdditionalProperties,
+                    )
+                    .message(&format!("{} cannot be set by a profile", section)),
+                );
+                continue;
+            }
+            if get_path(&defaults, &key).is_none() {
+                errors.push(
+                    ValidationError::new(
+                        &pointer,
+                        value.clone(),
+                        Constraint::NoAdditionalProperties,
+                    )
+                    .message(&format!("unknown configuration key {}", key))
+                    .suggestion(suggest(&key, &known)),
+                );
+                continue;
+            }
+
+            let mut candidate = defaults.clone();
+            let fits = split_path(&key)
+                .and_then(|segments| set_path(&mut candidate, &segments, value.clone()))
+                .map_err(|e| e.to_string())
+                .and_then(|_| {
+                    serde_json::from_value::<Config>(candidate).map_err(|e| e.to_string())
+                });
+            if let Err(e) = fits {
+                errors.push(
+                    ValidationError::new(
+                        &pointer,
+                        value.clone(),
+                        Constraint::Custom {
+                            description: "a value of the field's type".to_string(),
+                        },
+                    )
+                    .message(&e),
+                );
+            }
+        }
+
+        if errors.is_empty() {
+            let mut merged = defaults;
+            merge_values(&mut merged, self.overlay_value());
+            if let Ok(config) = serde_json::from_value::<Config>(merged) {
+                config.validate_fields("", &mut errors);
+            }
+        }
+        errors
+    }
+}
+
+/// Profiles available without any configuration file
+///
+/// Files can change these key by key or define profiles of their own.
+pub fn builtin_profiles() -> BTreeMap<String, Profile> {
+    let streaming = Profile::new("Recording and streaming sessions")
+        .set("audio.enable_echo_cancellation", Value::Bool(true))
+        .set("audio.volume_level", Value::from(80))
+        .set("obs.resolution", Value::from("1920x1080"))
+        .set("obs.fps", Value::from(60))
+        .set("obs.encoder", Value::from("nvenc"))
+        .set("swap.swappiness", Value::from(10));
+
+    let rust_dev = Profile::new("Heavy Rust builds and large workspaces")
+        .set("dev.build_mode", Value::from("debug"))
+        .set("dev.include_debug_symbols", Value::Bool(true))
+        .set("swap.size_mb", Value::from(16384))
+        .set("swap.swappiness", Value::from(10))
+        .set("sysctl.vm_dirty_ratio", Value::from(40))
+        .set("sysctl.fs_inotify_max_user_watches", Value::from(524288));
+
+    let laptop = Profile::new("Battery-friendly settings")
+        .set("audio.volume_level", Value::from(50))
+        .set("obs.resolution", Value::from("1280x720"))
+        .set("obs.fps", Value::from(30))
+        .set("obs.encoder", Value::from("vaapi"))
+        .set("swap.swappiness", Value::from(60))
+        .set("sysctl.vm_dirty_writeback_centisecs", Value::from(1500));
+
+    BTreeMap::from([
+        ("streaming".to_string(), streaming),
+        ("rust-dev".to_string(), rust_dev),
+        ("laptop".to_string(), laptop),
+    ])
+}
+
+/// Settable keys of the default configuration, excluding profile keys
+fn config_keys(defaults: &Value) -> Vec<String> {
+    leaf_paths(defaults)
+        .into_iter()
+        .map(|(key, _)| key)
+        .filter(|key| {
+            let section = key.split('.').next().unwrap_or_default();
+            !RESERVED_KEYS.contains(&section)
+        })
+        .collect()
+}
+
+fn default_value() -> Value {
+    serde_json::to_value(Config::default()).expect("default configuration serializes")
+}
+
+/// One key whose effective value differs between two configurations
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValueChange {
+    pub path: String,
+    pub from: Option<Value>,
+    pub to: Option<Value>,
+}
+
+/// Keys that differ between two merged configurations, ignoring profile keys
+pub fn diff_values(from: &Value, to: &Value) -> Vec<ValueChange> {
+    let from_leaves = leaf_paths(from);
+    let to_leaves = leaf_paths(to);
+
+    let mut paths: Vec<&String> = from_leaves.iter().map(|(path, _)| path).collect();
+    for (path, _) in &to_leaves {
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+
+    paths
+        .into_iter()
+        .filter(|path| {
+            let section = path.split('.').next().unwrap_or_default();
+            section != PROFILE_KEY && section != PROFILES_KEY
+        })
+        .filter_map(|path| {
+            let before = get_path(from, path).cloned();
+            let after = get_path(to, path).cloned();
+            (before != after).then(|| ValueChange {
+                path: path.clone(),
+                from: before,
+                to: after,
+            })
+        })
+        .collect()
+}
+
+/// `diff_values` output as a table with the two sides labelled
+pub fn diff_table(changes: &[ValueChange], from: &str, to: &str) -> Table {
+    let show = |value: &Option<Value>| {
+        value
+            .as_ref()
+            .map(Value::to_string)
+            .unwrap_or_else(|| "(unset)".to_string())
+    };
+    let mut table = Table::new(vec![
+        Column::new("Key"),
+        Column::new(from).max_width(40),
+        Column::new(to).max_width(40),
+    ]);
+    for change in changes {
+        table.add_row(vec![
+            change.path.clone(),
+            show(&change.from),
+            show(&change.to),
+        ]);
+    }
+    table
+}
+
+/// Profiles defined in a configuration, marking the active one
+pub fn profiles_table(config: &Config) -> Table {
+    let mut table = Table::new(vec![
+        Column::new("Profile"),
+        Column::new("Active"),
+        Column::new("Keys"),
+        Column::new("Description").wrap(),
+    ]);
+    for (name, profile) in &config.profiles {
+        let active = config.profile.as_deref() == Some(name.as_str());
+        table.add_row(vec![
+            name.clone(),
+            if active { "*" } else { "" }.to_string(),
+            profile.keys().len().to_string(),
+            profile.description.clone().unwrap_or_default(),
+        ]);
+    }
+    table
+}
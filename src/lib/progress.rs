@@ -0,0 +1,557 @@
+// Terminal progress rendering for Ubuntu Config Scripts
+//
+// This module renders progress for long-running operations including:
+// - Interactive progress bars with throughput and ETA when stderr is a TTY
+// - Nested and concurrent bars sharing a single terminal region
+// - Throttled log lines when output is redirected or running in CI
+// - Item and byte based progress (cleanup, downloads, disk scans)
+
+use crate::lib::common::is_ci;
+use log::info;
+use std::io::{self, IsTerminal, Write};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Minimum time between two interactive redraws
+const REDRAW_INTERVAL: Duration = Duration::from_millis(80);
+
+/// Default time between two progress log lines in non-interactive mode
+pub const DEFAULT_LOG_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Width used when the terminal size cannot be determined
+const FALLBACK_WIDTH: usize = 80;
+
+/// Unit of the quantity being tracked
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProgressUnit {
+    Items,
+    Bytes,
+}
+
+/// How progress is reported
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProgressMode {
+    /// Redraw bars in place on stderr
+    Interactive,
+    /// Emit throttled log lines
+    Log,
+    /// Report nothing
+    Hidden,
+}
+
+impl ProgressMode {
+    /// Pick interactive rendering when stderr is a terminal, log lines otherwise
+    pub fn detect() -> Self {
+        let dumb_term = std::env::var("TERM").map(|t| t == "dumb").unwrap_or(false);
+        if io::stderr().is_terminal() && !is_ci() && !dumb_term {
+            ProgressMode::Interactive
+        } else {
+            ProgressMode::Log
+        }
+    }
+}
+
+/// Snapshot of a single progress bar
+#[derive(Debug, Clone)]
+pub struct ProgressState {
+    pub message: String,
+    pub unit: ProgressUnit,
+    pub total: u64,
+    pub current: u64,
+    pub depth: usize,
+    pub finished: bool,
+    pub started: Instant,
+    last_log: Option<Instant>,
+}
+
+impl ProgressState {
+    pub fn new(total: u64, message: &str, unit: ProgressUnit) -> Self {
+        Self {
+            message: message.to_string(),
+            unit,
+            total,
+            current: 0,
+            depth: 0,
+            finished: false,
+            started: Instant::now(),
+            last_log: None,
+        }
+    }
+
+    /// Completed fraction in `0.0..=1.0`, or `None` when the total is unknown
+    pub fn fraction(&self) -> Option<f64> {
+        if self.total == 0 {
+            return None;
+        }
+        Some((self.current as f64 / self.total as f64).min(1.0))
+    }
+
+    /// Completed percentage, or `None` when the total is unknown
+    pub fn percent(&self) -> Option<u32> {
+        self.fraction().map(|f| (f * 100.0) as u32)
+    }
+
+    /// Average throughput in units per second since the bar started
+    pub fn rate(&self) -> f64 {
+        rate_for(self.current, self.started.elapsed())
+    }
+
+    /// Estimated time remaining, or `None` when it cannot be estimated yet
+    pub fn eta(&self) -> Option<Duration> {
+        eta_for(self.current, self.total, self.started.elapsed())
+    }
+}
+
+/// Throughput in units per second for `done` units over `elapsed`
+pub fn rate_for(done: u64, elapsed: Duration) -> f64 {
+    let secs = elapsed.as_secs_f64();
+    if secs <= 0.0 {
+        0.0
+    } else {
+        done as f64 / secs
+    }
+}
+
+/// Estimated time remaining for `done` of `total` units after `elapsed`
+pub fn eta_for(done: u64, total: u64, elapsed: Duration) -> Option<Duration> {
+    if total == 0 || done == 0 {
+        return None;
+    }
+    if done >= total {
+        return Some(Duration::ZERO);
+    }
+    let rate = rate_for(done, elapsed);
+    if rate <= 0.0 {
+        return None;
+    }
+    Some(Duration::from_secs_f64((total - done) as f64 / rate))
+}
+
+/// Format a byte count using binary units (e.g. `1.5 MiB`)
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Format a duration compactly (e.g. `42s`, `3m05s`, `1h02m`)
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+fn format_amount(value: u64, unit: ProgressUnit) -> String {
+    match unit {
+        ProgressUnit::Items => value.to_string(),
+        ProgressUnit::Bytes => format_bytes(value),
+    }
+}
+
+fn format_rate(rate: f64, unit: ProgressUnit) -> String {
+    match unit {
+        ProgressUnit::Items => format!("{:.1}/s", rate),
+        ProgressUnit::Bytes => format!("{}/s", format_bytes(rate as u64)),
+    }
+}
+
+fn format_counts(state: &ProgressState) -> String {
+    if state.total == 0 {
+        format_amount(state.current, state.unit)
+    } else {
+        format!(
+            "{}/{}",
+            format_amount(state.current, state.unit),
+            format_amount(state.total, state.unit)
+        )
+    }
+}
+
+/// Render a single bar line no wider than `width` columns
+pub fn render_bar(state: &ProgressState, width: usize) -> String {
+    let indent = "  ".repeat(state.depth);
+    let percent = state
+        .percent()
+        .map(|p| format!("{:>3}%", p))
+        .unwrap_or_else(|| "  ?%".to_string());
+    let timing = if state.finished {
+        format!("done in {}", format_duration(state.started.elapsed()))
+    } else {
+        let eta = state
+            .eta()
+            .map(format_duration)
+            .unwrap_or_else(|| "--".to_string());
+        format!("{} ETA {}", format_rate(state.rate(), state.unit), eta)
+    };
+    let suffix = format!(" {} {} {}", percent, format_counts(state), timing);
+
+    // Give the bar at most 30 columns and the message whatever remains
+    let available = width.saturating_sub(indent.chars().count() + suffix.chars().count() + 1);
+    let bar_width = available.clamp(10, 30);
+    let message_width = available.saturating_sub(bar_width + 2);
+    let message: String = state.message.chars().take(message_width).collect();
+
+    let filled = state
+        .fraction()
+        .map(|f| (f * bar_width as f64).round() as usize)
+        .unwrap_or(0);
+    let bar = format!("{}{}", "█".repeat(filled), "░".repeat(bar_width - filled));
+
+    let line = format!("{}{} [{}]{}", indent, message, bar, suffix);
+    line.chars().take(width).collect()
+}
+
+/// Render a single log line for non-interactive output
+pub fn render_log_line(state: &ProgressState) -> String {
+    let percent = state
+        .percent()
+        .map(|p| format!(" - {}%", p))
+        .unwrap_or_default();
+    let eta = state
+        .eta()
+        .map(|eta| format!(", ETA {}", format_duration(eta)))
+        .unwrap_or_default();
+    format!(
+        "📊 Progress: {} ({}){} ({}{})",
+        state.message,
+        format_counts(state),
+        percent,
+        format_rate(state.rate(), state.unit),
+        eta
+    )
+}
+
+fn terminal_width() -> usize {
+    terminal_size::terminal_size_of(io::stderr())
+        .map(|(terminal_size::Width(w), _)| w as usize)
+        .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+        .unwrap_or(FALLBACK_WIDTH)
+}
+
+/// Shared renderer state for a group of bars
+struct Renderer {
+    mode: ProgressMode,
+    log_interval: Duration,
+    next_id: usize,
+    /// Bars in display order; children follow their parent
+    bars: Vec<(usize, ProgressState)>,
+    drawn_lines: usize,
+    last_draw: Option<Instant>,
+}
+
+impl Renderer {
+    fn position(&self, id: usize) -> Option<usize> {
+        self.bars.iter().position(|(bar_id, _)| *bar_id == id)
+    }
+
+    fn state_mut(&mut self, id: usize) -> Option<&mut ProgressState> {
+        self.bars
+            .iter_mut()
+            .find(|(bar_id, _)| *bar_id == id)
+            .map(|(_, state)| state)
+    }
+
+    fn insert(&mut self, state: ProgressState, parent: Option<usize>) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let index = parent
+            .and_then(|parent_id| self.position(parent_id))
+            .map(|parent_index| {
+                // Insert after the parent's last descendant
+                let parent_depth = self.bars[parent_index].1.depth;
+                let mut index = parent_index + 1;
+                while index < self.bars.len() && self.bars[index].1.depth > parent_depth {
+                    index += 1;
+                }
+                index
+            })
+            .unwrap_or(self.bars.len());
+
+        self.bars.insert(index, (id, state));
+        id
+    }
+
+    fn log_update(&mut self, id: usize) {
+        let interval = self.log_interval;
+        if let Some(state) = self.state_mut(id) {
+            let due = state
+                .last_log
+                .map(|last| last.elapsed() >= interval)
+                .unwrap_or(true);
+            if due {
+                state.last_log = Some(Instant::now());
+                info!("{}", render_log_line(state));
+            }
+        }
+    }
+
+    fn draw(&mut self, force: bool) {
+        if !force {
+            if let Some(last) = self.last_draw {
+                if last.elapsed() < REDRAW_INTERVAL {
+                    return;
+                }
+            }
+        }
+        self.last_draw = Some(Instant::now());
+
+        let width = terminal_width();
+        let mut out = String::new();
+        if self.drawn_lines > 0 {
+            out.push_str(&format!("\x1b[{}A", self.drawn_lines));
+        }
+        for (_, state) in &self.bars {
+            out.push_str("\r\x1b[2K");
+            out.push_str(&render_bar(state, width));
+            out.push('\n');
+        }
+        out.push_str("\x1b[J");
+
+        // Finished bars at the top of the region are left on screen for good
+        let committed = self
+            .bars
+            .iter()
+            .take_while(|(_, state)| state.finished)
+            .count();
+        self.drawn_lines = self.bars.len() - committed;
+        self.bars.drain(..committed);
+
+        // Finished children further down are collapsed into their parent
+        let before = self.bars.len();
+        self.bars
+            .retain(|(_, state)| !(state.finished && state.depth > 0));
+        if self.bars.len() != before {
+            self.last_draw = None;
+        }
+
+        let mut stderr = io::stderr().lock();
+        let _ = stderr.write_all(out.as_bytes());
+        let _ = stderr.flush();
+    }
+
+    fn remove(&mut self, id: usize) {
+        if let Some(index) = self.position(id) {
+            self.bars.remove(index);
+        }
+    }
+}
+
+/// A group of progress bars rendered together, e.g. one per parallel download
+#[derive(Clone)]
+pub struct MultiProgress {
+    inner: Arc<Mutex<Renderer>>,
+}
+
+impl MultiProgress {
+    /// Create a group using the mode detected from stderr
+    pub fn new() -> Self {
+        Self::with_mode(ProgressMode::detect())
+    }
+
+    /// Create a group with an explicit output mode
+    pub fn with_mode(mode: ProgressMode) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Renderer {
+                mode,
+                log_interval: DEFAULT_LOG_INTERVAL,
+                next_id: 0,
+                bars: Vec::new(),
+                drawn_lines: 0,
+                last_draw: None,
+            })),
+        }
+    }
+
+    /// Set the minimum time between two log lines for the same bar
+    pub fn log_interval(self, interval: Duration) -> Self {
+        self.lock().log_interval = interval;
+        self
+    }
+
+    pub fn mode(&self) -> ProgressMode {
+        self.lock().mode
+    }
+
+    /// Add a top-level bar counting items
+    pub fn add(&self, total: u64, message: &str) -> ProgressBar {
+        self.add_with_unit(total, message, ProgressUnit::Items)
+    }
+
+    /// Add a top-level bar counting bytes
+    pub fn add_bytes(&self, total: u64, message: &str) -> ProgressBar {
+        self.add_with_unit(total, message, ProgressUnit::Bytes)
+    }
+
+    /// Add a top-level bar with the given unit
+    pub fn add_with_unit(&self, total: u64, message: &str, unit: ProgressUnit) -> ProgressBar {
+        self.insert(ProgressState::new(total, message, unit), None)
+    }
+
+    fn insert(&self, state: ProgressState, parent: Option<usize>) -> ProgressBar {
+        let depth = state.depth;
+        let mut renderer = self.lock();
+        if renderer.mode == ProgressMode::Log {
+            info!("📊 Starting: {} (0/{})", state.message, state.total);
+        }
+        let id = renderer.insert(state, parent);
+        if renderer.mode == ProgressMode::Interactive {
+            renderer.draw(true);
+        }
+        drop(renderer);
+
+        ProgressBar {
+            id,
+            depth,
+            multi: self.clone(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Renderer> {
+        // A panic while rendering must not take progress reporting down with it
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Default for MultiProgress {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Handle to a single bar inside a `MultiProgress` group
+pub struct ProgressBar {
+    id: usize,
+    depth: usize,
+    multi: MultiProgress,
+}
+
+impl ProgressBar {
+    /// Create a standalone bar counting items
+    pub fn new(total: u64, message: &str) -> Self {
+        MultiProgress::new().add(total, message)
+    }
+
+    /// Create a standalone bar counting bytes
+    pub fn bytes(total: u64, message: &str) -> Self {
+        MultiProgress::new().add_bytes(total, message)
+    }
+
+    /// Create a nested bar drawn below this one
+    pub fn child(&self, total: u64, message: &str, unit: ProgressUnit) -> ProgressBar {
+        let mut state = ProgressState::new(total, message, unit);
+        state.depth = self.depth + 1;
+        self.multi.insert(state, Some(self.id))
+    }
+
+    /// Set the absolute position
+    pub fn set_position(&self, current: u64) {
+        self.modify(|state| state.current = current);
+    }
+
+    /// Advance the position by `delta`
+    pub fn inc(&self, delta: u64) {
+        self.modify(|state| state.current = state.current.saturating_add(delta));
+    }
+
+    /// Change the total, e.g. once a download reports its content length
+    pub fn set_total(&self, total: u64) {
+        self.modify(|state| state.total = total);
+    }
+
+    pub fn set_message(&self, message: &str) {
+        self.modify(|state| state.message = message.to_string());
+    }
+
+    /// Snapshot of the current state
+    pub fn state(&self) -> Option<ProgressState> {
+        let renderer = self.multi.lock();
+        renderer
+            .position(self.id)
+            .map(|index| renderer.bars[index].1.clone())
+    }
+
+    pub fn position(&self) -> u64 {
+        self.state().map(|s| s.current).unwrap_or(0)
+    }
+
+    /// Mark the bar as complete
+    pub fn finish(&self) {
+        let mut renderer = self.multi.lock();
+        let mode = renderer.mode;
+        let Some(state) = renderer.state_mut(self.id) else {
+            return;
+        };
+        if state.finished {
+            return;
+        }
+        state.finished = true;
+        if state.total > 0 {
+            state.current = state.current.max(state.total);
+        }
+
+        match mode {
+            ProgressMode::Interactive => renderer.draw(true),
+            ProgressMode::Log => {
+                let state = renderer.state_mut(self.id).map(|s| s.clone());
+                if let Some(state) = state {
+                    info!(
+                        "✅ Completed: {} ({}) in {:?}",
+                        state.message,
+                        format_counts(&state),
+                        state.started.elapsed()
+                    );
+                }
+                renderer.remove(self.id);
+            }
+            ProgressMode::Hidden => renderer.remove(self.id),
+        }
+    }
+
+    fn modify<F: FnOnce(&mut ProgressState)>(&self, f: F) {
+        let mut renderer = self.multi.lock();
+        let Some(state) = renderer.state_mut(self.id) else {
+            return;
+        };
+        if state.finished {
+            return;
+        }
+        f(state);
+
+        match renderer.mode {
+            ProgressMode::Interactive => renderer.draw(false),
+            ProgressMode::Log => renderer.log_update(self.id),
+            ProgressMode::Hidden => {}
+        }
+    }
+}
+
+impl Drop for ProgressBar {
+    fn drop(&mut self) {
+        // Bars dropped without finishing (e.g. on error) disappear quietly
+        let mut renderer = self.multi.lock();
+        let unfinished = renderer
+            .state_mut(self.id)
+            .map(|state| !state.finished)
+            .unwrap_or(false);
+        if unfinished {
+            renderer.remove(self.id);
+            if renderer.mode == ProgressMode::Interactive {
+                renderer.draw(true);
+            }
+        }
+    }
+}
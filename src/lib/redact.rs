@@ -0,0 +1,316 @@
+// Secret redaction for Ubuntu Config Scripts
+//
+// This module masks secrets before they reach any output, including:
+// - Values of sensitive command line flags (`--token X`, `--password=X`)
+// - Sensitive environment assignments (`GH_RUNNER_TOKEN=...`)
+// - Well-known secret shapes (GitHub tokens, URL credentials, auth headers)
+// - Literal secret values registered at runtime
+
+use anyhow::{Context, Result};
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+/// Replacement text for masked secrets
+pub const MASK: &str = "********";
+
+/// Registered literal secrets shorter than this are ignored to avoid masking common words
+const MIN_LITERAL_SECRET_LEN: usize = 4;
+
+/// Flags whose value is always masked
+pub const DEFAULT_SENSITIVE_FLAGS: [&str; 8] = [
+    "--token",
+    "--password",
+    "--passwd",
+    "--secret",
+    "--api-key",
+    "--access-token",
+    "--auth-token",
+    "--client-secret",
+];
+
+/// Environment variable name patterns whose value is always masked (`*` is a wildcard)
+pub const DEFAULT_SENSITIVE_ENV: [&str; 6] = [
+    "*_TOKEN",
+    "*_PASSWORD",
+    "*_SECRET",
+    "*_API_KEY",
+    "*_ACCESS_KEY",
+    "*_PAT",
+];
+
+/// Secret shapes recognised anywhere in text; a `secret` group limits the mask
+const DEFAULT_PATTERNS: [&str; 4] = [
+    r"\bgh[pousr]_[A-Za-z0-9]{36,}\b",
+    r"\bgithub_pat_[A-Za-z0-9_]{22,}\b",
+    r"://[^/\s:@]+:(?P<secret>[^/\s@]+)@",
+    r"(?i)\bauthorization:\s*(?:bearer|basic|token)\s+(?P<secret>\S+)",
+];
+
+/// Configurable secret masker
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    flags: Vec<String>,
+    env_patterns: Vec<String>,
+    patterns: Vec<Regex>,
+    literals: Vec<String>,
+    flag_regex: Option<Regex>,
+    env_regex: Option<Regex>,
+    env_name_regex: Option<Regex>,
+}
+
+impl Redactor {
+    /// Create a redactor that masks nothing
+    pub fn empty() -> Self {
+        Self {
+            flags: Vec::new(),
+            env_patterns: Vec::new(),
+            patterns: Vec::new(),
+            literals: Vec::new(),
+            flag_regex: None,
+            env_regex: None,
+            env_name_regex: None,
+        }
+    }
+
+    /// Create a redactor with the default flags, environment patterns and secret shapes
+    pub fn new() -> Self {
+        let mut redactor = Self::empty();
+        redactor.flags = DEFAULT_SENSITIVE_FLAGS
+            .iter()
+            .map(|f| f.to_string())
+            .collect();
+        redactor.env_patterns = DEFAULT_SENSITIVE_ENV
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        redactor.patterns = DEFAULT_PATTERNS
+            .iter()
+            .map(|p| Regex::new(p).expect("default redaction pattern is valid"))
+            .collect();
+        redactor.rebuild();
+        redactor
+    }
+
+    /// Mask the value following `flag` (e.g. `--registration-token`)
+    pub fn flag(mut self, flag: &str) -> Self {
+        self.flags.push(flag.to_string());
+        self.rebuild();
+        self
+    }
+
+    /// Mask values of environment variables matching `pattern` (e.g. `*_TOKEN`)
+    pub fn env_var(mut self, pattern: &str) -> Self {
+        self.env_patterns.push(pattern.to_string());
+        self.rebuild();
+        self
+    }
+
+    /// Mask text matching a regular expression, or only its `secret` group if present
+    pub fn pattern(mut self, pattern: &str) -> Result<Self> {
+        let regex = Regex::new(pattern)
+            .with_context(|| format!("Invalid redaction pattern: {}", pattern))?;
+        self.patterns.push(regex);
+        Ok(self)
+    }
+
+    /// Mask every occurrence of a known secret value
+    pub fn secret(mut self, value: &str) -> Self {
+        self.add_secret(value);
+        self
+    }
+
+    fn add_secret(&mut self, value: &str) {
+        if value.len() >= MIN_LITERAL_SECRET_LEN && !self.literals.iter().any(|l| l == value) {
+            self.literals.push(value.to_string());
+            // Longest first so overlapping secrets are fully masked
+            self.literals.sort_by_key(|l| std::cmp::Reverse(l.len()));
+        }
+    }
+
+    fn rebuild(&mut self) {
+        self.flag_regex = if self.flags.is_empty() {
+            None
+        } else {
+            let flags: Vec<String> = self.flags.iter().map(|f| regex::escape(f)).collect();
+            Regex::new(&format!(
+                r#"((?:^|\s)(?:{}))(=|\s+)("[^"]*"|'[^']*'|\S+)"#,
+                flags.join("|")
+            ))
+            .ok()
+        };
+
+        let names: Vec<String> = self.env_patterns.iter().map(|p| env_pattern(p)).collect();
+        (self.env_regex, self.env_name_regex) = if names.is_empty() {
+            (None, None)
+        } else {
+            let names = names.join("|");
+            (
+                Regex::new(&format!(r#"\b((?:{}))=("[^"]*"|'[^']*'|\S+)"#, names)).ok(),
+                Regex::new(&format!("^(?:{})$", names)).ok(),
+            )
+        };
+    }
+
+    /// Whether `flag` is a sensitive flag
+    pub fn is_sensitive_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+
+    /// Whether environment variable `name` holds a secret
+    pub fn is_sensitive_env(&self, name: &str) -> bool {
+        self.env_name_regex
+            .as_ref()
+            .is_some_and(|re| re.is_match(name))
+    }
+
+    /// Mask secrets in free-form text such as a log message
+    pub fn redact_text(&self, text: &str) -> String {
+        let mut output = text.to_string();
+
+        for literal in &self.literals {
+            if output.contains(literal.as_str()) {
+                output = output.replace(literal.as_str(), MASK);
+            }
+        }
+
+        if let Some(ref re) = self.flag_regex {
+            output = re
+                .replace_all(&output, |caps: &Captures| {
+                    format!("{}{}{}", &caps[1], &caps[2], MASK)
+                })
+                .into_owned();
+        }
+
+        if let Some(ref re) = self.env_regex {
+            output = re
+                .replace_all(&output, |caps: &Captures| format!("{}={}", &caps[1], MASK))
+                .into_owned();
+        }
+
+        for re in &self.patterns {
+            output = re
+                .replace_all(&output, |caps: &Captures| match caps.name("secret") {
+                    Some(secret) => {
+                        let whole = caps.get(0).expect("match has group 0");
+                        let start = secret.start() - whole.start();
+                        let end = secret.end() - whole.start();
+                        let text = whole.as_str();
+                        format!("{}{}{}", &text[..start], MASK, &text[end..])
+                    }
+                    None => MASK.to_string(),
+                })
+                .into_owned();
+        }
+
+        output
+    }
+
+    /// Mask secrets in an argument vector, one argument at a time
+    pub fn redact_args(&self, args: &[&str]) -> Vec<String> {
+        let mut output = Vec::with_capacity(args.len());
+        let mut mask_next = false;
+
+        for arg in args {
+            if mask_next {
+                output.push(MASK.to_string());
+                mask_next = false;
+                continue;
+            }
+
+            if self.is_sensitive_flag(arg) {
+                mask_next = true;
+                output.push(arg.to_string());
+                continue;
+            }
+
+            if let Some((name, _)) = arg.split_once('=') {
+                if self.is_sensitive_flag(name) || self.is_sensitive_env(name) {
+                    output.push(format!("{}={}", name, MASK));
+                    continue;
+                }
+            }
+
+            output.push(self.redact_text(arg));
+        }
+
+        output
+    }
+
+    /// Render a command line with secrets masked
+    pub fn redact_command(&self, cmd: &[&str]) -> String {
+        self.redact_args(cmd).join(" ")
+    }
+
+    /// Mask the values of sensitive variables in an environment map
+    pub fn redact_env(&self, env: &HashMap<String, String>) -> HashMap<String, String> {
+        env.iter()
+            .map(|(name, value)| {
+                let value = if self.is_sensitive_env(name) {
+                    MASK.to_string()
+                } else {
+                    self.redact_text(value)
+                };
+                (name.clone(), value)
+            })
+            .collect()
+    }
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Translate an environment name pattern with `*` wildcards into a regex fragment
+fn env_pattern(pattern: &str) -> String {
+    pattern
+        .split('*')
+        .map(regex::escape)
+        .collect::<Vec<_>>()
+        .join("[A-Za-z0-9_]*")
+}
+
+fn global() -> &'static RwLock<Redactor> {
+    static REDACTOR: OnceLock<RwLock<Redactor>> = OnceLock::new();
+    REDACTOR.get_or_init(|| RwLock::new(Redactor::new()))
+}
+
+/// Replace the process-wide redactor used by all log output
+pub fn set_redactor(redactor: Redactor) {
+    if let Ok(mut active) = global().write() {
+        *active = redactor;
+    }
+}
+
+/// Copy of the process-wide redactor
+pub fn redactor() -> Redactor {
+    global()
+        .read()
+        .map(|r| r.clone())
+        .unwrap_or_else(|_| Redactor::new())
+}
+
+/// Register a secret value (e.g. a token read from a file) to be masked everywhere
+pub fn register_secret(value: &str) {
+    if let Ok(mut active) = global().write() {
+        active.add_secret(value);
+    }
+}
+
+/// Mask secrets in text using the process-wide redactor
+pub fn redact(text: &str) -> String {
+    match global().read() {
+        Ok(r) => r.redact_text(text),
+        Err(_) => text.to_string(),
+    }
+}
+
+/// Render a command line with secrets masked using the process-wide redactor
+pub fn redact_command(cmd: &[&str]) -> String {
+    match global().read() {
+        Ok(r) => r.redact_command(cmd),
+        Err(_) => cmd.join(" "),
+    }
+}
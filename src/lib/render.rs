@@ -0,0 +1,422 @@
+// Tabular output rendering for Ubuntu Config Scripts
+//
+// This module renders row data in several output formats including:
+// - Box-drawing tables sized by terminal display width (UTF-8 aware)
+// - Markdown, CSV and JSON from the same rows
+// - Per-column alignment, truncation and wrapping
+// - Output format selection via `--format`
+
+use anyhow::{anyhow, Result};
+use std::fmt;
+use std::str::FromStr;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Narrowest a column is shrunk to when fitting a table into a width limit
+const MIN_COLUMN_WIDTH: usize = 3;
+
+/// Output format selected with `--format`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Markdown,
+    Csv,
+    Json,
+}
+
+impl OutputFormat {
+    pub const NAMES: [&'static str; 4] = ["table", "markdown", "csv", "json"];
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "table" | "text" => Ok(OutputFormat::Table),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            "csv" => Ok(OutputFormat::Csv),
+            "json" => Ok(OutputFormat::Json),
+            _ => Err(anyhow!(
+                "Invalid output format: {} (expected one of: {})",
+                s,
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OutputFormat::Table => "table",
+            OutputFormat::Markdown => "markdown",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Json => "json",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Horizontal alignment of a column
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Align {
+    #[default]
+    Left,
+    Right,
+    Center,
+}
+
+/// Column definition
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub header: String,
+    pub align: Align,
+    pub max_width: Option<usize>,
+    pub wrap: bool,
+}
+
+impl Column {
+    pub fn new(header: &str) -> Self {
+        Self {
+            header: header.to_string(),
+            align: Align::Left,
+            max_width: None,
+            wrap: false,
+        }
+    }
+
+    pub fn align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Limit the column to `width` display columns
+    pub fn max_width(mut self, width: usize) -> Self {
+        self.max_width = Some(width);
+        self
+    }
+
+    /// Wrap overlong cells onto several lines instead of truncating them
+    pub fn wrap(mut self) -> Self {
+        self.wrap = true;
+        self
+    }
+}
+
+/// Row data plus column definitions, renderable in any `OutputFormat`
+#[derive(Debug, Clone)]
+pub struct Table {
+    columns: Vec<Column>,
+    rows: Vec<Vec<String>>,
+    max_width: Option<usize>,
+}
+
+impl Table {
+    pub fn new(columns: Vec<Column>) -> Self {
+        Self {
+            columns,
+            rows: Vec::new(),
+            max_width: None,
+        }
+    }
+
+    /// Create a table with left-aligned columns
+    pub fn from_headers(headers: &[&str]) -> Self {
+        Self::new(headers.iter().map(|h| Column::new(h)).collect())
+    }
+
+    /// Append a row; missing cells render empty and extra cells are ignored
+    pub fn add_row(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    pub fn rows(mut self, rows: Vec<Vec<String>>) -> Self {
+        self.rows.extend(rows);
+        self
+    }
+
+    /// Limit box tables to `width` display columns in total
+    pub fn max_width(mut self, width: usize) -> Self {
+        self.max_width = Some(width);
+        self
+    }
+
+    /// Limit box tables to the width of the terminal, if there is one
+    pub fn fit_terminal(self) -> Self {
+        match terminal_size::terminal_size() {
+            Some((terminal_size::Width(w), _)) => self.max_width(w as usize),
+            None => self,
+        }
+    }
+
+    pub fn render(&self, format: OutputFormat) -> String {
+        match format {
+            OutputFormat::Table => self.render_box(),
+            OutputFormat::Markdown => self.render_markdown(),
+            OutputFormat::Csv => self.render_csv(),
+            OutputFormat::Json => self.render_json(),
+        }
+    }
+
+    fn cell(&self, row: &[String], index: usize) -> String {
+        row.get(index).cloned().unwrap_or_default()
+    }
+
+    /// Final width of each column after applying column and table limits
+    fn column_widths(&self) -> Vec<usize> {
+        let mut widths: Vec<usize> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let natural = self
+                    .rows
+                    .iter()
+                    .map(|row| self.cell(row, i))
+                    .chain(std::iter::once(column.header.clone()))
+                    .flat_map(|cell| cell.lines().map(display_width).collect::<Vec<_>>())
+                    .max()
+                    .unwrap_or(0);
+                match column.max_width {
+                    Some(max) => natural.min(max.max(1)),
+                    None => natural,
+                }
+            })
+            .collect();
+
+        if let Some(limit) = self.max_width {
+            // Each column costs its width plus 3 for padding and border, plus the left edge
+            let overhead = widths.len() * 3 + 1;
+            while widths.iter().sum::<usize>() + overhead > limit {
+                let (widest, width) = widths
+                    .iter()
+                    .copied()
+                    .enumerate()
+                    .max_by_key(|(_, w)| *w)
+                    .unwrap_or((0, 0));
+                if width <= MIN_COLUMN_WIDTH {
+                    break;
+                }
+                widths[widest] -= 1;
+            }
+        }
+
+        widths
+    }
+
+    /// Lay out a cell into lines no wider than `width`
+    fn layout_cell(&self, text: &str, column: &Column, width: usize) -> Vec<String> {
+        let mut lines = Vec::new();
+        for line in text.lines() {
+            if column.wrap {
+                lines.extend(wrap_to_width(line, width));
+            } else {
+                lines.push(truncate_to_width(line, width));
+            }
+        }
+        if lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines
+    }
+
+    fn render_box(&self) -> String {
+        let widths = self.column_widths();
+        let border = |left: char, mid: char, right: char| {
+            let segments: Vec<String> = widths.iter().map(|w| "─".repeat(w + 2)).collect();
+            format!("{}{}{}", left, segments.join(&mid.to_string()), right)
+        };
+
+        let mut output = String::new();
+        output.push_str(&border('┌', '┬', '┐'));
+        output.push('\n');
+
+        let headers: Vec<String> = self.columns.iter().map(|c| c.header.clone()).collect();
+        output.push_str(&self.render_box_row(&headers, &widths));
+        output.push_str(&border('├', '┼', '┤'));
+        output.push('\n');
+
+        for row in &self.rows {
+            output.push_str(&self.render_box_row(row, &widths));
+        }
+
+        output.push_str(&border('└', '┴', '┘'));
+        output
+    }
+
+    fn render_box_row(&self, row: &[String], widths: &[usize]) -> String {
+        let cells: Vec<Vec<String>> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| self.layout_cell(&self.cell(row, i), column, widths[i]))
+            .collect();
+        let height = cells.iter().map(Vec::len).max().unwrap_or(1);
+
+        let mut output = String::new();
+        for line in 0..height {
+            output.push('│');
+            for (i, column) in self.columns.iter().enumerate() {
+                let text = cells[i].get(line).map(String::as_str).unwrap_or("");
+                output.push(' ');
+                output.push_str(&pad_to_width(text, widths[i], column.align));
+                output.push_str(" │");
+            }
+            output.push('\n');
+        }
+        output
+    }
+
+    fn render_markdown(&self) -> String {
+        let escape = |s: &str| s.replace('|', "\\|").replace('\n', "<br>");
+
+        let mut output = String::new();
+        let headers: Vec<String> = self.columns.iter().map(|c| escape(&c.header)).collect();
+        output.push_str(&format!("| {} |\n", headers.join(" | ")));
+
+        let separators: Vec<&str> = self
+            .columns
+            .iter()
+            .map(|c| match c.align {
+                Align::Left => "---",
+                Align::Right => "---:",
+                Align::Center => ":---:",
+            })
+            .collect();
+        output.push_str(&format!("| {} |\n", separators.join(" | ")));
+
+        for row in &self.rows {
+            let cells: Vec<String> = (0..self.columns.len())
+                .map(|i| escape(&self.cell(row, i)))
+                .collect();
+            output.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+        output
+    }
+
+    fn render_csv(&self) -> String {
+        let mut output = String::new();
+        let headers: Vec<String> = self.columns.iter().map(|c| csv_field(&c.header)).collect();
+        output.push_str(&headers.join(","));
+        output.push('\n');
+
+        for row in &self.rows {
+            let cells: Vec<String> = (0..self.columns.len())
+                .map(|i| csv_field(&self.cell(row, i)))
+                .collect();
+            output.push_str(&cells.join(","));
+            output.push('\n');
+        }
+        output
+    }
+
+    fn render_json(&self) -> String {
+        // Maps keep insertion order, so keys follow the column order
+        let objects: Vec<serde_json::Map<String, serde_json::Value>> = self
+            .rows
+            .iter()
+            .map(|row| {
+                self.columns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, c)| (c.header.clone(), self.cell(row, i).into()))
+                    .collect()
+            })
+            .collect();
+        serde_json::to_string_pretty(&objects).expect("string cells always serialize")
+    }
+}
+
+/// Number of terminal columns `s` occupies
+pub fn display_width(s: &str) -> usize {
+    UnicodeWidthStr::width(s)
+}
+
+/// Cut `s` to at most `width` display columns, marking the cut with `…`
+pub fn truncate_to_width(s: &str, width: usize) -> String {
+    if display_width(s) <= width {
+        return s.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+
+    let mut output = String::new();
+    let mut used = 0;
+    for c in s.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > width - 1 {
+            break;
+        }
+        output.push(c);
+        used += w;
+    }
+    output.push('…');
+    output
+}
+
+/// Break `s` into lines of at most `width` display columns, preferring word boundaries
+pub fn wrap_to_width(s: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    let mut current_width = 0;
+
+    for word in s.split(' ') {
+        let word_width = display_width(word);
+        let separator = usize::from(!current.is_empty());
+
+        if current_width + separator + word_width <= width {
+            if separator == 1 {
+                current.push(' ');
+            }
+            current.push_str(word);
+            current_width += separator + word_width;
+            continue;
+        }
+
+        if !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+            current_width = 0;
+        }
+
+        // Words longer than the line are split at character boundaries
+        for c in word.chars() {
+            let w = c.width().unwrap_or(0);
+            if current_width + w > width && !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+                current_width = 0;
+            }
+            current.push(c);
+            current_width += w;
+        }
+    }
+
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Pad `s` with spaces to exactly `width` display columns
+pub fn pad_to_width(s: &str, width: usize, align: Align) -> String {
+    let fill = width.saturating_sub(display_width(s));
+    match align {
+        Align::Left => format!("{}{}", s, " ".repeat(fill)),
+        Align::Right => format!("{}{}", " ".repeat(fill), s),
+        Align::Center => {
+            let left = fill / 2;
+            format!("{}{}{}", " ".repeat(left), s, " ".repeat(fill - left))
+        }
+    }
+}
+
+/// Quote a CSV field when it contains separators, quotes or newlines
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
@@ -0,0 +1,655 @@
+// Software bill of materials for Ubuntu Config Scripts
+//
+// This module describes the crates compiled into the deployed binaries as
+// an SBOM, including:
+// - CycloneDX 1.5 JSON and SPDX 2.3 JSON documents
+// - Name, version, purl, license and SHA-256 checksum of every component
+// - Dependency relationships taken from Cargo.lock
+// - Optionally the Debian packages providing the commands the scripts run
+//
+// Components are the packages reachable from the workspace members when
+// building for one target (the build target by default); crates only
+// reached through `[dev-dependencies]` or through `[target.'cfg(..)']`
+// tables of other platforms are left out.
+
+use crate::lib::license::{LicenseExpr, LicenseField, LicenseSources};
+use crate::lib::lockfile::{DependencyGraph, PackageSource};
+use anyhow::{anyhow, Context, Result};
+use cfg_expr::targets::{get_builtin_target_by_triple, TargetInfo};
+use cfg_expr::{Expression, Predicate};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command as StdCommand;
+use std::str::FromStr;
+
+/// SBOM document format
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SbomFormat {
+    #[default]
+    CycloneDx,
+    Spdx,
+}
+
+impl SbomFormat {
+    pub const NAMES: [&'static str; 2] = ["cyclonedx", "spdx"];
+    pub const ALL: [SbomFormat; 2] = [SbomFormat::CycloneDx, SbomFormat::Spdx];
+
+    /// Conventional file name for the document
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            SbomFormat::CycloneDx => "sbom.cdx.json",
+            SbomFormat::Spdx => "sbom.spdx.json",
+        }
+    }
+}
+
+impl FromStr for SbomFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "cyclonedx" | "cdx" => Ok(SbomFormat::CycloneDx),
+            "spdx" => Ok(SbomFormat::Spdx),
+            _ => Err(anyhow!(
+                "Invalid SBOM format: {} (expected one of: {})",
+                s,
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SbomFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SbomFormat::CycloneDx => "cyclonedx",
+            SbomFormat::Spdx => "spdx",
+        };
+        f.write_str(name)
+    }
+}
+
+/// What a component is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentKind {
+    /// A workspace member or a system package providing commands
+    Application,
+    /// A crate linked into the binaries
+    Library,
+}
+
+/// One entry of the bill of materials
+#[derive(Debug, Clone, PartialEq)]
+pub struct SbomComponent {
+    pub name: String,
+    pub version: String,
+    pub kind: ComponentKind,
+    pub purl: String,
+    /// Normalized SPDX expression
+    pub license: Option<String>,
+    /// SHA-256 of the downloaded archive (`checksum` in Cargo.lock)
+    pub sha256: Option<String>,
+    /// Where the source can be downloaded from
+    pub download_location: Option<String>,
+    /// Indexes of the components this one depends on
+    pub dependencies: Vec<usize>,
+}
+
+/// Installed Debian package that provides a command
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SystemPackage {
+    pub name: String,
+    pub version: String,
+    pub architecture: String,
+    /// `ID` from /etc/os-release (`ubuntu`)
+    pub vendor: String,
+    /// `ID-VERSION_ID` from /etc/os-release (`ubuntu-24.04`)
+    pub distro: Option<String>,
+}
+
+impl SystemPackage {
+    pub fn purl(&self) -> String {
+        let mut purl = format!(
+            "pkg:deb/{}/{}@{}?arch={}",
+            self.vendor,
+            purl_encode(&self.name),
+            purl_encode(&self.version),
+            purl_encode(&self.architecture)
+        );
+        if let Some(ref distro) = self.distro {
+            purl.push_str(&format!("&distro={}", purl_encode(distro)));
+        }
+        purl
+    }
+}
+
+/// Debian packages owning the given commands, as installed on this host
+///
+/// Commands that are missing or not installed through dpkg (e.g. cargo from
+/// rustup) are skipped, so the result is empty on hosts without dpkg.
+pub fn installed_system_packages(commands: &[&str]) -> Vec<SystemPackage> {
+    let (vendor, distro) = os_release();
+    let mut packages = BTreeSet::new();
+    for command in commands {
+        let Ok(path) = which::which(command) else {
+            continue;
+        };
+        let Some(name) = dpkg_owner(&path) else {
+            continue;
+        };
+        let output = StdCommand::new("dpkg-query")
+            .args(["-W", "-f", "${Version}\t${Architecture}", &name])
+            .output();
+        let Ok(output) = output else {
+            continue;
+        };
+        let fields = String::from_utf8_lossy(&output.stdout).to_string();
+        if let Some((version, architecture)) = fields.split_once('\t') {
+            packages.insert(SystemPackage {
+                name,
+                version: version.to_string(),
+                architecture: architecture.to_string(),
+                vendor: vendor.clone(),
+                distro: distro.clone(),
+            });
+        }
+    }
+    packages.into_iter().collect()
+}
+
+/// Package owning a file, trying the merged-/usr alias as dpkg records either
+fn dpkg_owner(path: &Path) -> Option<String> {
+    let mut candidates = vec![path.to_path_buf()];
+    if let Ok(canonical) = path.canonicalize() {
+        candidates.push(canonical);
+    }
+    for candidate in candidates.clone() {
+        if let Ok(rest) = candidate.strip_prefix("/usr") {
+            candidates.push(Path::new("/").join(rest));
+        } else if candidate.starts_with("/bin") || candidate.starts_with("/sbin") {
+            candidates.push(Path::new("/usr").join(candidate.strip_prefix("/").ok()?));
+        }
+    }
+
+    candidates.iter().find_map(|candidate| {
+        let output = StdCommand::new("dpkg-query")
+            .arg("-S")
+            .arg(candidate)
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        // `coreutils: /usr/bin/ls` or `git, git-man: /usr/share/...`
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let line = stdout.lines().find(|l| !l.starts_with("diversion by"))?;
+        let (owners, _) = line.split_once(": ")?;
+        let owner = owners.split(", ").next()?;
+        Some(owner.split(':').next()?.to_string())
+    })
+}
+
+/// `ID` and `ID-VERSION_ID` from /etc/os-release, `debian` when unknown
+fn os_release() -> (String, Option<String>) {
+    let content = std::fs::read_to_string("/etc/os-release").unwrap_or_default();
+    let field = |key: &str| {
+        content.lines().find_map(|line| {
+            let value = line.strip_prefix(key)?.strip_prefix('=')?;
+            Some(value.trim_matches('"').to_string())
+        })
+    };
+    let vendor = field("ID").unwrap_or_else(|| "debian".to_string());
+    let distro = field("VERSION_ID").map(|version| format!("{}-{}", vendor, version));
+    (vendor, distro)
+}
+
+/// Percent-encode everything but the purl unreserved characters
+fn purl_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'.' | b'-' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Package URL of a locked crate
+pub fn cargo_purl(name: &str, version: &str, source: &PackageSource) -> String {
+    let purl = format!("pkg:cargo/{}@{}", purl_encode(name), purl_encode(version));
+    match source {
+        PackageSource::Path => purl,
+        source if source.is_crates_io() => purl,
+        PackageSource::Registry(url) => {
+            let url = url.split_once('+').map_or(url.as_str(), |(_, url)| url);
+            format!("{}?repository_url={}", purl, purl_encode(url))
+        }
+        PackageSource::Git { url, rev } => {
+            let vcs = match rev {
+                Some(rev) => format!("git+{}@{}", url, rev),
+                None => format!("git+{}", url),
+            };
+            format!("{}?vcs_url={}", purl, purl_encode(&vcs))
+        }
+        PackageSource::Other(_) => purl,
+    }
+}
+
+/// Names a manifest lists in the given dependency tables, including `[target.*]` ones
+/// Target triple the crate was built for
+pub const BUILD_TARGET: &str = env!("UCS_BUILD_TARGET");
+
+/// Whether a `[target.<key>]` table applies when building for `target`
+fn target_applies(key: &str, target: &TargetInfo) -> bool {
+    if !key.starts_with("cfg(") {
+        return key == target.triple.as_str();
+    }
+    match Expression::parse(key) {
+        Ok(expr) => expr.eval(|predicate| match predicate {
+            Predicate::Target(predicate) => predicate.matches(target),
+            _ => false,
+        }),
+        // Keep dependencies whose condition cannot be read
+        Err(_) => true,
+    }
+}
+
+/// Package names declared in `tables` of a manifest, including the target
+/// tables that apply to `target`
+fn manifest_dependencies(
+    manifest: &toml::Value,
+    tables: &[&str],
+    target: &TargetInfo,
+) -> BTreeSet<String> {
+    let mut sections = vec![manifest];
+    if let Some(targets) = manifest.get("target").and_then(|t| t.as_table()) {
+        sections.extend(
+            targets
+                .iter()
+                .filter(|(key, _)| target_applies(key, target))
+                .map(|(_, section)| section),
+        );
+    }
+    let mut names = BTreeSet::new();
+    for section in sections {
+        for table in tables {
+            let Some(entries) = section.get(*table).and_then(|t| t.as_table()) else {
+                continue;
+            };
+            for (key, value) in entries {
+                // `alias = { package = "real-name", ... }`
+                let name = value.get("package").and_then(|p| p.as_str()).unwrap_or(key);
+                names.insert(name.to_string());
+            }
+        }
+    }
+    names
+}
+
+/// Packages the workspace members need outside of tests and benchmarks when
+/// building for `target`, and the dependency edges followed for each
+///
+/// Packages whose manifest is not available (e.g. git crates, or registry
+/// crates missing from the cache) keep every edge Cargo.lock records.
+fn runtime_packages(
+    graph: &DependencyGraph,
+    sources: &LicenseSources,
+    target: &TargetInfo,
+) -> (Vec<bool>, Vec<Vec<usize>>) {
+    let count = graph.packages().len();
+    let mut reached = vec![false; count];
+    let mut edges = vec![Vec::new(); count];
+    let mut queue: VecDeque<usize> = graph.roots().iter().copied().collect();
+    for &root in graph.roots() {
+        reached[root] = true;
+    }
+    while let Some(index) = queue.pop_front() {
+        let package = graph.package(index);
+        let used = sources.manifest_of(package).map(|manifest| {
+            manifest_dependencies(&manifest, &["dependencies", "build-dependencies"], target)
+        });
+        for &dependency in &package.dependencies {
+            let name = &graph.package(dependency).name;
+            if used.as_ref().is_some_and(|used| !used.contains(name)) {
+                continue;
+            }
+            edges[index].push(dependency);
+            if !reached[dependency] {
+                reached[dependency] = true;
+                queue.push_back(dependency);
+            }
+        }
+    }
+    (reached, edges)
+}
+
+/// Bill of materials for a workspace
+#[derive(Debug, Clone)]
+pub struct Sbom {
+    /// Name and version of the described application (the first workspace member)
+    pub name: String,
+    pub version: String,
+    /// Components; the first one is the described application
+    pub components: Vec<SbomComponent>,
+    serial_number: String,
+    timestamp: String,
+}
+
+impl Sbom {
+    /// Components for every package the workspace members need at runtime
+    /// on the build target
+    pub fn from_graph(graph: &DependencyGraph, sources: &LicenseSources) -> Result<Self> {
+        Self::for_target(graph, sources, BUILD_TARGET)
+    }
+
+    /// Components for every package the workspace members need at runtime
+    /// on `triple` (e.g. `aarch64-unknown-linux-gnu`)
+    pub fn for_target(
+        graph: &DependencyGraph,
+        sources: &LicenseSources,
+        triple: &str,
+    ) -> Result<Self> {
+        let target = get_builtin_target_by_triple(triple)
+            .ok_or_else(|| anyhow!("Unknown target triple: {}", triple))?;
+        let primary = *graph
+            .roots()
+            .first()
+            .ok_or_else(|| anyhow!("Cargo.lock has no workspace member to describe"))?;
+        let (reached, edges) = runtime_packages(graph, sources, target);
+
+        // The described application first, then the rest by name and version
+        let mut rest: Vec<usize> = (0..graph.packages().len())
+            .filter(|&i| reached[i] && i != primary)
+            .collect();
+        rest.sort_by(|&a, &b| {
+            let (a, b) = (graph.package(a), graph.package(b));
+            a.name.cmp(&b.name).then_with(|| a.version.cmp(&b.version))
+        });
+        let mut order = vec![primary];
+        order.extend(rest);
+        let mut position = vec![None; graph.packages().len()];
+        for (n, &index) in order.iter().enumerate() {
+            position[index] = Some(n);
+        }
+
+        let components = order
+            .iter()
+            .map(|&index| {
+                let package = graph.package(index);
+                let version = package.version.to_string();
+                let license = match sources.license_of(package) {
+                    LicenseField::Expression(text) => {
+                        LicenseExpr::parse(&text).ok().map(|expr| expr.to_string())
+                    }
+                    _ => None,
+                };
+                let download_location = match package.source {
+                    ref source if source.is_crates_io() => Some(format!(
+                        "https://crates.io/api/v1/crates/{}/{}/download",
+                        package.name, version
+                    )),
+                    PackageSource::Git {
+                        ref url,
+                        rev: Some(ref rev),
+                    } => Some(format!("git+{}@{}", url, rev)),
+                    _ => None,
+                };
+                SbomComponent {
+                    name: package.name.clone(),
+                    purl: cargo_purl(&package.name, &version, &package.source),
+                    version,
+                    kind: if graph.roots().contains(&index) {
+                        ComponentKind::Application
+                    } else {
+                        ComponentKind::Library
+                    },
+                    license,
+                    sha256: package.checksum.clone(),
+                    download_location,
+                    dependencies: edges[index].iter().filter_map(|&d| position[d]).collect(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        Ok(Self {
+            name: components[0].name.clone(),
+            version: components[0].version.clone(),
+            components,
+            serial_number: new_uuid(),
+            timestamp: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        })
+    }
+
+    /// Add system packages as dependencies of the described application
+    pub fn system_packages(mut self, packages: &[SystemPackage]) -> Self {
+        for package in packages {
+            let index = self.components.len();
+            self.components.push(SbomComponent {
+                name: package.name.clone(),
+                version: package.version.clone(),
+                kind: ComponentKind::Application,
+                purl: package.purl(),
+                license: None,
+                sha256: None,
+                download_location: None,
+                dependencies: Vec::new(),
+            });
+            self.components[0].dependencies.push(index);
+        }
+        self
+    }
+
+    /// Use a fixed serial number (a UUID) instead of a random one
+    pub fn serial_number(mut self, uuid: &str) -> Self {
+        self.serial_number = uuid.to_string();
+        self
+    }
+
+    /// Use a fixed creation time (RFC 3339) instead of now
+    pub fn timestamp(mut self, timestamp: &str) -> Self {
+        self.timestamp = timestamp.to_string();
+        self
+    }
+
+    /// Unique references per component: purls, disambiguated if two collide
+    fn references(&self, make: impl Fn(&SbomComponent) -> String) -> Vec<String> {
+        let mut used = HashSet::new();
+        self.components
+            .iter()
+            .map(|component| {
+                let base = make(component);
+                let mut reference = base.clone();
+                let mut n = 1;
+                while !used.insert(reference.clone()) {
+                    n += 1;
+                    reference = format!("{}-{}", base, n);
+                }
+                reference
+            })
+            .collect()
+    }
+
+    /// CycloneDX 1.5 JSON document
+    pub fn to_cyclonedx(&self) -> Value {
+        let refs = self.references(|c| c.purl.clone());
+        let component = |index: usize| {
+            let c = &self.components[index];
+            let mut value = json!({
+                "type": match c.kind {
+                    ComponentKind::Application => "application",
+                    ComponentKind::Library => "library",
+                },
+                "bom-ref": refs[index],
+                "name": c.name,
+                "version": c.version,
+                "purl": c.purl,
+            });
+            if let Some(ref license) = c.license {
+                value["licenses"] = json!([{ "expression": license }]);
+            }
+            if let Some(ref sha256) = c.sha256 {
+                value["hashes"] = json!([{ "alg": "SHA-256", "content": sha256 }]);
+            }
+            value
+        };
+
+        json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": "1.5",
+            "serialNumber": format!("urn:uuid:{}", self.serial_number),
+            "version": 1,
+            "metadata": {
+                "timestamp": self.timestamp,
+                "tools": {
+                    "components": [{
+                        "type": "application",
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION"),
+                    }],
+                },
+                "component": component(0),
+            },
+            "components": (1..self.components.len()).map(component).collect::<Vec<_>>(),
+            "dependencies": self
+                .components
+                .iter()
+                .enumerate()
+                .map(|(n, c)| json!({
+                    "ref": refs[n],
+                    "dependsOn": c.dependencies.iter().map(|&d| &refs[d]).collect::<Vec<_>>(),
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    /// SPDX 2.3 JSON document
+    pub fn to_spdx(&self) -> Value {
+        let ids = self.references(|c| {
+            let id: String = format!("{}-{}", c.name, c.version)
+                .chars()
+                .map(|ch| {
+                    if ch.is_ascii_alphanumeric() || ch == '.' {
+                        ch
+                    } else {
+                        '-'
+                    }
+                })
+                .collect();
+            format!("SPDXRef-Package-{}", id)
+        });
+        let packages: Vec<Value> = self
+            .components
+            .iter()
+            .enumerate()
+            .map(|(n, c)| {
+                let mut value = json!({
+                    "SPDXID": ids[n],
+                    "name": c.name,
+                    "versionInfo": c.version,
+                    "downloadLocation": c.download_location.as_deref().unwrap_or("NOASSERTION"),
+                    "filesAnalyzed": false,
+                    "licenseConcluded": "NOASSERTION",
+                    "licenseDeclared": c.license.as_deref().unwrap_or("NOASSERTION"),
+                    "copyrightText": "NOASSERTION",
+                    "externalRefs": [{
+                        "referenceCategory": "PACKAGE-MANAGER",
+                        "referenceType": "purl",
+                        "referenceLocator": c.purl,
+                    }],
+                    "primaryPackagePurpose": match c.kind {
+                        ComponentKind::Application => "APPLICATION",
+                        ComponentKind::Library => "LIBRARY",
+                    },
+                });
+                if let Some(ref sha256) = c.sha256 {
+                    value["checksums"] =
+                        json!([{ "algorithm": "SHA256", "checksumValue": sha256 }]);
+                }
+                value
+            })
+            .collect();
+
+        let mut relationships = vec![json!({
+            "spdxElementId": "SPDXRef-DOCUMENT",
+            "relationshipType": "DESCRIBES",
+            "relatedSpdxElement": ids[0],
+        })];
+        for (n, component) in self.components.iter().enumerate() {
+            for &dependency in &component.dependencies {
+                relationships.push(json!({
+                    "spdxElementId": ids[n],
+                    "relationshipType": "DEPENDS_ON",
+                    "relatedSpdxElement": ids[dependency],
+                }));
+            }
+        }
+
+        json!({
+            "spdxVersion": "SPDX-2.3",
+            "dataLicense": "CC0-1.0",
+            "SPDXID": "SPDXRef-DOCUMENT",
+            "name": format!("{}-{}", self.name, self.version),
+            "documentNamespace": format!(
+                "https://spdx.org/spdxdocs/{}-{}-{}",
+                self.name, self.version, self.serial_number
+            ),
+            "creationInfo": {
+                "created": self.timestamp,
+                "creators": [format!(
+                    "Tool: {}-{}",
+                    env!("CARGO_PKG_NAME"),
+                    env!("CARGO_PKG_VERSION")
+                )],
+            },
+            "packages": packages,
+            "relationships": relationships,
+        })
+    }
+
+    pub fn render(&self, format: SbomFormat) -> String {
+        let document = match format {
+            SbomFormat::CycloneDx => self.to_cyclonedx(),
+            SbomFormat::Spdx => self.to_spdx(),
+        };
+        let mut json = serde_json::to_string_pretty(&document).unwrap_or_default();
+        json.push('\n');
+        json
+    }
+
+    /// Write the document in every format into a directory, returning the paths
+    pub fn write_all<P: AsRef<Path>>(&self, dir: P) -> Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+        for format in SbomFormat::ALL {
+            let path = dir.as_ref().join(format.file_name());
+            std::fs::write(&path, self.render(format))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
+
+/// Random UUID from the kernel, or one derived from the clock without it
+fn new_uuid() -> String {
+    if let Ok(uuid) = std::fs::read_to_string("/proc/sys/kernel/random/uuid") {
+        return uuid.trim().to_string();
+    }
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default()
+        ^ u128::from(std::process::id()) << 64;
+    let hex = format!("{:032x}", nanos);
+    format!(
+        "{}-{}-4{}-8{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[13..16],
+        &hex[17..20],
+        &hex[20..32]
+    )
+}
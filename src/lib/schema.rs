@@ -1,19 +1,162 @@
 // Type-safe schema validation for Ubuntu Config Scripts
 //
 // This module provides validation and type safety for configuration data
-// using serde and custom validation logic, with support for complex validation rules
-
+// using serde and custom validation logic, with support for complex validation rules.
+// Configuration files may be JSON, TOML or YAML, chosen by file extension, and
+// the Config type can be exported as a JSON Schema for editor validation.
+// `Shape` describes and validates arbitrary JSON documents.
+
+use crate::lib::migrate::{migrate_value, MigrationChange, CONFIG_VERSION};
+use crate::lib::profile::{builtin_profiles, Profile, PROFILE_NAME_PATTERN};
+use crate::lib::render::OutputFormat;
+use crate::lib::secret::SecretString;
 use anyhow::{anyhow, Context, Result};
+use schemars::generate::SchemaSettings;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::fmt::Debug;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Debug};
+use std::path::Path;
+use std::sync::{OnceLock, RwLock};
+pub use ubuntu_config_scripts_derive::Validate;
+
+/// On-disk configuration file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// Format for a file extension (without the dot), if recognised
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            "json" => Some(ConfigFormat::Json),
+            "toml" => Some(ConfigFormat::Toml),
+            "yaml" | "yml" => Some(ConfigFormat::Yaml),
+            _ => None,
+        }
+    }
+
+    /// Format for a path by extension, defaulting to JSON
+    pub fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_extension)
+            .unwrap_or(ConfigFormat::Json)
+    }
+
+    /// Parse `content` in this format
+    pub fn parse<T: DeserializeOwned>(self, content: &str) -> Result<T, ConfigParseError> {
+        match self {
+            ConfigFormat::Json => serde_json::from_str(content).map_err(|e| {
+                ConfigParseError::new(self, Some((e.line(), e.column())), &e.to_string())
+            }),
+            ConfigFormat::Toml => toml::from_str(content).map_err(|e| {
+                let location = e.span().map(|span| line_column(content, span.start));
+                ConfigParseError::new(self, location, e.message())
+            }),
+            ConfigFormat::Yaml => serde_norway::from_str(content).map_err(|e| {
+                let location = e.location().map(|l| (l.line(), l.column()));
+                ConfigParseError::new(self, location, &e.to_string())
+            }),
+        }
+    }
+
+    /// Serialize `value` in this format
+    pub fn serialize<T: Serialize>(self, value: &T) -> Result<String> {
+        match self {
+            ConfigFormat::Json => {
+                serde_json::to_string_pretty(value).context("Failed to serialize JSON")
+            }
+            ConfigFormat::Toml => toml::to_string_pretty(value).context("Failed to serialize TOML"),
+            ConfigFormat::Yaml => serde_norway::to_string(value).context("Failed to serialize YAML"),
+        }
+    }
+}
+
+impl fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigFormat::Json => write!(f, "JSON"),
+            ConfigFormat::Toml => write!(f, "TOML"),
+            ConfigFormat::Yaml => write!(f, "YAML"),
+        }
+    }
+}
+
+/// Parse failure with the 1-based line and column of the offending input
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigParseError {
+    pub format: ConfigFormat,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl ConfigParseError {
+    fn new(format: ConfigFormat, location: Option<(usize, usize)>, message: &str) -> Self {
+        // serde_json reports line 0 when the error is not tied to a position
+        let location = location.filter(|(line, _)| *line > 0);
+        let mut message = message.trim().to_string();
+        if let Some((line, column)) = location {
+            // Drop the location suffix the parsers append; it is reported separately
+            let suffix = format!(" at line {} column {}", line, column);
+            if let Some(stripped) = message.strip_suffix(&suffix) {
+                message = stripped.to_string();
+            }
+        }
+        Self {
+            format,
+            line: location.map(|(line, _)| line),
+            column: location.map(|(_, column)| column),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for ConfigParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(
+                f,
+                "{} error at line {}, column {}: {}",
+                self.format, line, column, self.message
+            ),
+            _ => write!(f, "{} error: {}", self.format, self.message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigParseError {}
+
+/// 1-based line and column of a byte offset
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .map(|l| l.chars().count())
+        .unwrap_or(0)
+        + 1;
+    (line, column)
+}
 
 /// Configuration schema for system scripts
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Validate)]
+#[serde(default)]
 pub struct SystemConfig {
+    /// Install updates automatically when running system scripts
     pub auto_update: bool,
+    /// Back up files before system scripts modify them
     pub backup_enabled: bool,
+    /// Minimum level of log messages
+    #[validate(one_of(LOG_LEVELS))]
     pub log_level: String,
+    /// Directory for temporary files (system default when unset)
     pub temp_dir: Option<String>,
 }
 
@@ -29,11 +172,17 @@ impl Default for SystemConfig {
 }
 
 /// Configuration schema for audio scripts
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Validate)]
+#[serde(default)]
 pub struct AudioConfig {
+    /// Output device name to make the default sink
     pub default_sink: Option<String>,
+    /// Input device name to make the default source
     pub default_source: Option<String>,
+    /// Output volume in percent
+    #[validate(range(min = 0, max = MAX_VOLUME_LEVEL))]
     pub volume_level: Option<u8>,
+    /// Load the PipeWire echo cancellation module
     pub enable_echo_cancellation: bool,
 }
 
@@ -49,11 +198,18 @@ impl Default for AudioConfig {
 }
 
 /// Configuration schema for development scripts
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Validate)]
+#[serde(default)]
 pub struct DevConfig {
+    /// Cargo profile used for builds
+    #[validate(one_of(BUILD_MODES))]
     pub build_mode: String,
+    /// Target architectures to build for
     pub target_arch: Vec<String>,
+    /// Compiler optimization level
+    #[validate(range(min = 0, max = MAX_OPTIMIZATION_LEVEL))]
     pub optimization_level: u8,
+    /// Keep debug symbols in release builds
     pub include_debug_symbols: bool,
 }
 
@@ -68,34 +224,382 @@ impl Default for DevConfig {
     }
 }
 
+/// Configuration schema for `cleanup_disk`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Validate)]
+#[serde(default)]
+pub struct CleanupConfig {
+    /// Directories to clean (`~` is expanded)
+    pub paths: Vec<String>,
+    /// Only remove files at least this large, in megabytes
+    pub min_size_mb: u64,
+    /// Only remove files not modified for this many days
+    #[validate(range(min = 0, max = MAX_CLEANUP_AGE_DAYS))]
+    pub older_than_days: u32,
+    /// Empty the desktop trash as well
+    pub empty_trash: bool,
+}
+
+impl Default for CleanupConfig {
+    fn default() -> Self {
+        Self {
+            paths: vec!["~/.cache".to_string(), "/tmp".to_string()],
+            min_size_mb: 0,
+            older_than_days: 30,
+            empty_trash: false,
+        }
+    }
+}
+
+/// Configuration schema for `configure_time`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Validate)]
+#[serde(default)]
+pub struct TimeConfig {
+    /// IANA time zone name (e.g. `Europe/Berlin`)
+    #[validate(regex(path = TIMEZONE_PATTERN))]
+    pub timezone: String,
+    /// Synchronise the clock with NTP
+    pub ntp_enabled: bool,
+    /// NTP servers, in order of preference
+    #[validate(regex(path = HOSTNAME_PATTERN))]
+    pub ntp_servers: Vec<String>,
+}
+
+impl Default for TimeConfig {
+    fn default() -> Self {
+        Self {
+            timezone: "Etc/UTC".to_string(),
+            ntp_enabled: true,
+            ntp_servers: vec!["ntp.ubuntu.com".to_string()],
+        }
+    }
+}
+
+/// Configuration schema for `configure_obs`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Validate)]
+#[serde(default)]
+pub struct ObsConfig {
+    /// Output resolution as `WIDTHxHEIGHT`
+    #[validate(regex(path = RESOLUTION_PATTERN))]
+    pub resolution: String,
+    /// Frames per second
+    #[validate(range(min = 1, max = MAX_OBS_FPS))]
+    pub fps: u32,
+    /// Video encoder
+    #[validate(one_of(OBS_ENCODERS))]
+    pub encoder: String,
+    /// Directory for recordings (OBS default when unset)
+    pub recording_dir: Option<String>,
+}
+
+impl Default for ObsConfig {
+    fn default() -> Self {
+        Self {
+            resolution: "1920x1080".to_string(),
+            fps: 30,
+            encoder: "x264".to_string(),
+            recording_dir: None,
+        }
+    }
+}
+
+/// Configuration schema for `upgrade_nvidia_driver`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, Validate)]
+#[serde(default)]
+pub struct NvidiaConfig {
+    /// Driver branch to install (e.g. `550`); the recommended driver when unset
+    #[validate(regex(path = DRIVER_VERSION_PATTERN))]
+    pub target_version: Option<String>,
+    /// Install the open kernel modules variant
+    pub open_kernel_modules: bool,
+    /// Reboot once the new driver is installed
+    pub reboot_after_upgrade: bool,
+}
+
+/// Configuration schema for `create_pipewire_monitor`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Validate)]
+#[serde(default)]
+pub struct PipewireMonitorConfig {
+    /// Seconds between health checks
+    #[validate(range(min = 1, max = MAX_MONITOR_INTERVAL_SECS))]
+    pub check_interval_secs: u64,
+    /// Seconds to wait before restarting a failed service
+    #[validate(range(min = 0, max = MAX_MONITOR_INTERVAL_SECS))]
+    pub restart_delay_secs: u64,
+    /// Restarts allowed before the monitor gives up
+    pub max_restarts: u32,
+}
+
+impl Default for PipewireMonitorConfig {
+    fn default() -> Self {
+        Self {
+            check_interval_secs: 30,
+            restart_delay_secs: 5,
+            max_restarts: 3,
+        }
+    }
+}
+
+/// Configuration schema for `refresh_kde_desktop`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Validate)]
+#[serde(default)]
+pub struct KdeConfig {
+    /// Restart plasmashell
+    pub restart_plasmashell: bool,
+    /// Clear the icon and plasma caches
+    pub clear_cache: bool,
+    /// Rebuild the KService desktop file cache (kbuildsycoca)
+    pub rebuild_sycoca: bool,
+}
+
+impl Default for KdeConfig {
+    fn default() -> Self {
+        Self {
+            restart_plasmashell: true,
+            clear_cache: true,
+            rebuild_sycoca: true,
+        }
+    }
+}
+
+/// Configuration schema for `deploy`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Validate)]
+#[serde(default)]
+pub struct DeployConfig {
+    /// Directory the built binaries are installed into (`~` is expanded)
+    pub install_dir: String,
+    /// Binaries to deploy; every binary when empty
+    pub binaries: Vec<String>,
+    /// Strip symbols from installed binaries
+    pub strip: bool,
+    /// Embed CycloneDX and SPDX SBOMs in the package
+    pub sbom: bool,
+    /// List the system packages the scripts need in the SBOMs
+    pub sbom_system_packages: bool,
+}
+
+impl Default for DeployConfig {
+    fn default() -> Self {
+        Self {
+            install_dir: "~/.local/bin".to_string(),
+            binaries: Vec::new(),
+            strip: true,
+            sbom: true,
+            sbom_system_packages: false,
+        }
+    }
+}
+
+/// Configuration schema for `configure_runner`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Validate)]
+#[serde(default)]
+pub struct RunnerConfig {
+    /// Repository or organization the runner registers with
+    #[validate(regex(path = GITHUB_URL_PATTERN))]
+    pub url: Option<String>,
+    /// Runner name (the host name when unset)
+    pub name: Option<String>,
+    /// Extra labels added to the default ones
+    pub labels: Vec<String>,
+    /// Registration token, best given as a reference (`${env:GH_RUNNER_TOKEN}`)
+    #[validate(nested)]
+    pub token: Option<SecretString>,
+    /// Directory for job workspaces, relative to the runner
+    pub work_dir: String,
+}
+
+impl Default for RunnerConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            name: None,
+            labels: Vec::new(),
+            token: None,
+            work_dir: "_work".to_string(),
+        }
+    }
+}
+
+/// Configuration schema for `configure_swap`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Validate)]
+#[serde(default)]
+pub struct SwapConfig {
+    /// Swap file location
+    pub file: String,
+    /// Swap file size in MiB; the current size is kept when unset
+    pub size_mb: Option<u32>,
+    /// Kernel tendency to swap (`vm.swappiness`); unchanged when unset
+    #[validate(range(min = 0, max = MAX_SWAPPINESS))]
+    pub swappiness: Option<u8>,
+}
+
+impl Default for SwapConfig {
+    fn default() -> Self {
+        Self {
+            file: "/swapfile".to_string(),
+            size_mb: None,
+            swappiness: None,
+        }
+    }
+}
+
+/// Kernel parameters applied by `configure_sysctl`; unset values are left unchanged
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, Validate)]
+#[serde(default)]
+pub struct SysctlConfig {
+    /// Percent of memory that may hold dirty pages (`vm.dirty_ratio`)
+    #[validate(range(min = 1, max = MAX_DIRTY_RATIO))]
+    pub vm_dirty_ratio: Option<u8>,
+    /// Interval between dirty page flushes (`vm.dirty_writeback_centisecs`)
+    pub vm_dirty_writeback_centisecs: Option<u32>,
+    /// Inotify watches per user (`fs.inotify.max_user_watches`)
+    pub fs_inotify_max_user_watches: Option<u32>,
+}
+
+impl SysctlConfig {
+    /// sysctl names and values of the parameters that are set
+    pub fn settings(&self) -> Vec<(&'static str, String)> {
+        [
+            ("vm.dirty_ratio", self.vm_dirty_ratio.map(u32::from)),
+            (
+                "vm.dirty_writeback_centisecs",
+                self.vm_dirty_writeback_centisecs,
+            ),
+            (
+                "fs.inotify.max_user_watches",
+                self.fs_inotify_max_user_watches,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|v| (name, v.to_string())))
+        .collect()
+    }
+}
+
 /// Main configuration container
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, Validate)]
+#[serde(default)]
 pub struct Config {
+    /// Layout version of this file, used to migrate older files
+    pub version: u32,
+    /// Settings for system scripts
+    #[validate(nested)]
     pub system: SystemConfig,
+    /// Settings for audio scripts
+    #[validate(nested)]
     pub audio: AudioConfig,
+    /// Settings for development scripts
+    #[validate(nested)]
     pub dev: DevConfig,
+    /// Settings for `cleanup_disk`
+    #[validate(nested)]
+    pub cleanup: CleanupConfig,
+    /// Settings for `configure_time`
+    #[validate(nested)]
+    pub time: TimeConfig,
+    /// Settings for `configure_obs`
+    #[validate(nested)]
+    pub obs: ObsConfig,
+    /// Settings for `upgrade_nvidia_driver`
+    #[validate(nested)]
+    pub nvidia: NvidiaConfig,
+    /// Settings for `create_pipewire_monitor`
+    #[validate(nested)]
+    pub pipewire_monitor: PipewireMonitorConfig,
+    /// Settings for `refresh_kde_desktop`
+    #[validate(nested)]
+    pub kde: KdeConfig,
+    /// Settings for `deploy`
+    #[validate(nested)]
+    pub deploy: DeployConfig,
+    /// Settings for `configure_runner`
+    #[validate(nested)]
+    pub runner: RunnerConfig,
+    /// Settings for `configure_swap`
+    #[validate(nested)]
+    pub swap: SwapConfig,
+    /// Settings for `configure_sysctl`
+    #[validate(nested)]
+    pub sysctl: SysctlConfig,
+    /// Profile laid over the configuration files (see `profiles`)
+    pub profile: Option<String>,
+    /// Named sets of values that overlay any section while their profile is active
+    pub profiles: BTreeMap<String, Profile>,
     #[serde(flatten)]
     pub extra: HashMap<String, serde_json::Value>,
 }
 
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            system: SystemConfig::default(),
+            audio: AudioConfig::default(),
+            dev: DevConfig::default(),
+            cleanup: CleanupConfig::default(),
+            time: TimeConfig::default(),
+            obs: ObsConfig::default(),
+            nvidia: NvidiaConfig::default(),
+            pipewire_monitor: PipewireMonitorConfig::default(),
+            kde: KdeConfig::default(),
+            deploy: DeployConfig::default(),
+            runner: RunnerConfig::default(),
+            swap: SwapConfig::default(),
+            sysctl: SysctlConfig::default(),
+            profile: None,
+            profiles: builtin_profiles(),
+            extra: HashMap::new(),
+        }
+    }
+}
+
 impl Config {
     /// Load configuration from JSON string
     pub fn from_json(json: &str) -> Result<Self> {
-        serde_json::from_str(json).context("Failed to parse JSON configuration")
+        Self::parse_migrated(json, ConfigFormat::Json)
+            .context("Failed to parse JSON configuration")
+    }
+
+    /// Load configuration from a string in the given format
+    pub fn from_str_as(content: &str, format: ConfigFormat) -> Result<Self> {
+        Self::parse_migrated(content, format)
+            .with_context(|| format!("Failed to parse {} configuration", format))
     }
 
-    /// Load configuration from JSON file
+    /// Load configuration from a JSON, TOML or YAML file (chosen by extension)
     pub fn from_file(path: &str) -> Result<Self> {
         let content = std::fs::read_to_string(path)
             .with_context(|| format!("Failed to read config file: {}", path))?;
-        Self::from_json(&content)
+        Self::parse_migrated(&content, ConfigFormat::from_path(Path::new(path)))
+            .with_context(|| format!("Failed to parse config file: {}", path))
+    }
+
+    /// Parse content, upgrading older layouts in memory first
+    fn parse_migrated(content: &str, format: ConfigFormat) -> Result<Self> {
+        let report = migrate_value(format.parse(content)?)?;
+        let layout_changed = report
+            .changes
+            .iter()
+            .any(|change| !matches!(change, MigrationChange::Versioned { .. }));
+        if layout_changed {
+            serde_json::from_value(report.value).context("Migrated configuration is invalid")
+        } else {
+            // Parse the text directly so type errors keep their line and column
+            Ok(format.parse(content)?)
+        }
     }
 
-    /// Save configuration to JSON file
+    /// Save configuration in the format matching the file extension
     pub fn to_file(&self, path: &str) -> Result<()> {
-        let json =
-            serde_json::to_string_pretty(self).context("Failed to serialize configuration")?;
-        std::fs::write(path, json).with_context(|| format!("Failed to write config file: {}", path))
+        let content = self.to_string_as(ConfigFormat::from_path(Path::new(path)))?;
+        std::fs::write(path, content)
+            .with_context(|| format!("Failed to write config file: {}", path))
+    }
+
+    /// Serialize configuration in the given format
+    pub fn to_string_as(&self, format: ConfigFormat) -> Result<String> {
+        format
+            .serialize(self)
+            .with_context(|| format!("Failed to serialize configuration to {}", format))
     }
 
     /// Convert to JSON string
@@ -103,45 +607,1097 @@ impl Config {
         serde_json::to_string_pretty(self).context("Failed to serialize configuration to JSON")
     }
 
-    /// Validate configuration values
+    /// Validate configuration values, reporting every problem at once
     pub fn validate(&self) -> Result<()> {
-        // Validate system config
-        if !["debug", "info", "warn", "error"].contains(&self.system.log_level.as_str()) {
-            return Err(anyhow::anyhow!(
-                "Invalid log level: {}",
-                self.system.log_level
-            ));
-        }
-
-        // Validate audio config
-        if let Some(volume) = self.audio.volume_level {
-            if volume > 100 {
-                return Err(anyhow::anyhow!(
-                    "Volume level cannot exceed 100: {}",
-                    volume
-                ));
+        self.validation_errors().into_result()
+    }
+
+    /// Collect every validation problem with the path of the offending field
+    pub fn validation_errors(&self) -> ValidationErrors {
+        let mut errors = ValidationErrors::new();
+        self.validate_fields("", &mut errors);
+        for (name, profile) in &self.profiles {
+            errors.check(
+                &format!("/profiles/{}", name),
+                &serde_json::Value::String(name.clone()),
+                &Constraint::Pattern {
+                    pattern: PROFILE_NAME_PATTERN.to_string(),
+                },
+            );
+            errors.extend_at(&format!("/profiles/{}", name), profile.validation_errors());
+        }
+        errors
+    }
+
+    /// JSON Schema (draft 2020-12) describing the configuration file
+    ///
+    /// Descriptions and defaults come from the Rust types; enums and ranges
+    /// come from the same rules `validate` enforces.
+    pub fn json_schema() -> serde_json::Value {
+        let generator = SchemaSettings::draft2020_12()
+            .with(|settings| settings.inline_subschemas = true)
+            .into_generator();
+        let mut schema = generator.into_root_schema_for::<Config>().to_value();
+
+        for rule in config_rules() {
+            let pointer = rule
+                .path
+                .split('/')
+                .skip(1)
+                .map(|segment| format!("/properties/{}", segment))
+                .collect::<String>();
+            if let Some(serde_json::Value::Object(field)) = schema.pointer_mut(&pointer) {
+                rule.constraint.apply_to_schema(field);
+            }
+        }
+        schema
+    }
+}
+
+/// A constraint on one configuration field
+#[derive(Debug, Clone)]
+pub struct FieldRule {
+    /// JSON pointer of the field (e.g. `/audio/volume_level`)
+    pub path: String,
+    pub constraint: Constraint,
+}
+
+/// Constraints on `Config` fields, shared by validation and schema export
+pub fn config_rules() -> Vec<FieldRule> {
+    Config::field_rules()
+}
+
+/// Path-aware field validation, implemented by `#[derive(Validate)]`
+pub trait ValidateFields {
+    /// Constraints declared on fields, with paths relative to this type
+    fn field_rules() -> Vec<FieldRule>
+    where
+        Self: Sized;
+
+    /// Check every field, reporting errors below `path`
+    fn validate_fields(&self, path: &str, errors: &mut ValidationErrors);
+}
+
+impl<T: ValidateFields> ValidateFields for Option<T> {
+    fn field_rules() -> Vec<FieldRule> {
+        T::field_rules()
+    }
+
+    fn validate_fields(&self, path: &str, errors: &mut ValidationErrors) {
+        if let Some(value) = self {
+            value.validate_fields(path, errors);
+        }
+    }
+}
+
+impl<T: ValidateFields> ValidateFields for Vec<T> {
+    /// Item rules are not expressible as a single path, so none are listed
+    fn field_rules() -> Vec<FieldRule> {
+        Vec::new()
+    }
+
+    fn validate_fields(&self, path: &str, errors: &mut ValidationErrors) {
+        for (index, item) in self.iter().enumerate() {
+            item.validate_fields(&format!("{}/{}", path, index), errors);
+        }
+    }
+}
+
+/// Accepted values for `system.log_level` and `--log-level`
+pub const LOG_LEVELS: [&str; 4] = ["debug", "info", "warn", "error"];
+
+/// Accepted values for `dev.build_mode`
+pub const BUILD_MODES: [&str; 2] = ["debug", "release"];
+
+/// Upper bound for `audio.volume_level`
+pub const MAX_VOLUME_LEVEL: u8 = 100;
+
+/// Upper bound for `dev.optimization_level`
+pub const MAX_OPTIMIZATION_LEVEL: u8 = 3;
+
+/// Upper bound for `cleanup.older_than_days`
+pub const MAX_CLEANUP_AGE_DAYS: u32 = 3650;
+
+/// IANA time zone names for `time.timezone`
+pub const TIMEZONE_PATTERN: &str = "^[A-Za-z_]+(/[A-Za-z0-9_+-]+)*$";
+
+/// Host names for `time.ntp_servers`
+pub const HOSTNAME_PATTERN: &str = "^[A-Za-z0-9.-]+$";
+
+/// `WIDTHxHEIGHT` values for `obs.resolution`
+pub const RESOLUTION_PATTERN: &str = "^[0-9]+x[0-9]+$";
+
+/// Driver branches for `nvidia.target_version` (e.g. `550` or `550.120`)
+pub const DRIVER_VERSION_PATTERN: &str = "^[0-9]+(\\.[0-9]+)*$";
+
+/// Accepted values for `obs.encoder`
+pub const OBS_ENCODERS: [&str; 3] = ["x264", "nvenc", "vaapi"];
+
+/// Upper bound for `obs.fps`
+pub const MAX_OBS_FPS: u32 = 240;
+
+/// Upper bound for the `pipewire_monitor` intervals, in seconds
+pub const MAX_MONITOR_INTERVAL_SECS: u64 = 3600;
+
+/// Repository or organization URLs for `runner.url`
+pub const GITHUB_URL_PATTERN: &str = "^https://github\\.com/[^/]+(/[^/]+)?/?$";
+
+/// Upper bound for `swap.swappiness` (kernels since 5.8)
+pub const MAX_SWAPPINESS: u8 = 200;
+
+/// Upper bound for `sysctl.vm_dirty_ratio`, in percent
+pub const MAX_DIRTY_RATIO: u8 = 100;
+
+/// Closest accepted value is only suggested above this Jaro-Winkler similarity
+const SUGGESTION_THRESHOLD: f64 = 0.8;
+
+/// Rule a value failed to satisfy
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Constraint {
+    OneOf {
+        allowed: Vec<String>,
+    },
+    Range {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        min: Option<f64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max: Option<f64>,
+    },
+    Length {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        min: Option<usize>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max: Option<usize>,
+    },
+    Pattern {
+        pattern: String,
+    },
+    Type {
+        expected: String,
+    },
+    Required,
+    NoAdditionalProperties,
+    PathExists,
+    Union {
+        alternatives: Vec<String>,
+    },
+    Custom {
+        description: String,
+    },
+}
+
+impl Constraint {
+    pub fn one_of(allowed: &[&str]) -> Self {
+        Constraint::OneOf {
+            allowed: allowed.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    pub fn range(min: f64, max: f64) -> Self {
+        Constraint::Range {
+            min: Some(min),
+            max: Some(max),
+        }
+    }
+
+    /// Add the matching JSON Schema keywords to a property schema
+    pub fn apply_to_schema(&self, schema: &mut serde_json::Map<String, serde_json::Value>) {
+        // Value rules on arrays are checked against each item
+        let per_item = matches!(self, Constraint::OneOf { .. } | Constraint::Pattern { .. });
+        if per_item && schema.get("type").is_some_and(|t| t == "array") {
+            if let Some(serde_json::Value::Object(items)) = schema.get_mut("items") {
+                self.apply_to_schema(items);
+            }
+            return;
+        }
+
+        match self {
+            Constraint::OneOf { allowed } => {
+                let mut values: Vec<serde_json::Value> =
+                    allowed.iter().map(|a| a.as_str().into()).collect();
+                if schema_allows_null(schema) {
+                    values.push(serde_json::Value::Null);
+                }
+                schema.insert("enum".to_string(), values.into());
+            }
+            Constraint::Range { min, max } => {
+                if let Some(min) = min {
+                    schema.insert("minimum".to_string(), schema_number(*min));
+                }
+                if let Some(max) = max {
+                    schema.insert("maximum".to_string(), schema_number(*max));
+                }
+            }
+            Constraint::Length { min, max } => {
+                let (min_key, max_key) = match schema.get("type") {
+                    Some(t) if t == "array" => ("minItems", "maxItems"),
+                    _ => ("minLength", "maxLength"),
+                };
+                if let Some(min) = min {
+                    schema.insert(min_key.to_string(), (*min).into());
+                }
+                if let Some(max) = max {
+                    schema.insert(max_key.to_string(), (*max).into());
+                }
+            }
+            Constraint::Pattern { pattern } => {
+                schema.insert("pattern".to_string(), pattern.as_str().into());
+            }
+            Constraint::Type { expected } => {
+                schema.insert("type".to_string(), expected.as_str().into());
             }
+            Constraint::NoAdditionalProperties => {
+                schema.insert("additionalProperties".to_string(), false.into());
+            }
+            Constraint::Required
+            | Constraint::PathExists
+            | Constraint::Union { .. }
+            | Constraint::Custom { .. } => {}
         }
+    }
+}
+
+fn schema_allows_null(schema: &serde_json::Map<String, serde_json::Value>) -> bool {
+    match schema.get("type") {
+        Some(serde_json::Value::Array(types)) => types.iter().any(|t| t == "null"),
+        Some(t) => t == "null",
+        None => false,
+    }
+}
+
+/// Whole numbers stay integers so `"maximum": 100` is not written as `100.0`
+fn schema_number(n: f64) -> serde_json::Value {
+    if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        (n as i64).into()
+    } else {
+        n.into()
+    }
+}
 
-        // Validate dev config
-        if !["debug", "release"].contains(&self.dev.build_mode.as_str()) {
-            return Err(anyhow::anyhow!(
-                "Invalid build mode: {}",
-                self.dev.build_mode
-            ));
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Constraint::OneOf { allowed } => write!(f, "one of: {}", allowed.join(", ")),
+            Constraint::Range {
+                min: Some(min),
+                max: Some(max),
+            } => write!(f, "between {} and {}", min, max),
+            Constraint::Range { min: Some(min), .. } => write!(f, "at least {}", min),
+            Constraint::Range { max: Some(max), .. } => write!(f, "at most {}", max),
+            Constraint::Range { .. } => write!(f, "any number"),
+            Constraint::Length {
+                min: Some(min),
+                max: Some(max),
+            } => write!(f, "length between {} and {}", min, max),
+            Constraint::Length { min: Some(min), .. } => write!(f, "length at least {}", min),
+            Constraint::Length { max: Some(max), .. } => write!(f, "length at most {}", max),
+            Constraint::Length { .. } => write!(f, "any length"),
+            Constraint::Pattern { pattern } => write!(f, "matching /{}/", pattern),
+            Constraint::Type { expected } => write!(f, "of type {}", expected),
+            Constraint::Required => write!(f, "present"),
+            Constraint::NoAdditionalProperties => write!(f, "a known key"),
+            Constraint::PathExists => write!(f, "an existing path"),
+            Constraint::Union { alternatives } => {
+                write!(f, "exactly one of: {}", alternatives.join(", "))
+            }
+            Constraint::Custom { description } => write!(f, "{}", description),
         }
+    }
+}
+
+/// A single validation failure located by JSON pointer (e.g. `/audio/volume_level`)
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationError {
+    pub path: String,
+    pub value: serde_json::Value,
+    pub constraint: Constraint,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
 
-        if self.dev.optimization_level > 3 {
-            return Err(anyhow::anyhow!(
-                "Optimization level cannot exceed 3: {}",
-                self.dev.optimization_level
-            ));
+impl ValidationError {
+    pub fn new(path: &str, value: serde_json::Value, constraint: Constraint) -> Self {
+        let message = format!("{} must be {}", value, constraint);
+        Self {
+            path: path.to_string(),
+            value,
+            constraint,
+            message,
+            suggestion: None,
         }
+    }
+
+    pub fn message(mut self, message: &str) -> Self {
+        self.message = message.to_string();
+        self
+    }
+
+    pub fn suggestion(mut self, suggestion: Option<String>) -> Self {
+        self.suggestion = suggestion;
+        self
+    }
+}
 
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)?;
+        if let Some(ref suggestion) = self.suggestion {
+            write!(f, " (did you mean \"{}\"?)", suggestion)?;
+        }
         Ok(())
     }
 }
 
+/// Every validation failure found in one pass
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct ValidationErrors {
+    errors: Vec<ValidationError>,
+}
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, error: ValidationError) {
+        self.errors.push(error);
+    }
+
+    /// Append errors found in a nested value, prefixing their paths with `path`
+    pub fn extend_at(&mut self, path: &str, other: ValidationErrors) {
+        for mut error in other.errors {
+            error.path = format!("{}{}", path, error.path);
+            self.errors.push(error);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.errors
+    }
+
+    /// Error for the field at `path`, if any
+    pub fn get(&self, path: &str) -> Option<&ValidationError> {
+        self.errors.iter().find(|e| e.path == path)
+    }
+
+    fn push_pattern_error(&mut self, path: &str, value: &serde_json::Value, pattern: &str) {
+        let message = format!("{} does not match /{}/", value, pattern);
+        let constraint = Constraint::Pattern {
+            pattern: pattern.to_string(),
+        };
+        self.push(ValidationError::new(path, value.clone(), constraint).message(&message));
+    }
+
+    /// Record an error if a field value breaks `constraint` (`None` is skipped)
+    pub fn check_field<T: Serialize>(&mut self, path: &str, value: &T, constraint: &Constraint) {
+        match serde_json::to_value(value) {
+            Ok(value) => self.check(path, &value, constraint),
+            Err(e) => self.push(
+                ValidationError::new(path, serde_json::Value::Null, constraint.clone())
+                    .message(&format!("value cannot be checked: {}", e)),
+            ),
+        }
+    }
+
+    /// Record an error if a JSON value breaks `constraint` (null values are skipped)
+    ///
+    /// Value constraints on an array apply to each item (e.g. `/dev/target_arch/0`).
+    pub fn check(&mut self, path: &str, value: &serde_json::Value, constraint: &Constraint) {
+        match (constraint, value) {
+            (_, serde_json::Value::Null) => {}
+            (
+                Constraint::OneOf { .. } | Constraint::Pattern { .. } | Constraint::PathExists,
+                serde_json::Value::Array(items),
+            ) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.check(&format!("{}/{}", path, index), item, constraint);
+                }
+            }
+            (Constraint::PathExists, serde_json::Value::String(s)) => {
+                if !Path::new(s).exists() {
+                    let message = format!("path does not exist: {}", s);
+                    self.push(
+                        ValidationError::new(path, value.clone(), constraint.clone())
+                            .message(&message),
+                    );
+                }
+            }
+            (Constraint::OneOf { allowed }, serde_json::Value::String(s)) => {
+                let allowed: Vec<&str> = allowed.iter().map(String::as_str).collect();
+                self.check_one_of(path, s, &allowed);
+            }
+            (Constraint::Range { min, max }, serde_json::Value::Number(n)) => {
+                let number = n.as_f64().unwrap_or(f64::NAN);
+                let below = min.is_some_and(|min| number < min);
+                let above = max.is_some_and(|max| number > max);
+                if below || above {
+                    self.push_range_error(path, value.clone(), *min, *max);
+                }
+            }
+            (
+                Constraint::Length { min, max },
+                serde_json::Value::String(_) | serde_json::Value::Array(_),
+            ) => {
+                self.check_length(path, value, *min, *max);
+            }
+            (Constraint::Pattern { pattern }, serde_json::Value::String(s)) => {
+                match compiled_pattern(pattern) {
+                    Ok(re) if re.is_match(s) => {}
+                    Ok(_) => self.push_pattern_error(path, value, pattern),
+                    Err(e) => {
+                        let message = format!("invalid pattern /{}/: {}", pattern, e);
+                        self.push(
+                            ValidationError::new(path, value.clone(), constraint.clone())
+                                .message(&message),
+                        );
+                    }
+                }
+            }
+            // Structural constraints are checked by `Shape`
+            (
+                Constraint::Type { .. }
+                | Constraint::Required
+                | Constraint::NoAdditionalProperties
+                | Constraint::Union { .. }
+                | Constraint::Custom { .. },
+                _,
+            ) => {}
+            (constraint, value) => {
+                let message = format!("{} has the wrong type, expected {}", value, constraint);
+                self.push(
+                    ValidationError::new(path, value.clone(), constraint.clone()).message(&message),
+                );
+            }
+        }
+    }
+
+    /// Record an error unless `value` is one of `allowed`
+    pub fn check_one_of(&mut self, path: &str, value: &str, allowed: &[&str]) {
+        if allowed.contains(&value) {
+            return;
+        }
+        let constraint = Constraint::OneOf {
+            allowed: allowed.iter().map(|a| a.to_string()).collect(),
+        };
+        let message = format!("invalid value \"{}\", expected {}", value, constraint);
+        self.push(
+            ValidationError::new(path, value.into(), constraint)
+                .message(&message)
+                .suggestion(suggest(value, allowed)),
+        );
+    }
+
+    /// Record an error unless `min <= value <= max`
+    pub fn check_range<T>(&mut self, path: &str, value: T, min: Option<f64>, max: Option<f64>)
+    where
+        T: Copy + Into<f64> + Into<serde_json::Value>,
+    {
+        let number: f64 = value.into();
+        let below = min.is_some_and(|min| number < min);
+        let above = max.is_some_and(|max| number > max);
+        if below || above {
+            self.push_range_error(path, value.into(), min, max);
+        }
+    }
+
+    fn check_length(
+        &mut self,
+        path: &str,
+        value: &serde_json::Value,
+        min: Option<usize>,
+        max: Option<usize>,
+    ) {
+        let length = match value {
+            serde_json::Value::String(s) => s.chars().count(),
+            serde_json::Value::Array(items) => items.len(),
+            _ => return,
+        };
+        let short = min.is_some_and(|min| length < min);
+        let long = max.is_some_and(|max| length > max);
+        if short || long {
+            let constraint = Constraint::Length { min, max };
+            let message = format!("length {} is out of range, expected {}", length, constraint);
+            self.push(ValidationError::new(path, value.clone(), constraint).message(&message));
+        }
+    }
+
+    fn push_range_error(
+        &mut self,
+        path: &str,
+        value: serde_json::Value,
+        min: Option<f64>,
+        max: Option<f64>,
+    ) {
+        let constraint = Constraint::Range { min, max };
+        let message = format!("{} is out of range, expected {}", value, constraint);
+        self.push(ValidationError::new(path, value, constraint).message(&message));
+    }
+
+    /// Split into the errors at or below `path` and all the others
+    pub fn partition_at(self, path: &str) -> (Self, Self) {
+        let (inside, outside) = self.errors.into_iter().partition(|error| {
+            error.path == path || error.path.starts_with(&format!("{}/", path))
+        });
+        (Self { errors: inside }, Self { errors: outside })
+    }
+
+    /// `Ok` when no errors were recorded
+    pub fn into_result(self) -> Result<()> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self.into())
+        }
+    }
+
+    /// Render as pretty-printed JSON (an array of errors)
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).context("Failed to serialize validation errors")
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.errors.len() {
+            0 => write!(f, "no validation errors"),
+            1 => write!(f, "validation failed: {}", self.errors[0]),
+            n => {
+                write!(f, "{} validation errors:", n)?;
+                for error in &self.errors {
+                    write!(f, "\n  - {}", error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+/// Closest accepted value to a misspelled one, if any is close enough
+pub fn suggest(value: &str, candidates: &[&str]) -> Option<String> {
+    let value = value.to_lowercase();
+    candidates
+        .iter()
+        .map(|c| (strsim::jaro_winkler(&value, &c.to_lowercase()), *c))
+        .filter(|(score, _)| *score >= SUGGESTION_THRESHOLD)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, c)| c.to_string())
+}
+
+/// Compile a `Constraint::Pattern` once and reuse it on later checks
+fn compiled_pattern(pattern: &str) -> std::result::Result<regex::Regex, regex::Error> {
+    static PATTERNS: OnceLock<RwLock<HashMap<String, regex::Regex>>> = OnceLock::new();
+    let patterns = PATTERNS.get_or_init(|| RwLock::new(HashMap::new()));
+    if let Some(re) = patterns.read().ok().and_then(|p| p.get(pattern).cloned()) {
+        return Ok(re);
+    }
+    let re = regex::Regex::new(pattern)?;
+    if let Ok(mut patterns) = patterns.write() {
+        patterns.insert(pattern.to_string(), re.clone());
+    }
+    Ok(re)
+}
+
+/// Expected type of a JSON value in a `Shape`
+#[derive(Debug, Clone)]
+enum ShapeKind {
+    Any,
+    Null,
+    Bool,
+    String {
+        pattern: Option<regex::Regex>,
+    },
+    Number {
+        integer: bool,
+    },
+    Array {
+        items: Box<Shape>,
+    },
+    Object {
+        properties: Vec<(String, Shape, bool)>,
+        additional: Option<Box<Shape>>,
+    },
+    Enum(Vec<serde_json::Value>),
+    OneOf(Vec<Shape>),
+    AnyOf(Vec<Shape>),
+}
+
+/// Composable description of a JSON document shape
+///
+/// ```ignore
+/// let shape = Shape::object()
+///     .required("name", Shape::string().min_length(1))
+///     .optional("tags", Shape::array(Shape::string()))
+///     .optional("mode", Shape::enumeration(&["apt", "snap"]))
+///     .deny_additional();
+/// let errors = shape.validate(&document);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Shape {
+    kind: ShapeKind,
+    nullable: bool,
+    min: Option<f64>,
+    max: Option<f64>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    description: Option<String>,
+}
+
+impl Shape {
+    fn of(kind: ShapeKind) -> Self {
+        Self {
+            kind,
+            nullable: false,
+            min: None,
+            max: None,
+            min_length: None,
+            max_length: None,
+            description: None,
+        }
+    }
+
+    /// Any value
+    pub fn any() -> Self {
+        Self::of(ShapeKind::Any)
+    }
+
+    pub fn null() -> Self {
+        Self::of(ShapeKind::Null)
+    }
+
+    pub fn boolean() -> Self {
+        Self::of(ShapeKind::Bool)
+    }
+
+    pub fn string() -> Self {
+        Self::of(ShapeKind::String { pattern: None })
+    }
+
+    pub fn number() -> Self {
+        Self::of(ShapeKind::Number { integer: false })
+    }
+
+    pub fn integer() -> Self {
+        Self::of(ShapeKind::Number { integer: true })
+    }
+
+    /// Array whose items all match `items`
+    pub fn array(items: Shape) -> Self {
+        Self::of(ShapeKind::Array {
+            items: Box::new(items),
+        })
+    }
+
+    /// Object; unknown keys are allowed until `deny_additional` or `additional`
+    pub fn object() -> Self {
+        Self::of(ShapeKind::Object {
+            properties: Vec::new(),
+            additional: Some(Box::new(Shape::any())),
+        })
+    }
+
+    /// One of a fixed set of strings
+    pub fn enumeration(values: &[&str]) -> Self {
+        Self::of(ShapeKind::Enum(
+            values.iter().map(|v| (*v).into()).collect(),
+        ))
+    }
+
+    /// One of a fixed set of JSON values
+    pub fn values(values: Vec<serde_json::Value>) -> Self {
+        Self::of(ShapeKind::Enum(values))
+    }
+
+    /// Exactly one of the alternatives must match
+    pub fn one_of(alternatives: Vec<Shape>) -> Self {
+        Self::of(ShapeKind::OneOf(alternatives))
+    }
+
+    /// At least one of the alternatives must match
+    pub fn any_of(alternatives: Vec<Shape>) -> Self {
+        Self::of(ShapeKind::AnyOf(alternatives))
+    }
+
+    /// Also accept `null`
+    pub fn nullable(mut self) -> Self {
+        self.nullable = true;
+        self
+    }
+
+    /// Minimum for numbers
+    pub fn min(mut self, value: f64) -> Self {
+        self.min = Some(value);
+        self
+    }
+
+    /// Maximum for numbers
+    pub fn max(mut self, value: f64) -> Self {
+        self.max = Some(value);
+        self
+    }
+
+    /// Minimum length for strings (in characters) and arrays (in items)
+    pub fn min_length(mut self, length: usize) -> Self {
+        self.min_length = Some(length);
+        self
+    }
+
+    /// Maximum length for strings (in characters) and arrays (in items)
+    pub fn max_length(mut self, length: usize) -> Self {
+        self.max_length = Some(length);
+        self
+    }
+
+    /// Regular expression strings must match (only valid on string shapes)
+    pub fn pattern(mut self, pattern: &str) -> Result<Self> {
+        let re =
+            regex::Regex::new(pattern).with_context(|| format!("Invalid pattern: {}", pattern))?;
+        match self.kind {
+            ShapeKind::String { pattern: ref mut p } => *p = Some(re),
+            _ => {
+                return Err(anyhow!(
+                    "pattern /{}/ needs a string shape, not {}",
+                    pattern,
+                    self.type_name()
+                ))
+            }
+        }
+        Ok(self)
+    }
+
+    /// Human-readable description, used in error messages for unions
+    pub fn describe(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Key that must be present in an object
+    pub fn required(self, key: &str, shape: Shape) -> Self {
+        self.property(key, shape, true)
+    }
+
+    /// Key that may be present in an object
+    pub fn optional(self, key: &str, shape: Shape) -> Self {
+        self.property(key, shape, false)
+    }
+
+    fn property(mut self, key: &str, shape: Shape, required: bool) -> Self {
+        if let ShapeKind::Object {
+            ref mut properties, ..
+        } = self.kind
+        {
+            properties.retain(|(k, _, _)| k != key);
+            properties.push((key.to_string(), shape, required));
+        }
+        self
+    }
+
+    /// Reject keys that are not declared
+    pub fn deny_additional(mut self) -> Self {
+        if let ShapeKind::Object {
+            ref mut additional, ..
+        } = self.kind
+        {
+            *additional = None;
+        }
+        self
+    }
+
+    /// Validate undeclared keys against `shape` (e.g. a map of strings)
+    pub fn additional(mut self, shape: Shape) -> Self {
+        if let ShapeKind::Object {
+            ref mut additional, ..
+        } = self.kind
+        {
+            *additional = Some(Box::new(shape));
+        }
+        self
+    }
+
+    /// Name of the expected type (e.g. `object`, `string or null`)
+    pub fn type_name(&self) -> String {
+        if let Some(ref description) = self.description {
+            return description.clone();
+        }
+        let name = match self.kind {
+            ShapeKind::Any => "any value".to_string(),
+            ShapeKind::Null => "null".to_string(),
+            ShapeKind::Bool => "boolean".to_string(),
+            ShapeKind::String { .. } => "string".to_string(),
+            ShapeKind::Number { integer: true } => "integer".to_string(),
+            ShapeKind::Number { integer: false } => "number".to_string(),
+            ShapeKind::Array { .. } => "array".to_string(),
+            ShapeKind::Object { .. } => "object".to_string(),
+            ShapeKind::Enum(ref values) => format!(
+                "one of {}",
+                values
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            ShapeKind::OneOf(ref alternatives) | ShapeKind::AnyOf(ref alternatives) => alternatives
+                .iter()
+                .map(Shape::type_name)
+                .collect::<Vec<_>>()
+                .join(" or "),
+        };
+        if self.nullable {
+            format!("{} or null", name)
+        } else {
+            name
+        }
+    }
+
+    /// Validate a document, collecting every error with its JSON pointer
+    pub fn validate(&self, value: &serde_json::Value) -> ValidationErrors {
+        let mut errors = ValidationErrors::new();
+        self.validate_at("", value, &mut errors);
+        errors
+    }
+
+    /// Whether a document matches without errors
+    pub fn matches(&self, value: &serde_json::Value) -> bool {
+        self.validate(value).is_empty()
+    }
+
+    fn validate_at(&self, path: &str, value: &serde_json::Value, errors: &mut ValidationErrors) {
+        use serde_json::Value;
+
+        if value.is_null() && self.nullable {
+            return;
+        }
+
+        match (&self.kind, value) {
+            (ShapeKind::Any, _)
+            | (ShapeKind::Null, Value::Null)
+            | (ShapeKind::Bool, Value::Bool(_)) => {}
+            (ShapeKind::String { pattern }, Value::String(_)) => {
+                errors.check(path, value, &self.length_constraint());
+                if let (Some(re), Value::String(s)) = (pattern, value) {
+                    if !re.is_match(s) {
+                        errors.push_pattern_error(path, value, re.as_str());
+                    }
+                }
+            }
+            (ShapeKind::Number { integer }, Value::Number(n)) => {
+                if *integer && !(n.is_i64() || n.is_u64()) {
+                    self.type_error(path, value, errors);
+                    return;
+                }
+                errors.check(
+                    path,
+                    value,
+                    &Constraint::Range {
+                        min: self.min,
+                        max: self.max,
+                    },
+                );
+            }
+            (ShapeKind::Array { items }, Value::Array(values)) => {
+                errors.check(path, value, &self.length_constraint());
+                for (index, item) in values.iter().enumerate() {
+                    items.validate_at(&format!("{}/{}", path, index), item, errors);
+                }
+            }
+            (
+                ShapeKind::Object {
+                    properties,
+                    additional,
+                },
+                Value::Object(object),
+            ) => {
+                for (key, shape, required) in properties {
+                    let child = format!("{}/{}", path, escape_pointer(key));
+                    match object.get(key) {
+                        Some(v) => shape.validate_at(&child, v, errors),
+                        None if *required => errors.push(
+                            ValidationError::new(&child, Value::Null, Constraint::Required)
+                                .message(&format!("missing required key \"{}\"", key)),
+                        ),
+                        None => {}
+                    }
+                }
+
+                let known: Vec<&str> = properties.iter().map(|(k, _, _)| k.as_str()).collect();
+                for (key, v) in object {
+                    if known.contains(&key.as_str()) {
+                        continue;
+                    }
+                    let child = format!("{}/{}", path, escape_pointer(key));
+                    match additional {
+                        Some(shape) => shape.validate_at(&child, v, errors),
+                        None => errors.push(
+                            ValidationError::new(
+                                &child,
+                                v.clone(),
+                                Constraint::NoAdditionalProperties,
+                            )
+                            .message(&format!("unknown key \"{}\"", key))
+                            .suggestion(suggest(key, &known)),
+                        ),
+                    }
+                }
+            }
+            (ShapeKind::Enum(allowed), _) => {
+                if allowed.contains(value) {
+                    return;
+                }
+                let names: Vec<String> = allowed
+                    .iter()
+                    .map(|v| {
+                        v.as_str()
+                            .map(str::to_string)
+                            .unwrap_or_else(|| v.to_string())
+                    })
+                    .collect();
+                match value.as_str() {
+                    Some(s) if allowed.iter().all(Value::is_string) => {
+                        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+                        errors.check_one_of(path, s, &names);
+                    }
+                    _ => {
+                        let constraint = Constraint::OneOf { allowed: names };
+                        let message = format!("invalid value {}, expected {}", value, constraint);
+                        errors.push(
+                            ValidationError::new(path, value.clone(), constraint).message(&message),
+                        );
+                    }
+                }
+            }
+            (ShapeKind::AnyOf(alternatives), _) => {
+                if !alternatives.iter().any(|shape| shape.matches(value)) {
+                    self.union_error(path, value, alternatives, errors);
+                }
+            }
+            (ShapeKind::OneOf(alternatives), _) => {
+                let matched = alternatives
+                    .iter()
+                    .filter(|shape| shape.matches(value))
+                    .count();
+                match matched {
+                    1 => {}
+                    0 => self.union_error(path, value, alternatives, errors),
+                    n => {
+                        let constraint = self.union_constraint(alternatives);
+                        let message =
+                            format!("value matches {} alternatives, expected {}", n, constraint);
+                        errors.push(
+                            ValidationError::new(path, value.clone(), constraint).message(&message),
+                        );
+                    }
+                }
+            }
+            _ => self.type_error(path, value, errors),
+        }
+    }
+
+    fn length_constraint(&self) -> Constraint {
+        Constraint::Length {
+            min: self.min_length,
+            max: self.max_length,
+        }
+    }
+
+    fn type_error(&self, path: &str, value: &serde_json::Value, errors: &mut ValidationErrors) {
+        let expected = self.type_name();
+        let message = format!("expected {}, found {}", expected, json_type_name(value));
+        errors.push(
+            ValidationError::new(path, value.clone(), Constraint::Type { expected })
+                .message(&message),
+        );
+    }
+
+    fn union_constraint(&self, alternatives: &[Shape]) -> Constraint {
+        Constraint::Union {
+            alternatives: alternatives.iter().map(Shape::type_name).collect(),
+        }
+    }
+
+    /// Report why no alternative matched
+    ///
+    /// When exactly one alternative accepts the value's JSON type, its errors are
+    /// more useful than a generic mismatch, so those are reported instead.
+    fn union_error(
+        &self,
+        path: &str,
+        value: &serde_json::Value,
+        alternatives: &[Shape],
+        errors: &mut ValidationErrors,
+    ) {
+        let candidates: Vec<&Shape> = alternatives
+            .iter()
+            .filter(|shape| shape.accepts_type_of(value))
+            .collect();
+        if let [candidate] = candidates.as_slice() {
+            candidate.validate_at(path, value, errors);
+            return;
+        }
+
+        let constraint = self.union_constraint(alternatives);
+        let message = format!(
+            "{} does not match any alternative, expected {}",
+            json_type_name(value),
+            constraint
+        );
+        errors.push(ValidationError::new(path, value.clone(), constraint).message(&message));
+    }
+
+    fn accepts_type_of(&self, value: &serde_json::Value) -> bool {
+        use serde_json::Value;
+
+        if value.is_null() && self.nullable {
+            return true;
+        }
+        match (&self.kind, value) {
+            (ShapeKind::Any, _)
+            | (ShapeKind::Null, Value::Null)
+            | (ShapeKind::Bool, Value::Bool(_))
+            | (ShapeKind::String { .. }, Value::String(_))
+            | (ShapeKind::Number { .. }, Value::Number(_))
+            | (ShapeKind::Array { .. }, Value::Array(_))
+            | (ShapeKind::Object { .. }, Value::Object(_)) => true,
+            (ShapeKind::Enum(allowed), _) => allowed
+                .iter()
+                .any(|a| json_type_name(a) == json_type_name(value)),
+            (ShapeKind::OneOf(alternatives) | ShapeKind::AnyOf(alternatives), _) => alternatives
+                .iter()
+                .any(|shape| shape.accepts_type_of(value)),
+            _ => false,
+        }
+    }
+}
+
+/// JSON type of a value as used in error messages
+pub fn json_type_name(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(n) if n.is_f64() => "number",
+        serde_json::Value::Number(_) => "integer",
+        serde_json::Value::String(_) => "string",
+        serde_json::Value::Array(_) => "array",
+        serde_json::Value::Object(_) => "object",
+    }
+}
+
+/// Escape an object key for use in a JSON pointer (RFC 6901)
+pub fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
 /// Schema validation trait for custom types
 pub trait Validate {
     type Error;
@@ -221,9 +1777,9 @@ impl StringValidator {
 
         if let Some(ref re) = self.pattern {
             if !re.is_match(value) {
-                return ValidationResult::Failure(format!(
-                    "String does not match required pattern"
-                ));
+                return ValidationResult::Failure(
+                    "String does not match required pattern".to_string(),
+                );
             }
         }
 
@@ -231,6 +1787,12 @@ impl StringValidator {
     }
 }
 
+impl Default for StringValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Number validator with constraints
 pub struct NumberValidator {
     minimum: Option<f64>,
@@ -283,6 +1845,12 @@ impl NumberValidator {
     }
 }
 
+impl Default for NumberValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Array validator with item validation
 pub struct ArrayValidator<F> {
     min_length: Option<usize>,
@@ -358,6 +1926,7 @@ pub struct Args {
     pub dry_run: bool,
     pub config_file: Option<String>,
     pub log_level: Option<String>,
+    pub format: Option<String>,
     pub extra: HashMap<String, String>,
 }
 
@@ -369,18 +1938,36 @@ impl Args {
             dry_run: args.get("dry-run").map(|v| v == "true").unwrap_or(false),
             config_file: args.get("config").cloned(),
             log_level: args.get("log-level").cloned(),
+            format: args.get("format").cloned(),
             extra: args,
         }
     }
 
-    /// Validate argument values
+    /// Output format selected with `--format`, defaulting to a table
+    pub fn output_format(&self) -> Result<OutputFormat> {
+        self.format
+            .as_deref()
+            .map(str::parse)
+            .unwrap_or(Ok(OutputFormat::Table))
+    }
+
+    /// Validate argument values, reporting every problem at once
     pub fn validate(&self) -> Result<()> {
+        self.validation_errors().into_result()
+    }
+
+    /// Collect every invalid argument, located by flag name (e.g. `/log-level`)
+    pub fn validation_errors(&self) -> ValidationErrors {
+        let mut errors = ValidationErrors::new();
         if let Some(ref level) = self.log_level {
-            if !["debug", "info", "warn", "error"].contains(&level.as_str()) {
-                return Err(anyhow::anyhow!("Invalid log level: {}", level));
+            errors.check_one_of("/log-level", level, &LOG_LEVELS);
+        }
+        if let Some(ref format) = self.format {
+            if format.parse::<OutputFormat>().is_err() {
+                errors.check_one_of("/format", format, &OutputFormat::NAMES);
             }
         }
-        Ok(())
+        errors
     }
 }
 
@@ -446,6 +2033,8 @@ pub mod json {
 /// Path validation utilities
 pub mod path {
     use super::*;
+    use crate::lib::preflight;
+    use crate::lib::progress::format_bytes;
     use std::path::Path;
 
     /// Validate that a path exists
@@ -481,27 +2070,68 @@ pub mod path {
         }
     }
 
-    /// Validate that a path is writable
+    /// Validate that the effective user can write `path` (or create it)
+    ///
+    /// Uses faccessat(2), so read-only mounts and root-owned paths fail.
     pub fn validate_writable(path: &str) -> ValidationResult<String> {
-        let p = Path::new(path);
-        let check_path = if p.exists() {
-            p.to_path_buf()
-        } else {
-            p.parent()
-                .map(|parent| parent.to_path_buf())
-                .unwrap_or_else(|| Path::new(".").to_path_buf())
-        };
+        match preflight::check_writable(Path::new(path)) {
+            Ok(()) => ValidationResult::Success(path.to_string()),
+            Err(e) => ValidationResult::Failure(format!("Path is not writable: {:#}", e)),
+        }
+    }
 
-        if check_path.exists() {
-            // Try to check if we can write
-            let metadata = std::fs::metadata(&check_path);
-            if metadata.is_ok() {
-                ValidationResult::Success(path.to_string())
-            } else {
-                ValidationResult::Failure(format!("Path is not accessible: {}", path))
+    /// Validate that the effective user can read `path`
+    pub fn validate_readable(path: &str) -> ValidationResult<String> {
+        match preflight::check_readable(Path::new(path)) {
+            Ok(()) => ValidationResult::Success(path.to_string()),
+            Err(e) => ValidationResult::Failure(format!("Path is not readable: {:#}", e)),
+        }
+    }
+
+    /// Validate that the filesystem holding `path` has `required` bytes free
+    ///
+    /// Succeeds with the available byte count.
+    pub fn validate_free_space(path: &str, required: u64) -> ValidationResult<u64> {
+        match preflight::available_space(Path::new(path)) {
+            Ok(available) if available >= required => ValidationResult::Success(available),
+            Ok(available) => ValidationResult::Failure(format!(
+                "Not enough free space for {}: needs {}, {} available",
+                path,
+                format_bytes(required),
+                format_bytes(available)
+            )),
+            Err(e) => ValidationResult::Failure(format!("{:#}", e)),
+        }
+    }
+
+    /// Validate that programs under `path` can run (the mount is not `noexec`)
+    pub fn validate_executable_mount(path: &str) -> ValidationResult<String> {
+        match preflight::find_mount(Path::new(path)) {
+            Ok(mount) if mount.is_noexec() => ValidationResult::Failure(format!(
+                "{} is on a noexec mount ({})",
+                path,
+                mount.mount_point.display()
+            )),
+            Ok(_) => ValidationResult::Success(path.to_string()),
+            Err(e) => ValidationResult::Failure(format!("{:#}", e)),
+        }
+    }
+
+    /// Validate that `path` is on one of the `allowed` filesystem types
+    ///
+    /// Succeeds with the filesystem type (e.g. `ext4`).
+    pub fn validate_filesystem(path: &str, allowed: &[&str]) -> ValidationResult<String> {
+        match preflight::filesystem_type(Path::new(path)) {
+            Ok(fs_type) if allowed.contains(&fs_type.as_str()) => {
+                ValidationResult::Success(fs_type)
             }
-        } else {
-            ValidationResult::Failure(format!("Parent directory does not exist: {}", path))
+            Ok(fs_type) => ValidationResult::Failure(format!(
+                "{} is on {}, expected {}",
+                path,
+                fs_type,
+                allowed.join(", ")
+            )),
+            Err(e) => ValidationResult::Failure(format!("{:#}", e)),
         }
     }
 }
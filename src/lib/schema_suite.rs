@@ -0,0 +1,797 @@
+// Schema test suites for Ubuntu Config Scripts
+//
+// This module runs the YAML test definitions under `schemas/` including:
+// - Loading suites (shared definitions plus a list of variants)
+// - Generating a Ruchy program for each variant
+// - Running it through `ruchydbg run` with a per-variant timeout
+// - Comparing the outcome with the expected one (pass, fail, timeout, unknown)
+// - Text, TAP, JUnit XML and JSON reports
+
+use crate::lib::render::{Align, Column, OutputFormat, Table};
+use crate::lib::schema::ConfigFormat;
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+/// Directory holding the schema suites, relative to the repository root
+pub const SCHEMA_DIR: &str = "schemas";
+
+/// Command used to run generated programs; the file and `--timeout <ms>` are appended
+pub const DEFAULT_RUNNER: [&str; 2] = ["ruchydbg", "run"];
+
+/// Exit code the runner uses to report a timeout
+pub const TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// Timeout used when neither the variant nor the suite sets one
+const DEFAULT_TIMEOUT_MS: u64 = 5000;
+
+/// Extra time given to the runner before it is killed for ignoring its own timeout
+const KILL_GRACE: Duration = Duration::from_secs(2);
+
+/// What was known about a variant when the suite was written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VariantStatus {
+    VerifiedPass,
+    VerifiedFail,
+    Untested,
+}
+
+/// Outcome a variant is expected to have
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Expectation {
+    Pass,
+    Fail,
+    Timeout,
+    /// Any outcome is accepted
+    Unknown,
+}
+
+impl Expectation {
+    /// Whether an outcome satisfies this expectation
+    pub fn accepts(self, outcome: Outcome) -> bool {
+        matches!(
+            (self, outcome),
+            (_, Outcome::Skipped)
+                | (Expectation::Unknown, _)
+                | (Expectation::Pass, Outcome::Pass)
+                | (Expectation::Fail, Outcome::Fail)
+                | (Expectation::Timeout, Outcome::Timeout)
+        )
+    }
+}
+
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Expectation::Pass => "pass",
+            Expectation::Fail => "fail",
+            Expectation::Timeout => "timeout",
+            Expectation::Unknown => "unknown",
+        };
+        f.write_str(name)
+    }
+}
+
+/// One test case of a suite
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Variant {
+    pub id: String,
+    pub name: String,
+    pub status: VariantStatus,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub expected: Expectation,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// Lines placed before the shared definitions (e.g. `use chrono::Utc;`)
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    /// Methods placed in the `impl` block of the shared struct
+    #[serde(default)]
+    pub impl_method: Option<String>,
+    /// Body of `main`
+    #[serde(default)]
+    pub test_code: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Counts recorded in the suite file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuiteStats {
+    pub total_variants: usize,
+    pub verified_pass: usize,
+    pub verified_fail: usize,
+    pub untested: usize,
+    pub coverage_percent: f64,
+}
+
+/// Run settings recorded in the suite file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SuiteConfig {
+    pub default_timeout_ms: u64,
+    pub parallel_execution: bool,
+    pub stop_on_first_failure: bool,
+    pub generate_minimal_reproductions: bool,
+}
+
+impl Default for SuiteConfig {
+    fn default() -> Self {
+        Self {
+            default_timeout_ms: DEFAULT_TIMEOUT_MS,
+            parallel_execution: false,
+            stop_on_first_failure: false,
+            generate_minimal_reproductions: false,
+        }
+    }
+}
+
+/// A schema test suite loaded from `schemas/*.yaml`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaSuite {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub issue_url: Option<String>,
+    #[serde(default)]
+    pub ruchy_version: Option<String>,
+    /// Enum shared by every variant
+    #[serde(default)]
+    pub enum_definition: String,
+    /// Struct shared by every variant; variant methods are implemented on it
+    #[serde(default)]
+    pub struct_definition: String,
+    pub variants: Vec<Variant>,
+    #[serde(default)]
+    pub stats: Option<SuiteStats>,
+    #[serde(default)]
+    pub test_config: SuiteConfig,
+}
+
+impl SchemaSuite {
+    /// Parse a suite from YAML
+    pub fn from_yaml(content: &str) -> Result<Self> {
+        let suite: SchemaSuite = ConfigFormat::Yaml.parse(content)?;
+        let mut ids = std::collections::HashSet::new();
+        if let Some(variant) = suite.variants.iter().find(|v| !ids.insert(v.id.as_str())) {
+            return Err(anyhow!("Duplicate variant id: {}", variant.id));
+        }
+        Ok(suite)
+    }
+
+    /// Load a suite from a YAML file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read schema suite: {}", path.display()))?;
+        Self::from_yaml(&content)
+            .with_context(|| format!("Failed to parse schema suite: {}", path.display()))
+    }
+
+    /// Name of the struct in `struct_definition` (the `impl` target)
+    pub fn struct_name(&self) -> Option<String> {
+        let re = Regex::new(r"struct\s+([A-Za-z_][A-Za-z0-9_]*)").expect("valid regex");
+        re.captures(&self.struct_definition)
+            .map(|captures| captures[1].to_string())
+    }
+
+    pub fn variant(&self, id: &str) -> Option<&Variant> {
+        self.variants.iter().find(|v| v.id == id)
+    }
+
+    /// Timeout for a variant, falling back to the suite default
+    pub fn timeout_for(&self, variant: &Variant) -> Duration {
+        Duration::from_millis(
+            variant
+                .timeout_ms
+                .unwrap_or(self.test_config.default_timeout_ms),
+        )
+    }
+
+    /// Ruchy program for a variant: dependencies, shared definitions, methods and `main`
+    pub fn program(&self, variant: &Variant) -> Result<String> {
+        let test_code = variant
+            .test_code
+            .as_deref()
+            .ok_or_else(|| anyhow!("Variant {} has no test_code", variant.id))?;
+
+        let mut parts: Vec<String> = Vec::new();
+        if !variant.dependencies.is_empty() {
+            parts.extend(variant.dependencies.iter().cloned());
+            parts.push(String::new());
+        }
+        for definition in [&self.enum_definition, &self.struct_definition] {
+            if !definition.trim().is_empty() {
+                parts.push(definition.trim().to_string());
+                parts.push(String::new());
+            }
+        }
+        if let Some(methods) = variant.impl_method.as_deref() {
+            let target = self
+                .struct_name()
+                .ok_or_else(|| anyhow!("Variant {} has methods but no struct", variant.id))?;
+            parts.push(format!("impl {} {{", target));
+            parts.push(methods.trim().to_string());
+            parts.push("}".to_string());
+            parts.push(String::new());
+        }
+        parts.push("fun main() {".to_string());
+        parts.push(test_code.trim().to_string());
+        parts.push("}".to_string());
+        Ok(parts.join("\n"))
+    }
+}
+
+/// Schema suites in a directory, sorted by file name
+pub fn find_suites(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read schema directory: {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("yaml") | Some("yml")
+            )
+        })
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Result of running one variant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Pass,
+    Fail,
+    Timeout,
+    /// The program could not be generated or the runner could not be started
+    Error,
+    /// The variant is disabled
+    Skipped,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Outcome::Pass => "pass",
+            Outcome::Fail => "fail",
+            Outcome::Timeout => "timeout",
+            Outcome::Error => "error",
+            Outcome::Skipped => "skipped",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Outcome of one variant compared with its expectation
+#[derive(Debug, Clone, Serialize)]
+pub struct VariantResult {
+    pub id: String,
+    pub name: String,
+    pub status: VariantStatus,
+    pub expected: Expectation,
+    pub outcome: Outcome,
+    pub duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub stdout: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub stderr: String,
+    /// Why the variant errored or was skipped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl VariantResult {
+    fn new(variant: &Variant, outcome: Outcome) -> Self {
+        Self {
+            id: variant.id.clone(),
+            name: variant.name.clone(),
+            status: variant.status,
+            expected: variant.expected,
+            outcome,
+            duration_ms: 0,
+            exit_code: None,
+            stdout: String::new(),
+            stderr: String::new(),
+            message: None,
+        }
+    }
+
+    /// Whether the outcome is the expected one
+    pub fn matched(&self) -> bool {
+        self.expected.accepts(self.outcome)
+    }
+
+    /// A variant recorded as passing that no longer passes
+    pub fn is_regression(&self) -> bool {
+        self.status == VariantStatus::VerifiedPass
+            && !matches!(self.outcome, Outcome::Pass | Outcome::Skipped)
+    }
+
+    /// A variant not tested before that now passes
+    pub fn is_new_fix(&self) -> bool {
+        self.status == VariantStatus::Untested && self.outcome == Outcome::Pass
+    }
+}
+
+/// Runs the variants of a suite
+#[derive(Debug, Clone)]
+pub struct SuiteRunner {
+    command: Vec<String>,
+    only: Vec<String>,
+    timeout: Option<Duration>,
+    stop_on_first_failure: Option<bool>,
+}
+
+impl SuiteRunner {
+    pub fn new() -> Self {
+        Self {
+            command: DEFAULT_RUNNER.iter().map(|s| s.to_string()).collect(),
+            only: Vec::new(),
+            timeout: None,
+            stop_on_first_failure: None,
+        }
+    }
+
+    /// Runner command; the program file and `--timeout <ms>` are appended
+    pub fn command(mut self, command: Vec<String>) -> Self {
+        self.command = command;
+        self
+    }
+
+    /// Run only the variants with these ids
+    pub fn only(mut self, ids: Vec<String>) -> Self {
+        self.only = ids;
+        self
+    }
+
+    /// Use this timeout for every variant instead of the suite values
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Override the suite's `stop_on_first_failure` setting
+    pub fn stop_on_first_failure(mut self, stop: bool) -> Self {
+        self.stop_on_first_failure = Some(stop);
+        self
+    }
+
+    /// Whether the runner command is installed
+    pub fn is_available(&self) -> bool {
+        self.command
+            .first()
+            .is_some_and(|program| which::which(program).is_ok())
+    }
+
+    /// Run every selected variant of a suite in order
+    pub async fn run(&self, suite: &SchemaSuite) -> Result<SuiteReport> {
+        for id in &self.only {
+            if suite.variant(id).is_none() {
+                return Err(anyhow!("Unknown variant in {}: {}", suite.name, id));
+            }
+        }
+        let stop_on_failure = self
+            .stop_on_first_failure
+            .unwrap_or(suite.test_config.stop_on_first_failure);
+
+        let mut results = Vec::new();
+        let mut stopped = false;
+        for variant in &suite.variants {
+            if !self.only.is_empty() && !self.only.contains(&variant.id) {
+                continue;
+            }
+            let result = if stopped {
+                let mut result = VariantResult::new(variant, Outcome::Skipped);
+                result.message = Some("stopped after first failure".to_string());
+                result
+            } else if !variant.enabled {
+                let mut result = VariantResult::new(variant, Outcome::Skipped);
+                result.message = Some("disabled".to_string());
+                result
+            } else {
+                self.run_variant(suite, variant).await
+            };
+            stopped = stopped || (stop_on_failure && !result.matched());
+            results.push(result);
+        }
+
+        Ok(SuiteReport {
+            name: suite.name.clone(),
+            description: suite.description.clone(),
+            results,
+        })
+    }
+
+    async fn run_variant(&self, suite: &SchemaSuite, variant: &Variant) -> VariantResult {
+        let mut result = VariantResult::new(variant, Outcome::Error);
+        let timeout = self.timeout.unwrap_or_else(|| suite.timeout_for(variant));
+        let start = Instant::now();
+        match self.execute(suite, variant, timeout).await {
+            Ok((outcome, code, stdout, stderr)) => {
+                result.outcome = outcome;
+                result.exit_code = code;
+                result.stdout = stdout;
+                result.stderr = stderr;
+            }
+            Err(e) => result.message = Some(format!("{:#}", e)),
+        }
+        result.duration_ms = start.elapsed().as_millis() as u64;
+        result
+    }
+
+    async fn execute(
+        &self,
+        suite: &SchemaSuite,
+        variant: &Variant,
+        timeout: Duration,
+    ) -> Result<(Outcome, Option<i32>, String, String)> {
+        let program = suite.program(variant)?;
+        let (runner, args) = self
+            .command
+            .split_first()
+            .ok_or_else(|| anyhow!("Runner command cannot be empty"))?;
+
+        let file = tempfile::Builder::new()
+            .prefix(&format!("{}-", variant.id))
+            .suffix(".ruchy")
+            .tempfile()
+            .context("Failed to create program file")?;
+        std::fs::write(file.path(), program).context("Failed to write program file")?;
+
+        let child = Command::new(runner)
+            .args(args)
+            .arg(file.path())
+            .arg("--timeout")
+            .arg(timeout.as_millis().to_string())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to execute command: {}", runner))?;
+
+        let output =
+            match tokio::time::timeout(timeout + KILL_GRACE, child.wait_with_output()).await {
+                Ok(output) => output.with_context(|| format!("Failed to wait for {}", runner))?,
+                Err(_) => return Ok((Outcome::Timeout, None, String::new(), String::new())),
+            };
+
+        let code = output.status.code();
+        let outcome = match code {
+            Some(0) => Outcome::Pass,
+            Some(TIMEOUT_EXIT_CODE) => Outcome::Timeout,
+            _ => Outcome::Fail,
+        };
+        Ok((
+            outcome,
+            code,
+            String::from_utf8_lossy(&output.stdout).to_string(),
+            String::from_utf8_lossy(&output.stderr).to_string(),
+        ))
+    }
+}
+
+impl Default for SuiteRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Report format selected with `--format`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ReportFormat {
+    #[default]
+    Text,
+    Tap,
+    Junit,
+    Json,
+}
+
+impl ReportFormat {
+    pub const NAMES: [&'static str; 4] = ["text", "tap", "junit", "json"];
+}
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "text" | "table" => Ok(ReportFormat::Text),
+            "tap" => Ok(ReportFormat::Tap),
+            "junit" | "xml" => Ok(ReportFormat::Junit),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(anyhow!(
+                "Invalid report format: {} (expected one of: {})",
+                s,
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ReportFormat::Text => "text",
+            ReportFormat::Tap => "tap",
+            ReportFormat::Junit => "junit",
+            ReportFormat::Json => "json",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Results of running one suite
+#[derive(Debug, Clone, Serialize)]
+pub struct SuiteReport {
+    pub name: String,
+    pub description: String,
+    pub results: Vec<VariantResult>,
+}
+
+impl SuiteReport {
+    pub fn count(&self, outcome: Outcome) -> usize {
+        self.results.iter().filter(|r| r.outcome == outcome).count()
+    }
+
+    /// Variants that ran (not skipped)
+    pub fn executed(&self) -> usize {
+        self.results.len() - self.count(Outcome::Skipped)
+    }
+
+    pub fn matched(&self) -> usize {
+        self.results.iter().filter(|r| r.matched()).count()
+    }
+
+    /// Variants whose outcome differs from the expected one
+    pub fn mismatches(&self) -> Vec<&VariantResult> {
+        self.results.iter().filter(|r| !r.matched()).collect()
+    }
+
+    pub fn regressions(&self) -> Vec<&VariantResult> {
+        self.results.iter().filter(|r| r.is_regression()).collect()
+    }
+
+    pub fn new_fixes(&self) -> Vec<&VariantResult> {
+        self.results.iter().filter(|r| r.is_new_fix()).collect()
+    }
+
+    /// Every variant met its expectation
+    pub fn is_success(&self) -> bool {
+        self.results.iter().all(|r| r.matched())
+    }
+
+    pub fn render(&self, format: ReportFormat) -> Result<String> {
+        Ok(match format {
+            ReportFormat::Text => self.to_text(),
+            ReportFormat::Tap => self.to_tap(),
+            ReportFormat::Junit => junit_report(std::slice::from_ref(self)),
+            ReportFormat::Json => serde_json::to_string_pretty(self)?,
+        })
+    }
+
+    /// Result table followed by a summary
+    pub fn to_text(&self) -> String {
+        let mut table = Table::new(vec![
+            Column::new("Variant"),
+            Column::new("Name").max_width(48),
+            Column::new("Expected"),
+            Column::new("Result"),
+            Column::new("Time").align(Align::Right),
+            Column::new(""),
+        ]);
+        for result in &self.results {
+            table.add_row(vec![
+                result.id.clone(),
+                result.name.clone(),
+                result.expected.to_string(),
+                result.outcome.to_string(),
+                format!("{}ms", result.duration_ms),
+                if result.matched() { "✅" } else { "❌" }.to_string(),
+            ]);
+        }
+
+        let mut out = format!("📋 {}\n", self.name);
+        if !self.description.is_empty() {
+            out.push_str(&format!("   {}\n", self.description));
+        }
+        out.push_str(&table.render(OutputFormat::Table));
+        out.push_str(&format!(
+            "\n{} passed, {} failed, {} timed out, {} errors, {} skipped; {}/{} matched expectations\n",
+            self.count(Outcome::Pass),
+            self.count(Outcome::Fail),
+            self.count(Outcome::Timeout),
+            self.count(Outcome::Error),
+            self.count(Outcome::Skipped),
+            self.matched(),
+            self.results.len()
+        ));
+        for result in self.mismatches() {
+            out.push_str(&format!(
+                "  ⚠️  {}: expected {}, got {}{}\n",
+                result.id,
+                result.expected,
+                result.outcome,
+                result
+                    .message
+                    .as_deref()
+                    .map(|m| format!(" ({})", m))
+                    .unwrap_or_default()
+            ));
+        }
+        for result in self.regressions() {
+            out.push_str(&format!(
+                "  🚨 regression: {} ({})\n",
+                result.name, result.outcome
+            ));
+        }
+        for result in self.new_fixes() {
+            out.push_str(&format!("  🎉 now passes: {}\n", result.name));
+        }
+        out
+    }
+
+    /// Test Anything Protocol (version 13) output
+    pub fn to_tap(&self) -> String {
+        let mut out = format!(
+            "TAP version 13\n# {}\n1..{}\n",
+            self.name,
+            self.results.len()
+        );
+        for (index, result) in self.results.iter().enumerate() {
+            let number = index + 1;
+            let description = format!("{} - {}", result.id, result.name);
+            if result.outcome == Outcome::Skipped {
+                out.push_str(&format!(
+                    "ok {} - {} # SKIP {}\n",
+                    number,
+                    description,
+                    result.message.as_deref().unwrap_or("skipped")
+                ));
+                continue;
+            }
+
+            let status = if result.matched() { "ok" } else { "not ok" };
+            out.push_str(&format!("{} {} - {}\n", status, number, description));
+            if !result.matched() {
+                out.push_str("  ---\n");
+                out.push_str(&format!("  expected: {}\n", result.expected));
+                out.push_str(&format!("  got: {}\n", result.outcome));
+                out.push_str(&format!("  duration_ms: {}\n", result.duration_ms));
+                if let Some(code) = result.exit_code {
+                    out.push_str(&format!("  exit_code: {}\n", code));
+                }
+                for (key, text) in [
+                    ("message", result.message.as_deref().unwrap_or_default()),
+                    ("stderr", result.stderr.as_str()),
+                ] {
+                    if !text.trim().is_empty() {
+                        out.push_str(&format!("  {}: |\n", key));
+                        for line in text.trim_end().lines() {
+                            out.push_str(&format!("    {}\n", line));
+                        }
+                    }
+                }
+                out.push_str("  ...\n");
+            }
+        }
+        out
+    }
+}
+
+/// JUnit XML with one `<testsuite>` per report
+pub fn junit_report(reports: &[SuiteReport]) -> String {
+    let count = |f: &dyn Fn(&VariantResult) -> bool| -> usize {
+        reports
+            .iter()
+            .flat_map(|r| &r.results)
+            .filter(|r| f(r))
+            .count()
+    };
+    let tests = count(&|_| true);
+    let failures = count(&|r| !r.matched() && r.outcome != Outcome::Error);
+    let errors = count(&|r| !r.matched() && r.outcome == Outcome::Error);
+    let skipped = count(&|r| r.outcome == Outcome::Skipped);
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!(
+        "<testsuites tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\">\n",
+        tests, failures, errors, skipped
+    ));
+    for report in reports {
+        let seconds: u64 = report.results.iter().map(|r| r.duration_ms).sum();
+        out.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+            xml_escape(&report.name),
+            report.results.len(),
+            report
+                .mismatches()
+                .iter()
+                .filter(|r| r.outcome != Outcome::Error)
+                .count(),
+            report
+                .mismatches()
+                .iter()
+                .filter(|r| r.outcome == Outcome::Error)
+                .count(),
+            report.count(Outcome::Skipped),
+            seconds as f64 / 1000.0
+        ));
+        for result in &report.results {
+            out.push_str(&format!(
+                "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+                xml_escape(&report.name),
+                xml_escape(&format!("{} - {}", result.id, result.name)),
+                result.duration_ms as f64 / 1000.0
+            ));
+            let detail = result.message.as_deref().unwrap_or(&result.stderr);
+            if result.outcome == Outcome::Skipped {
+                out.push_str(&format!(
+                    ">\n      <skipped message=\"{}\"/>\n",
+                    xml_escape(result.message.as_deref().unwrap_or("skipped"))
+                ));
+            } else if result.matched() {
+                out.push_str("/>\n");
+                continue;
+            } else {
+                let element = if result.outcome == Outcome::Error {
+                    "error"
+                } else {
+                    "failure"
+                };
+                out.push_str(&format!(
+                    ">\n      <{} message=\"expected {}, got {}\">{}</{}>\n",
+                    element,
+                    result.expected,
+                    result.outcome,
+                    xml_escape(detail.trim_end()),
+                    element
+                ));
+            }
+            if !result.stdout.is_empty() {
+                out.push_str(&format!(
+                    "      <system-out>{}</system-out>\n",
+                    xml_escape(result.stdout.trim_end())
+                ));
+            }
+            out.push_str("    </testcase>\n");
+        }
+        out.push_str("  </testsuite>\n");
+    }
+    out.push_str("</testsuites>\n");
+    out
+}
+
+/// Escape text for XML attributes and element content
+pub fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c if c.is_control() && !matches!(c, '\n' | '\t' | '\r') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,302 @@
+// Secret references in configuration values for Ubuntu Config Scripts
+//
+// This module keeps secrets out of configuration files including:
+// - `${env:NAME}` references to environment variables
+// - `${file:/path}` references to files (trailing newlines are dropped)
+// - `${cmd:pass show x}` references to command output
+// - `SecretString` fields that resolve on first use, register the result
+//   with the redactor and always serialize the reference, never the secret
+//
+// `$${` writes a literal `${`.
+
+use crate::lib::common::expand_tilde;
+use crate::lib::redact::{register_secret, MASK};
+use crate::lib::schema::{
+    Constraint, FieldRule, ValidateFields, ValidationError, ValidationErrors,
+};
+use anyhow::{anyhow, Context, Result};
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::fmt;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+/// Start of a reference
+const REFERENCE_START: &str = "${";
+
+/// Escaped form of a literal `${`
+const ESCAPED_START: &str = "$${";
+
+/// A `${kind:argument}` reference inside a configuration string
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reference {
+    Env(String),
+    File(String),
+    Cmd(String),
+}
+
+impl Reference {
+    /// Parse the inside of `${...}` (e.g. `env:GH_RUNNER_TOKEN`)
+    pub fn parse(inner: &str) -> Result<Self> {
+        let (kind, argument) = inner.split_once(':').ok_or_else(|| {
+            anyhow!(
+                "Reference ${{{}}} has no kind (expected env:, file: or cmd:)",
+                inner
+            )
+        })?;
+        let argument = argument.trim();
+        if argument.is_empty() {
+            return Err(anyhow!("Reference ${{{}}} is empty", inner));
+        }
+        match kind.trim() {
+            "env" => Ok(Reference::Env(argument.to_string())),
+            "file" => Ok(Reference::File(argument.to_string())),
+            "cmd" => Ok(Reference::Cmd(argument.to_string())),
+            other => Err(anyhow!(
+                "Unknown reference kind '{}' (expected env, file or cmd)",
+                other
+            )),
+        }
+    }
+
+    /// Look up the referenced value
+    pub fn resolve(&self) -> Result<String> {
+        match self {
+            Reference::Env(name) => {
+                std::env::var(name).map_err(|_| anyhow!("Environment variable {} is not set", name))
+            }
+            Reference::File(path) => {
+                let path = expand_tilde(path)?;
+                let content = std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read secret file: {}", path))?;
+                Ok(trim_newlines(&content).to_string())
+            }
+            Reference::Cmd(command) => {
+                let output = Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .stdin(Stdio::null())
+                    .output()
+                    .with_context(|| format!("Failed to execute command: {}", command))?;
+                if !output.status.success() {
+                    return Err(anyhow!(
+                        "Command `{}` failed ({}): {}",
+                        command,
+                        output.status,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    ));
+                }
+                let stdout = String::from_utf8(output.stdout)
+                    .with_context(|| format!("Command `{}` printed invalid UTF-8", command))?;
+                Ok(trim_newlines(&stdout).to_string())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reference::Env(name) => write!(f, "${{env:{}}}", name),
+            Reference::File(path) => write!(f, "${{file:{}}}", path),
+            Reference::Cmd(command) => write!(f, "${{cmd:{}}}", command),
+        }
+    }
+}
+
+/// Literal text or a reference within a configuration string
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Reference(Reference),
+}
+
+/// Split a string into literal text and references
+///
+/// Braces inside a reference may nest (`${cmd:awk '{print $1}' f}`).
+fn parse_parts(text: &str) -> Result<Vec<Part>> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find('$') {
+        literal.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if rest.starts_with(ESCAPED_START) {
+            literal.push_str(REFERENCE_START);
+            rest = &rest[ESCAPED_START.len()..];
+        } else if rest.starts_with(REFERENCE_START) {
+            let body = &rest[REFERENCE_START.len()..];
+            let mut depth = 1;
+            let end = body
+                .char_indices()
+                .find(|&(_, c)| {
+                    match c {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        _ => {}
+                    }
+                    depth == 0
+                })
+                .map(|(index, _)| index)
+                .ok_or_else(|| anyhow!("Unterminated reference in '{}'", text))?;
+            if !literal.is_empty() {
+                parts.push(Part::Text(std::mem::take(&mut literal)));
+            }
+            parts.push(Part::Reference(Reference::parse(&body[..end])?));
+            rest = &body[end + 1..];
+        } else {
+            literal.push('$');
+            rest = &rest[1..];
+        }
+    }
+    literal.push_str(rest);
+    if !literal.is_empty() {
+        parts.push(Part::Text(literal));
+    }
+    Ok(parts)
+}
+
+/// References contained in a configuration string
+pub fn references(text: &str) -> Result<Vec<Reference>> {
+    Ok(parse_parts(text)?
+        .into_iter()
+        .filter_map(|part| match part {
+            Part::Reference(reference) => Some(reference),
+            Part::Text(_) => None,
+        })
+        .collect())
+}
+
+/// Replace every reference in `text` with the value it points to
+pub fn resolve_references(text: &str) -> Result<String> {
+    parse_parts(text)?
+        .into_iter()
+        .map(|part| match part {
+            Part::Text(text) => Ok(text),
+            Part::Reference(reference) => reference
+                .resolve()
+                .with_context(|| format!("Failed to resolve {}", reference)),
+        })
+        .collect()
+}
+
+fn trim_newlines(text: &str) -> &str {
+    text.trim_end_matches(['\n', '\r'])
+}
+
+/// Configuration string that may hold secret references
+///
+/// The raw text is what gets serialized, so saving a configuration writes
+/// `${env:GH_RUNNER_TOKEN}` back rather than the token. `resolve` looks the
+/// references up on first use and caches the result.
+#[derive(Clone, Default)]
+pub struct SecretString {
+    raw: String,
+    resolved: OnceLock<String>,
+}
+
+impl SecretString {
+    pub fn new(raw: &str) -> Self {
+        Self {
+            raw: raw.to_string(),
+            resolved: OnceLock::new(),
+        }
+    }
+
+    /// Text as written in the configuration, references unresolved
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    /// Whether the value contains at least one reference
+    pub fn is_reference(&self) -> bool {
+        references(&self.raw).is_ok_and(|refs| !refs.is_empty())
+    }
+
+    /// Value with references resolved, registered with the redactor
+    pub fn resolve(&self) -> Result<&str> {
+        if let Some(value) = self.resolved.get() {
+            return Ok(value);
+        }
+        let value = resolve_references(&self.raw)?;
+        register_secret(&value);
+        Ok(self.resolved.get_or_init(|| value))
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(raw: &str) -> Self {
+        Self::new(raw)
+    }
+}
+
+impl PartialEq for SecretString {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+/// References are shown as written; plaintext values are masked
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.raw.is_empty() || self.is_reference() {
+            write!(f, "SecretString({:?})", self.raw)
+        } else {
+            write!(f, "SecretString({})", MASK)
+        }
+    }
+}
+
+impl Serialize for SecretString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.raw)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|raw| Self::new(&raw))
+    }
+}
+
+impl JsonSchema for SecretString {
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn schema_name() -> Cow<'static, str> {
+        "SecretString".into()
+    }
+
+    fn json_schema(_generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "type": "string",
+            "description": "Plain text or ${env:NAME}, ${file:/path} and ${cmd:command} references"
+        })
+    }
+}
+
+/// Reference syntax is checked when the configuration is validated
+impl ValidateFields for SecretString {
+    fn field_rules() -> Vec<FieldRule> {
+        Vec::new()
+    }
+
+    fn validate_fields(&self, path: &str, errors: &mut ValidationErrors) {
+        if let Err(e) = parse_parts(&self.raw) {
+            errors.push(
+                ValidationError::new(
+                    path,
+                    serde_json::Value::String(self.raw.clone()),
+                    Constraint::Custom {
+                        description: "plain text or ${env:..}, ${file:..} and ${cmd:..} references"
+                            .to_string(),
+                    },
+                )
+                .message(&e.to_string()),
+            );
+        }
+    }
+}
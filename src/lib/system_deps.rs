@@ -0,0 +1,483 @@
+// System dependency manifest for Ubuntu Config Scripts
+//
+// This module reads the declarative list of system requirements in
+// `config/system-deps.toml`, including:
+// - Capabilities with the commands that provide them
+// - Package names for apt, dnf (also used by yum) and pacman
+// - Minimum versions read from each command's `--version` output
+// - The scripts that need each capability, so a binary checks only its own
+// - Core tools (service manager, build toolchain) for the full dependency check
+// - An install plan for whatever is missing or too old
+//
+// The manifest is embedded in every binary; `UCS_SYSTEM_DEPS` points at a
+// replacement file.
+
+use crate::lib::render::{Column, Table};
+use anyhow::{anyhow, Context, Result};
+use regex::Regex;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command as StdCommand;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// Environment variable naming a manifest to use instead of the built-in one
+pub const SYSTEM_DEPS_ENV: &str = "UCS_SYSTEM_DEPS";
+
+/// Manifest shipped with the scripts
+pub const BUILTIN_SYSTEM_DEPS: &str = include_str!("../../config/system-deps.toml");
+
+/// `scripts` entry matching every script
+pub const ALL_SCRIPTS: &str = "*";
+
+/// Distribution package manager
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageManager {
+    Apt,
+    Dnf,
+    /// Older Fedora and RHEL releases; installs the packages listed for dnf
+    Yum,
+    Pacman,
+}
+
+impl PackageManager {
+    pub const NAMES: [&'static str; 4] = ["apt", "dnf", "yum", "pacman"];
+    pub const ALL: [PackageManager; 4] = [
+        PackageManager::Apt,
+        PackageManager::Dnf,
+        PackageManager::Yum,
+        PackageManager::Pacman,
+    ];
+
+    /// The first package manager found on PATH
+    pub fn detect() -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|manager| which::which(manager.to_string()).is_ok())
+    }
+
+    /// Command installing the given packages without prompting
+    pub fn install_command(&self, packages: &[String]) -> Vec<String> {
+        let mut command: Vec<String> = match self {
+            PackageManager::Apt => vec!["sudo", "apt", "install", "-y"],
+            PackageManager::Dnf => vec!["sudo", "dnf", "install", "-y"],
+            PackageManager::Yum => vec!["sudo", "yum", "install", "-y"],
+            PackageManager::Pacman => vec!["sudo", "pacman", "-S", "--needed", "--noconfirm"],
+        }
+        .into_iter()
+        .map(String::from)
+        .collect();
+        command.extend(packages.iter().cloned());
+        command
+    }
+}
+
+impl FromStr for PackageManager {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "apt" | "apt-get" => Ok(PackageManager::Apt),
+            "dnf" => Ok(PackageManager::Dnf),
+            "yum" => Ok(PackageManager::Yum),
+            "pacman" => Ok(PackageManager::Pacman),
+            _ => Err(anyhow!(
+                "Invalid package manager: {} (expected one of: {})",
+                s,
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for PackageManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PackageManager::Apt => "apt",
+            PackageManager::Dnf => "dnf",
+            PackageManager::Yum => "yum",
+            PackageManager::Pacman => "pacman",
+        };
+        f.write_str(name)
+    }
+}
+
+/// One capability the scripts rely on
+#[derive(Debug, Clone, PartialEq)]
+pub struct Requirement {
+    pub name: String,
+    pub description: String,
+    /// Commands that must all be on PATH
+    pub commands: Vec<String>,
+    /// Packages providing the commands, per package manager
+    pub packages: BTreeMap<PackageManager, Vec<String>>,
+    pub min_version: Option<Version>,
+    /// Command printing the installed version
+    pub version_command: Vec<String>,
+    /// Scripts needing it (`*` for every script)
+    pub scripts: Vec<String>,
+    /// Checked by the full dependency check whichever scripts need it
+    pub core: bool,
+}
+
+impl Requirement {
+    /// Packages for a package manager (empty when the distro has none)
+    pub fn packages_for(&self, manager: PackageManager) -> &[String] {
+        self.packages.get(&manager).map_or(&[], |p| p.as_slice())
+    }
+
+    pub fn needed_by(&self, script: &str) -> bool {
+        self.scripts.iter().any(|s| s == ALL_SCRIPTS || s == script)
+    }
+
+    /// Check the commands and, if a minimum is set, the installed version
+    pub fn check(&self) -> RequirementCheck {
+        let missing: Vec<String> = self
+            .commands
+            .iter()
+            .filter(|command| which::which(command).is_err())
+            .cloned()
+            .collect();
+        let mut check = RequirementCheck {
+            name: self.name.clone(),
+            status: RequirementStatus::Satisfied,
+            missing_commands: missing,
+            version: None,
+            min_version: self.min_version.as_ref().map(|v| v.to_string()),
+        };
+        if !check.missing_commands.is_empty() {
+            check.status = RequirementStatus::Missing;
+            return check;
+        }
+        let Some(ref minimum) = self.min_version else {
+            return check;
+        };
+
+        let output = StdCommand::new(&self.version_command[0])
+            .args(&self.version_command[1..])
+            .output();
+        let version = output.ok().and_then(|output| {
+            let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            parse_loose_version(&text)
+        });
+        match version {
+            Some(version) => {
+                if version < *minimum {
+                    check.status = RequirementStatus::Outdated;
+                }
+                check.version = Some(version.to_string());
+            }
+            None => check.status = RequirementStatus::Unknown,
+        }
+        check
+    }
+}
+
+/// First version number in a command's output (`git version 2.39.5` gives 2.39.5)
+///
+/// Missing minor and patch numbers are taken as zero (`systemd 252` gives 252.0.0).
+pub fn parse_loose_version(text: &str) -> Option<Version> {
+    static VERSION: OnceLock<Regex> = OnceLock::new();
+    let re = VERSION.get_or_init(|| Regex::new(r"\d+(\.\d+){0,2}").expect("valid regex"));
+    let found = re.find(text)?.as_str();
+    let mut parts: Vec<&str> = found.split('.').collect();
+    parts.resize(3, "0");
+    Version::parse(&parts.join(".")).ok()
+}
+
+#[derive(Deserialize)]
+struct RawManifest {
+    #[serde(default)]
+    capabilities: BTreeMap<String, RawRequirement>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawRequirement {
+    #[serde(default)]
+    description: String,
+    commands: Vec<String>,
+    #[serde(default)]
+    apt: Vec<String>,
+    #[serde(default)]
+    dnf: Vec<String>,
+    #[serde(default)]
+    pacman: Vec<String>,
+    min_version: Option<String>,
+    version_command: Option<String>,
+    #[serde(default)]
+    scripts: Vec<String>,
+    #[serde(default)]
+    core: bool,
+}
+
+/// Parsed system dependency manifest
+#[derive(Debug, Clone, Default)]
+pub struct SystemRequirements {
+    pub requirements: Vec<Requirement>,
+}
+
+impl SystemRequirements {
+    pub fn parse(content: &str) -> Result<Self> {
+        let raw: RawManifest =
+            toml::from_str(content).context("Failed to parse system dependency manifest")?;
+        let mut requirements = Vec::new();
+        for (name, raw) in raw.capabilities {
+            if raw.commands.is_empty() {
+                return Err(anyhow!("Capability {} lists no commands", name));
+            }
+            let min_version = raw
+                .min_version
+                .map(|v| {
+                    parse_loose_version(&v)
+                        .ok_or_else(|| anyhow!("Invalid min_version for {}: {}", name, v))
+                })
+                .transpose()?;
+            let version_command: Vec<String> = match raw.version_command {
+                Some(command) => command.split_whitespace().map(String::from).collect(),
+                None => vec![raw.commands[0].clone(), "--version".to_string()],
+            };
+            if version_command.is_empty() {
+                return Err(anyhow!("Capability {} has an empty version_command", name));
+            }
+            let packages = [
+                (PackageManager::Apt, raw.apt),
+                (PackageManager::Yum, raw.dnf.clone()),
+                (PackageManager::Dnf, raw.dnf),
+                (PackageManager::Pacman, raw.pacman),
+            ]
+            .into_iter()
+            .filter(|(_, packages)| !packages.is_empty())
+            .collect();
+            requirements.push(Requirement {
+                name,
+                description: raw.description,
+                commands: raw.commands,
+                packages,
+                min_version,
+                version_command,
+                scripts: raw.scripts,
+                core: raw.core,
+            });
+        }
+        Ok(Self { requirements })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid manifest: {}", path.display()))
+    }
+
+    /// The manifest embedded in the binaries
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN_SYSTEM_DEPS).expect("built-in system-deps.toml is valid")
+    }
+
+    /// `UCS_SYSTEM_DEPS` if set, otherwise the built-in manifest
+    pub fn load() -> Result<Self> {
+        match std::env::var_os(SYSTEM_DEPS_ENV) {
+            Some(path) => Self::from_file(PathBuf::from(path)),
+            None => Ok(Self::builtin()),
+        }
+    }
+
+    /// Only the requirements of one script, including those of every script
+    pub fn for_script(&self, script: &str) -> Self {
+        Self {
+            requirements: self
+                .requirements
+                .iter()
+                .filter(|r| r.needed_by(script))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Only the requirements every script shares
+    pub fn common(&self) -> Self {
+        self.for_script(ALL_SCRIPTS)
+    }
+
+    /// Only the core requirements (`core = true`), for the full dependency check
+    pub fn core(&self) -> Self {
+        Self {
+            requirements: self
+                .requirements
+                .iter()
+                .filter(|r| r.core)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Requirement> {
+        self.requirements.iter().find(|r| r.name == name)
+    }
+
+    /// Requirement providing a command
+    pub fn providing(&self, command: &str) -> Option<&Requirement> {
+        self.requirements
+            .iter()
+            .find(|r| r.commands.iter().any(|c| c == command))
+    }
+
+    /// Every command, in manifest order
+    pub fn commands(&self) -> Vec<&str> {
+        let mut commands = Vec::new();
+        for command in self.requirements.iter().flat_map(|r| &r.commands) {
+            if !commands.contains(&command.as_str()) {
+                commands.push(command.as_str());
+            }
+        }
+        commands
+    }
+
+    /// Check every requirement on this host
+    pub fn check(&self) -> RequirementReport {
+        RequirementReport {
+            checks: self.requirements.iter().map(|r| r.check()).collect(),
+        }
+    }
+
+    /// Packages to install for the unmet requirements in a report
+    pub fn install_plan(&self, report: &RequirementReport, manager: PackageManager) -> InstallPlan {
+        let mut plan = InstallPlan {
+            manager,
+            packages: Vec::new(),
+            unavailable: Vec::new(),
+        };
+        for check in report.unmet() {
+            let Some(requirement) = self.get(&check.name) else {
+                continue;
+            };
+            let packages = requirement.packages_for(manager);
+            if packages.is_empty() {
+                plan.unavailable.push(requirement.name.clone());
+            }
+            for package in packages {
+                if !plan.packages.contains(package) {
+                    plan.packages.push(package.clone());
+                }
+            }
+        }
+        plan
+    }
+}
+
+/// Result of checking one requirement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RequirementStatus {
+    Satisfied,
+    Missing,
+    /// Installed, but older than `min_version`
+    Outdated,
+    /// Installed, but the version could not be read
+    Unknown,
+}
+
+impl fmt::Display for RequirementStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RequirementStatus::Satisfied => "ok",
+            RequirementStatus::Missing => "MISSING",
+            RequirementStatus::Outdated => "OUTDATED",
+            RequirementStatus::Unknown => "unknown",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Check result for one requirement
+#[derive(Debug, Clone, Serialize)]
+pub struct RequirementCheck {
+    pub name: String,
+    pub status: RequirementStatus,
+    pub missing_commands: Vec<String>,
+    pub version: Option<String>,
+    pub min_version: Option<String>,
+}
+
+/// Check results for a set of requirements
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RequirementReport {
+    pub checks: Vec<RequirementCheck>,
+}
+
+impl RequirementReport {
+    /// Missing or outdated requirements (an unreadable version is not unmet)
+    pub fn unmet(&self) -> Vec<&RequirementCheck> {
+        self.checks
+            .iter()
+            .filter(|c| {
+                matches!(
+                    c.status,
+                    RequirementStatus::Missing | RequirementStatus::Outdated
+                )
+            })
+            .collect()
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.unmet().is_empty()
+    }
+
+    /// Missing commands across every check
+    pub fn missing_commands(&self) -> Vec<String> {
+        self.checks
+            .iter()
+            .flat_map(|c| c.missing_commands.iter().cloned())
+            .collect()
+    }
+
+    pub fn table(&self) -> Table {
+        let mut table = Table::new(vec![
+            Column::new("Capability"),
+            Column::new("Status"),
+            Column::new("Version"),
+            Column::new("Minimum"),
+            Column::new("Missing commands").wrap(),
+        ]);
+        for check in &self.checks {
+            table.add_row(vec![
+                check.name.clone(),
+                check.status.to_string(),
+                check.version.clone().unwrap_or_default(),
+                check.min_version.clone().unwrap_or_default(),
+                check.missing_commands.join(", "),
+            ]);
+        }
+        table
+    }
+}
+
+/// Packages that would satisfy the unmet requirements
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InstallPlan {
+    pub manager: PackageManager,
+    pub packages: Vec<String>,
+    /// Unmet requirements this package manager has no package for
+    pub unavailable: Vec<String>,
+}
+
+impl InstallPlan {
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty() && self.unavailable.is_empty()
+    }
+
+    /// The install command, if there is anything to install
+    pub fn command(&self) -> Option<Vec<String>> {
+        if self.packages.is_empty() {
+            None
+        } else {
+            Some(self.manager.install_command(&self.packages))
+        }
+    }
+}
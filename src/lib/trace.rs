@@ -0,0 +1,296 @@
+// Hierarchical spans and trace export for Ubuntu Config Scripts
+//
+// This module links nested logging contexts into a span tree including:
+// - Span IDs with parent links, tracked per async task (or per thread outside
+//   a task scope)
+// - Span attachment for every log record emitted inside a context
+// - Per-run recording of spans and log events
+// - Export in Chrome trace-event JSON (chrome://tracing, Perfetto)
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+/// Environment variable naming the file a run's trace is written to
+pub const TRACE_ENV: &str = "UCS_TRACE";
+
+static NEXT_SPAN_ID: AtomicU64 = AtomicU64::new(1);
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+static RECORDING: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static SPAN_STACK: RefCell<Vec<SpanInfo>> = const { RefCell::new(Vec::new()) };
+    static THREAD_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+tokio::task_local! {
+    // A task may resume on another worker thread after every `.await`, so
+    // spans entered inside `task_scope` travel with the task instead
+    static TASK_SPANS: RefCell<Vec<SpanInfo>>;
+}
+
+/// Identity of an active span
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanInfo {
+    pub id: u64,
+    pub parent: Option<u64>,
+    pub name: String,
+}
+
+/// A single event in Chrome trace-event format
+#[derive(Debug, Clone, Serialize)]
+pub struct TraceEvent {
+    pub name: String,
+    pub cat: String,
+    pub ph: String,
+    /// Microseconds since the start of the run
+    pub ts: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dur: Option<u64>,
+    pub pid: u32,
+    pub tid: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub s: Option<String>,
+    pub args: serde_json::Map<String, serde_json::Value>,
+}
+
+fn epoch() -> Instant {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    *EPOCH.get_or_init(Instant::now)
+}
+
+fn micros_since_epoch(at: Instant) -> u64 {
+    at.saturating_duration_since(epoch()).as_micros() as u64
+}
+
+fn events() -> &'static Mutex<Vec<TraceEvent>> {
+    static EVENTS: OnceLock<Mutex<Vec<TraceEvent>>> = OnceLock::new();
+    EVENTS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+fn push_event(event: TraceEvent) {
+    if let Ok(mut events) = events().lock() {
+        events.push(event);
+    }
+}
+
+/// Small stable number for the current thread, used as the trace `tid`
+fn thread_id() -> u64 {
+    THREAD_ID.with(|id| {
+        if id.get() == 0 {
+            id.set(NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed));
+        }
+        id.get()
+    })
+}
+
+fn in_task_scope() -> bool {
+    TASK_SPANS.try_with(|_| ()).is_ok()
+}
+
+/// Run `f` on the task's span stack, or on this thread's when `task` is false
+///
+/// Returns `None` when the task stack is gone, i.e. a span entered inside a
+/// task scope is dropped outside it.
+fn with_stack<R>(task: bool, f: impl FnOnce(&mut Vec<SpanInfo>) -> R) -> Option<R> {
+    if task {
+        TASK_SPANS.try_with(|stack| f(&mut stack.borrow_mut())).ok()
+    } else {
+        Some(SPAN_STACK.with(|stack| f(&mut stack.borrow_mut())))
+    }
+}
+
+/// Innermost active span of the current task or thread
+pub fn current_span() -> Option<SpanInfo> {
+    with_stack(in_task_scope(), |stack| stack.last().cloned()).flatten()
+}
+
+/// Names of the active spans of the current task or thread, outermost first
+pub fn span_path() -> Vec<String> {
+    with_stack(in_task_scope(), |stack| {
+        stack.iter().map(|s| s.name.clone()).collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Run `future` with its own span stack, starting from the spans active here
+///
+/// Spans entered by the future stay with it when the runtime moves it to
+/// another worker thread, and leave nothing behind on the threads it ran on.
+/// Wrap async code that keeps a span open across an `.await`.
+pub fn task_scope<F: Future>(future: F) -> impl Future<Output = F::Output> {
+    let spans = with_stack(in_task_scope(), |stack| stack.clone()).unwrap_or_default();
+    TASK_SPANS.scope(RefCell::new(spans), future)
+}
+
+/// Guard for an entered span; the span closes when it is dropped
+#[derive(Debug)]
+pub struct Span {
+    info: SpanInfo,
+    start: Instant,
+    tid: u64,
+    /// Whether the span was pushed on a task's stack rather than the thread's
+    task: bool,
+}
+
+impl Span {
+    /// Open a span as a child of the current span of this task or thread
+    pub fn enter(name: &str) -> Self {
+        let info = SpanInfo {
+            id: NEXT_SPAN_ID.fetch_add(1, Ordering::Relaxed),
+            parent: current_span().map(|s| s.id),
+            name: name.to_string(),
+        };
+        let task = in_task_scope();
+        with_stack(task, |stack| stack.push(info.clone()));
+        epoch();
+
+        Self {
+            info,
+            start: Instant::now(),
+            tid: thread_id(),
+            task,
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.info.id
+    }
+
+    pub fn parent(&self) -> Option<u64> {
+        self.info.parent
+    }
+
+    pub fn info(&self) -> &SpanInfo {
+        &self.info
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let id = self.info.id;
+        // Spans may close out of order; remove exactly this one
+        with_stack(self.task, |stack| stack.retain(|s| s.id != id));
+
+        if is_recording() {
+            push_event(TraceEvent {
+                name: self.info.name.clone(),
+                cat: "span".to_string(),
+                ph: "X".to_string(),
+                ts: micros_since_epoch(self.start),
+                dur: Some(self.start.elapsed().as_micros() as u64),
+                pid: std::process::id(),
+                tid: self.tid,
+                s: None,
+                args: span_args(&self.info),
+            });
+        }
+    }
+}
+
+fn span_args(info: &SpanInfo) -> serde_json::Map<String, serde_json::Value> {
+    let mut args = serde_json::Map::new();
+    args.insert("span_id".to_string(), info.id.into());
+    if let Some(parent) = info.parent {
+        args.insert("parent_id".to_string(), parent.into());
+    }
+    args
+}
+
+/// Record a log message as an instant event inside the current span
+pub fn record_log(level: &str, target: &str, message: &str) {
+    if !is_recording() {
+        return;
+    }
+    let mut args = current_span().map(|s| span_args(&s)).unwrap_or_default();
+    args.insert("level".to_string(), level.into());
+    args.insert("target".to_string(), target.into());
+
+    push_event(TraceEvent {
+        name: message.to_string(),
+        cat: "log".to_string(),
+        ph: "i".to_string(),
+        ts: micros_since_epoch(Instant::now()),
+        dur: None,
+        pid: std::process::id(),
+        tid: thread_id(),
+        s: Some("t".to_string()),
+        args,
+    });
+}
+
+/// Start recording spans and log events for this run
+pub fn start_recording() {
+    epoch();
+    if let Ok(mut events) = events().lock() {
+        events.clear();
+    }
+    RECORDING.store(true, Ordering::Relaxed);
+}
+
+/// Stop recording and return everything recorded so far
+pub fn stop_recording() -> Vec<TraceEvent> {
+    RECORDING.store(false, Ordering::Relaxed);
+    events()
+        .lock()
+        .map(|mut events| std::mem::take(&mut *events))
+        .unwrap_or_default()
+}
+
+pub fn is_recording() -> bool {
+    RECORDING.load(Ordering::Relaxed)
+}
+
+/// Render events as a Chrome trace-event JSON document
+pub fn to_chrome_trace(events: &[TraceEvent]) -> Result<String> {
+    let document = serde_json::json!({
+        "traceEvents": events,
+        "displayTimeUnit": "ms",
+    });
+    serde_json::to_string_pretty(&document).context("Failed to serialize trace")
+}
+
+/// Write events to `path` as Chrome trace-event JSON
+pub fn write_chrome_trace(path: &Path, events: &[TraceEvent]) -> Result<()> {
+    let json = to_chrome_trace(events)?;
+    std::fs::write(path, json)
+        .with_context(|| format!("Failed to write trace file: {}", path.display()))
+}
+
+/// Records a run and writes the trace file when dropped
+pub struct TraceGuard {
+    path: PathBuf,
+}
+
+impl TraceGuard {
+    /// Record until the guard is dropped, then write the trace to `path`
+    pub fn new(path: &str) -> Self {
+        start_recording();
+        Self {
+            path: PathBuf::from(path),
+        }
+    }
+
+    /// Record only when `UCS_TRACE` names an output file
+    pub fn from_env() -> Option<Self> {
+        std::env::var(TRACE_ENV)
+            .ok()
+            .filter(|path| !path.is_empty())
+            .map(|path| Self::new(&path))
+    }
+}
+
+impl Drop for TraceGuard {
+    fn drop(&mut self) {
+        let events = stop_recording();
+        match write_chrome_trace(&self.path, &events) {
+            Ok(()) => log::info!("🧭 Trace written to {}", self.path.display()),
+            Err(e) => log::warn!("{:#}", e),
+        }
+    }
+}
@@ -8,6 +8,20 @@ use ubuntu_config_scripts::*;
 async fn main() -> anyhow::Result<()> {
     init_logger()?;
     log_script_start("cleanup_disk");
+    check_script_requirements("cleanup_disk");
+
+    let config = load_config("cleanup")?;
+    let cleanup = &config.cleanup;
+    log_info(
+        &format!(
+            "Cleaning {} (files older than {} days, at least {} MB, trash: {})",
+            cleanup.paths.join(", "),
+            cleanup.older_than_days,
+            cleanup.min_size_mb,
+            cleanup.empty_trash
+        ),
+        "CLEANUP",
+    );
 
     println!("Disk cleanup utility - Placeholder");
 
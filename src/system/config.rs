@@ -0,0 +1,406 @@
+// config utility for Ubuntu systems
+//
+// Inspects the layered configuration (defaults, system file, user file,
+// profiles, UCS_ environment variables and command line overrides), edits
+// single values in the system or user file and manages named profiles.
+
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+use ubuntu_config_scripts::*;
+
+#[derive(Parser)]
+#[command(name = "config", about = "Inspect Ubuntu Config Scripts configuration")]
+struct Cli {
+    /// Additional configuration file layered above the user file
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    /// Override a value for this run (e.g. --set audio.volume_level=60)
+    #[arg(long = "set", value_name = "KEY=VALUE", global = true)]
+    overrides: Vec<String>,
+
+    /// Apply this profile instead of the recorded one (see `config profile list`)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Output format: table, markdown, csv or json
+    #[arg(long, default_value = "table", global = true)]
+    format: OutputFormat,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// Layer written by `set`, `unset`, `edit` and `profile activate` (the user file unless --system)
+#[derive(Args)]
+struct Scope {
+    /// Write the system-wide file (/etc/ubuntu-config-scripts)
+    #[arg(long, conflicts_with = "user")]
+    system: bool,
+    /// Write the per-user file (default)
+    #[arg(long)]
+    user: bool,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Show every effective value and the layer that set it
+    Explain,
+    /// Print the merged configuration as JSON
+    Show,
+    /// Print the effective value of a key (e.g. audio.volume_level)
+    Get { key: String },
+    /// Set a key in the user or system file, keeping comments and ordering
+    Set {
+        key: String,
+        value: String,
+        #[command(flatten)]
+        scope: Scope,
+    },
+    /// Remove a key from the user or system file so the default applies
+    Unset {
+        key: String,
+        #[command(flatten)]
+        scope: Scope,
+    },
+    /// Open the user or system file in $EDITOR and validate it before saving
+    Edit {
+        #[command(flatten)]
+        scope: Scope,
+    },
+    /// Check the merged configuration and report every invalid value
+    Validate,
+    /// List, inspect, activate and compare named profiles
+    Profile {
+        #[command(subcommand)]
+        action: ProfileCommand,
+    },
+    /// Upgrade a configuration file to the current layout, keeping a backup
+    Migrate {
+        /// File to upgrade (defaults to --config, then the user config file)
+        file: Option<PathBuf>,
+        /// Report the changes without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Print the JSON Schema for configuration files (for editor validation)
+    Schema {
+        /// Write the schema to a file instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileCommand {
+    /// List the defined profiles and mark the active one
+    List,
+    /// Show the values a profile changes
+    Show { name: String },
+    /// Record a profile as active so every script resolves config through it
+    Activate {
+        name: String,
+        #[command(flatten)]
+        scope: Scope,
+    },
+    /// Compare effective values without a profile and with FROM, or FROM and TO
+    Diff { from: String, to: Option<String> },
+}
+
+fn loader(cli: &Cli) -> anyhow::Result<ConfigLoader> {
+    let mut loader = ConfigLoader::new().file(cli.config.clone());
+    if let Some(ref name) = cli.profile {
+        loader = loader.profile(Some(name));
+    }
+    for assignment in &cli.overrides {
+        loader = loader.set_assignment(assignment)?;
+    }
+    Ok(loader)
+}
+
+/// File written for a scope: --config when given, else the layer's config file
+fn target_file(cli: &Cli, scope: &Scope) -> anyhow::Result<PathBuf> {
+    if let Some(ref path) = cli.config {
+        return Ok(path.clone());
+    }
+    let dir = if scope.system {
+        PathBuf::from(SYSTEM_CONFIG_DIR)
+    } else {
+        user_config_dir()
+            .ok_or_else(|| anyhow::anyhow!("Cannot determine the user configuration directory"))?
+    };
+    Ok(layer_file(&dir))
+}
+
+/// Apply `set`, `unset` or `edit` to a single file
+fn edit(cli: &Cli) -> anyhow::Result<()> {
+    match cli.command {
+        Command::Set {
+            ref key,
+            ref value,
+            ref scope,
+        } => {
+            let path = target_file(cli, scope)?;
+            let mut document = ConfigDocument::open(&path)?;
+            let value = document.assign(key, value)?;
+            document.save()?;
+            log_success(
+                &format!("Set {} = {} in {}", key, value, path.display()),
+                "CONFIG",
+            );
+        }
+        Command::Unset { ref key, ref scope } => {
+            split_path(key)?;
+            let path = target_file(cli, scope)?;
+            let mut document = ConfigDocument::open(&path)?;
+            if !path.exists() || !document.unset(key)? {
+                log_info(
+                    &format!("{} is not set in {}", key, path.display()),
+                    "CONFIG",
+                );
+                return Ok(());
+            }
+            document.save()?;
+            log_success(
+                &format!("Removed {} from {}", key, path.display()),
+                "CONFIG",
+            );
+        }
+        Command::Edit { ref scope } => {
+            let path = target_file(cli, scope)?;
+            let changed = edit_file(&path, &editor_command(), |problems| {
+                eprintln!("{}", problems);
+                confirm("Edit again?", true)
+            })?;
+            if changed {
+                log_success(&format!("Saved {}", path.display()), "CONFIG");
+            } else {
+                log_info("No changes", "CONFIG");
+            }
+        }
+        _ => unreachable!("only file edits are handled here"),
+    }
+    Ok(())
+}
+
+/// Handle `config profile` subcommands
+fn profile(cli: &Cli, action: &ProfileCommand) -> anyhow::Result<()> {
+    let loader = || loader(cli).map(|loader| loader.strict_profile(true));
+    let loaded = loader()?.load()?;
+    let find = |name: &str| {
+        loaded.config.profiles.get(name).ok_or_else(|| {
+            let names: Vec<&str> = loaded.config.profiles.keys().map(String::as_str).collect();
+            let hint = suggest(name, &names)
+                .map(|s| format!(" (did you mean {}?)", s))
+                .unwrap_or_default();
+            anyhow::anyhow!("Unknown profile: {}{}", name, hint)
+        })
+    };
+
+    match action {
+        ProfileCommand::List => {
+            print!(
+                "{}",
+                profiles_table(&loaded.config)
+                    .fit_terminal()
+                    .render(cli.format)
+            );
+        }
+        ProfileCommand::Show { name } => {
+            let profile = find(name)?;
+            if cli.format == OutputFormat::Json {
+                println!("{}", serde_json::to_string_pretty(profile)?);
+                return Ok(());
+            }
+            if let Some(ref description) = profile.description {
+                println!("{}: {}\n", name, description);
+            }
+            let overlay = profile.overlay_value();
+            let mut table = Table::new(vec![Column::new("Key"), Column::new("Value")]);
+            for (key, value) in leaf_paths(&overlay) {
+                table.add_row(vec![key, value.to_string()]);
+            }
+            print!("{}", table.fit_terminal().render(cli.format));
+        }
+        ProfileCommand::Activate { name, scope } => {
+            find(name)?;
+            let path = target_file(cli, scope)?;
+            let mut document = ConfigDocument::open(&path)?;
+            document.assign(PROFILE_KEY, name)?;
+            document.save()?;
+            log_success(
+                &format!("Activated profile {} in {}", name, path.display()),
+                "CONFIG",
+            );
+        }
+        ProfileCommand::Diff { from, to } => {
+            let (before, after) = match to {
+                Some(to) => {
+                    find(from)?;
+                    find(to)?;
+                    (
+                        loader()?.profile(Some(from)).load()?,
+                        loader()?.profile(Some(to)).load()?,
+                    )
+                }
+                None => {
+                    find(from)?;
+                    (
+                        loader()?.profile(None).load()?,
+                        loader()?.profile(Some(from)).load()?,
+                    )
+                }
+            };
+            let labels = match to {
+                Some(to) => (from.as_str(), to.as_str()),
+                None => ("(no profile)", from.as_str()),
+            };
+            let changes = diff_values(&before.value, &after.value);
+            if cli.format == OutputFormat::Json {
+                println!("{}", serde_json::to_string_pretty(&changes)?);
+            } else if changes.is_empty() {
+                log_info("No differences", "CONFIG");
+            } else {
+                print!(
+                    "{}",
+                    diff_table(&changes, labels.0, labels.1)
+                        .fit_terminal()
+                        .render(cli.format)
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn migrate(file: Option<&PathBuf>, dry_run: bool, format: OutputFormat) -> anyhow::Result<()> {
+    let path = match file {
+        Some(path) => path.clone(),
+        None => user_config_dir()
+            .as_deref()
+            .and_then(find_config_file)
+            .ok_or_else(|| anyhow::anyhow!("No user configuration file found"))?,
+    };
+
+    let report = migrate_file(&path, &Migrations::builtin(), dry_run)?;
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    if !report.is_changed() {
+        log_info(
+            &format!(
+                "{} is already at version {}",
+                path.display(),
+                report.to_version
+            ),
+            "CONFIG",
+        );
+        return Ok(());
+    }
+
+    for change in &report.changes {
+        println!("  {}", change);
+    }
+    match report.backup {
+        Some(ref backup) => log_success(
+            &format!(
+                "Migrated {} to version {} (backup: {})",
+                path.display(),
+                report.to_version,
+                backup.display()
+            ),
+            "CONFIG",
+        ),
+        None => log_info("Dry run: no files were changed", "CONFIG"),
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    init_logger()?;
+    log_script_start("config");
+
+    let cli = Cli::parse();
+    check_script_requirements("config");
+
+    if let Command::Schema { ref output } = cli.command {
+        let schema = serde_json::to_string_pretty(&Config::json_schema())?;
+        match output {
+            Some(path) => {
+                std::fs::write(path, schema + "\n")?;
+                log_success(&format!("Schema written to {}", path.display()), "CONFIG");
+            }
+            None => println!("{}", schema),
+        }
+        log_script_complete("config");
+        return Ok(());
+    }
+
+    if let Command::Migrate { ref file, dry_run } = cli.command {
+        migrate(file.as_ref().or(cli.config.as_ref()), dry_run, cli.format)?;
+        log_script_complete("config");
+        return Ok(());
+    }
+
+    if let Command::Profile { ref action } = cli.command {
+        profile(&cli, action)?;
+        log_script_complete("config");
+        return Ok(());
+    }
+
+    if matches!(
+        cli.command,
+        Command::Set { .. } | Command::Unset { .. } | Command::Edit { .. }
+    ) {
+        edit(&cli)?;
+        log_script_complete("config");
+        return Ok(());
+    }
+
+    let loaded = loader(&cli)?.load()?;
+
+    match cli.command {
+        Command::Explain => {
+            print!(
+                "{}",
+                loaded.explain_table().fit_terminal().render(cli.format)
+            );
+        }
+        Command::Show => {
+            println!("{}", serde_json::to_string_pretty(&loaded.value)?);
+        }
+        Command::Get { ref key } => {
+            let value = get_path(&loaded.value, key)
+                .ok_or_else(|| anyhow::anyhow!("Unknown configuration key: {}", key))?;
+            match (value, cli.format) {
+                (serde_json::Value::String(text), format) if format != OutputFormat::Json => {
+                    println!("{}", text)
+                }
+                (value, _) => println!("{}", serde_json::to_string_pretty(value)?),
+            }
+        }
+        Command::Validate => {
+            let errors = loaded.config.validation_errors();
+            match cli.format {
+                OutputFormat::Json => println!("{}", errors.to_json()?),
+                _ if errors.is_empty() => log_success("Configuration is valid", "CONFIG"),
+                _ => eprintln!("{}", errors),
+            }
+            if !errors.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Command::Schema { .. }
+        | Command::Migrate { .. }
+        | Command::Profile { .. }
+        | Command::Set { .. }
+        | Command::Unset { .. }
+        | Command::Edit { .. } => unreachable!("handled before loading"),
+    }
+
+    log_script_complete("config");
+    Ok(())
+}
@@ -6,6 +6,17 @@ use ubuntu_config_scripts::*;
 async fn main() -> anyhow::Result<()> {
     init_logger()?;
     log_script_start("configure_obs");
+    check_script_requirements("configure_obs");
+
+    let config = load_config("obs")?;
+    let obs = &config.obs;
+    log_info(
+        &format!(
+            "OBS output {} at {} fps using {}",
+            obs.resolution, obs.fps, obs.encoder
+        ),
+        "OBS",
+    );
 
     println!("configure_obs utility - Placeholder");
 
@@ -0,0 +1,31 @@
+// configure_swap utility for Ubuntu systems
+
+use ubuntu_config_scripts::*;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    init_logger()?;
+    log_script_start("configure_swap");
+    check_script_requirements("configure_swap");
+
+    let config = load_config("swap")?;
+    let swap = &config.swap;
+    log_info(
+        &format!(
+            "Swap file {} ({}), swappiness {}",
+            swap.file,
+            swap.size_mb
+                .map(|mb| format!("{} MiB", mb))
+                .unwrap_or_else(|| "current size".to_string()),
+            swap.swappiness
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| "unchanged".to_string())
+        ),
+        "SWAP",
+    );
+
+    println!("configure_swap utility - Placeholder");
+
+    log_script_complete("configure_swap");
+    Ok(())
+}
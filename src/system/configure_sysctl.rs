@@ -0,0 +1,24 @@
+// configure_sysctl utility for Ubuntu systems
+
+use ubuntu_config_scripts::*;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    init_logger()?;
+    log_script_start("configure_sysctl");
+    check_script_requirements("configure_sysctl");
+
+    let config = load_config("sysctl")?;
+    let settings = config.sysctl.settings();
+    if settings.is_empty() {
+        log_info("No kernel parameters configured", "SYSCTL");
+    }
+    for (name, value) in &settings {
+        log_info(&format!("{} = {}", name, value), "SYSCTL");
+    }
+
+    println!("configure_sysctl utility - Placeholder");
+
+    log_script_complete("configure_sysctl");
+    Ok(())
+}
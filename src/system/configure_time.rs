@@ -6,6 +6,23 @@ use ubuntu_config_scripts::*;
 async fn main() -> anyhow::Result<()> {
     init_logger()?;
     log_script_start("configure_time");
+    check_script_requirements("configure_time");
+
+    let config = load_config("time")?;
+    let time = &config.time;
+    log_info(
+        &format!(
+            "Time zone {}, NTP {} ({})",
+            time.timezone,
+            if time.ntp_enabled {
+                "enabled"
+            } else {
+                "disabled"
+            },
+            time.ntp_servers.join(", ")
+        ),
+        "TIME",
+    );
 
     println!("configure_time utility - Placeholder");
 
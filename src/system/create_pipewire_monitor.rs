@@ -6,6 +6,17 @@ use ubuntu_config_scripts::*;
 async fn main() -> anyhow::Result<()> {
     init_logger()?;
     log_script_start("create_pipewire_monitor");
+    check_script_requirements("create_pipewire_monitor");
+
+    let config = load_config("pipewire_monitor")?;
+    let monitor = &config.pipewire_monitor;
+    log_info(
+        &format!(
+            "Checking every {}s, restarting after {}s (at most {} restarts)",
+            monitor.check_interval_secs, monitor.restart_delay_secs, monitor.max_restarts
+        ),
+        "PIPEWIRE",
+    );
 
     println!("create_pipewire_monitor utility - Placeholder");
 
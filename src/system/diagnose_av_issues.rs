@@ -5,7 +5,9 @@ use ubuntu_config_scripts::*;
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     init_logger()?;
+    let _trace = TraceGuard::from_env();
     log_script_start("diagnose_av_issues");
+    check_script_requirements("diagnose_av_issues");
 
     println!("diagnose_av_issues utility - Placeholder");
 
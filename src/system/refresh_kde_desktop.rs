@@ -6,6 +6,17 @@ use ubuntu_config_scripts::*;
 async fn main() -> anyhow::Result<()> {
     init_logger()?;
     log_script_start("refresh_kde_desktop");
+    check_script_requirements("refresh_kde_desktop");
+
+    let config = load_config("kde")?;
+    let kde = &config.kde;
+    log_info(
+        &format!(
+            "Restart plasmashell: {}, clear caches: {}, rebuild sycoca: {}",
+            kde.restart_plasmashell, kde.clear_cache, kde.rebuild_sycoca
+        ),
+        "KDE",
+    );
 
     println!("refresh_kde_desktop utility - Placeholder");
 
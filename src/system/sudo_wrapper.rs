@@ -6,6 +6,7 @@ use ubuntu_config_scripts::*;
 async fn main() -> anyhow::Result<()> {
     init_logger()?;
     log_script_start("sudo_wrapper");
+    check_script_requirements("sudo_wrapper");
 
     println!("sudo_wrapper utility - Placeholder");
 
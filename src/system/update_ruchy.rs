@@ -6,6 +6,7 @@ use ubuntu_config_scripts::*;
 async fn main() -> anyhow::Result<()> {
     init_logger()?;
     log_script_start("update_ruchy");
+    check_script_requirements("update_ruchy");
 
     println!("update_ruchy utility - Placeholder");
 
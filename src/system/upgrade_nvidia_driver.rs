@@ -6,6 +6,22 @@ use ubuntu_config_scripts::*;
 async fn main() -> anyhow::Result<()> {
     init_logger()?;
     log_script_start("upgrade_nvidia_driver");
+    check_script_requirements("upgrade_nvidia_driver");
+
+    let config = load_config("nvidia")?;
+    let nvidia = &config.nvidia;
+    log_info(
+        &format!(
+            "Target driver: {}{}",
+            nvidia.target_version.as_deref().unwrap_or("recommended"),
+            if nvidia.open_kernel_modules {
+                " (open kernel modules)"
+            } else {
+                ""
+            }
+        ),
+        "NVIDIA",
+    );
 
     println!("upgrade_nvidia_driver utility - Placeholder");
 
@@ -68,6 +68,9 @@ mod tests {
         let system_binaries = [
             "cleanup_disk",
             "configure_obs",
+            "configure_runner",
+            "configure_swap",
+            "configure_sysctl",
             "configure_time",
             "create_pipewire_monitor",
             "diagnose_av_issues",
@@ -124,6 +127,9 @@ mod tests {
             // System binaries
             "cleanup_disk",
             "configure_obs",
+            "configure_runner",
+            "configure_swap",
+            "configure_sysctl",
             "configure_time",
             "create_pipewire_monitor",
             "diagnose_av_issues",
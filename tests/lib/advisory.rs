@@ -0,0 +1,235 @@
+// Tests for advisory module
+//
+// This module tests CVSS scoring, RustSec advisory parsing, version range
+// matching and auditing a lockfile against a local advisory database
+
+use std::path::Path;
+use tempfile::TempDir;
+use ubuntu_config_scripts::*;
+
+const CARGO_LOCK: &str = r#"version = 4
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = [
+ "chrono",
+ "time 0.3.5",
+]
+
+[[package]]
+name = "chrono"
+version = "0.4.19"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = [
+ "time 0.1.43",
+]
+
+[[package]]
+name = "time"
+version = "0.1.43"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "time"
+version = "0.3.5"
+source = "git+https://github.com/time-rs/time#abc123"
+"#;
+
+const TIME_ADVISORY: &str = r#"```toml
+[advisory]
+id = "RUSTSEC-2020-0071"
+package = "time"
+date = "2020-11-18"
+url = "https://github.com/time-rs/time/issues/293"
+categories = ["code-execution", "memory-corruption"]
+aliases = ["CVE-2020-26235", "GHSA-wcg3-cvx6-7396"]
+cvss = "CVSS:3.1/AV:N/AC:H/PR:N/UI:N/S:U/C:N/I:N/A:H"
+
+[affected.functions]
+"time::at_utc" = ["< 0.2.23"]
+
+[versions]
+patched = [">= 0.2.23"]
+unaffected = ["= 0.2.0", "= 0.2.1", "= 0.2.2"]
+```
+
+# Potential segfault in the time crate
+
+Unix-like operating systems may segfault due to dereferencing a dangling
+pointer in specific circumstances.
+"#;
+
+const CHRONO_ADVISORY: &str = r#"```toml
+[advisory]
+id = "RUSTSEC-2020-0159"
+package = "chrono"
+date = "2020-11-10"
+informational = "unsound"
+
+[versions]
+patched = [">= 0.4.20"]
+```
+
+# Potential segfault in `localtime_r` invocations
+"#;
+
+const WITHDRAWN_ADVISORY: &str = r#"```toml
+[advisory]
+id = "RUSTSEC-2021-0001"
+package = "time"
+date = "2021-01-01"
+withdrawn = "2021-02-01"
+
+[versions]
+patched = []
+```
+
+# Withdrawn
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_db(dir: &Path) {
+        for (krate, id, content) in [
+            ("time", "RUSTSEC-2020-0071", TIME_ADVISORY),
+            ("chrono", "RUSTSEC-2020-0159", CHRONO_ADVISORY),
+            ("time", "RUSTSEC-2021-0001", WITHDRAWN_ADVISORY),
+        ] {
+            let crate_dir = dir.join("crates").join(krate);
+            std::fs::create_dir_all(&crate_dir).unwrap();
+            std::fs::write(crate_dir.join(format!("{}.md", id)), content).unwrap();
+        }
+        std::fs::write(dir.join("README.md"), "# RustSec Advisory Database\n").unwrap();
+    }
+
+    #[test]
+    fn test_cvss_base_score() {
+        let score = |v: &str| cvss_base_score(v).unwrap();
+        assert_eq!(score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"), 9.8);
+        assert_eq!(score("CVSS:3.1/AV:N/AC:H/PR:N/UI:N/S:U/C:N/I:N/A:H"), 5.9);
+        assert_eq!(score("CVSS:3.0/AV:N/AC:L/PR:N/UI:R/S:C/C:L/I:L/A:N"), 6.1);
+        assert_eq!(score("CVSS:3.1/AV:L/AC:L/PR:H/UI:N/S:C/C:H/I:H/A:H"), 8.2);
+        assert_eq!(score("CVSS:3.1/AV:L/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:N"), 0.0);
+
+        assert!(cvss_base_score("CVSS:4.0/AV:N/AC:L/AT:N/PR:N/UI:N").is_err());
+        assert!(
+            cvss_base_score("CVSS:3.1/AV:X/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H")
+                .unwrap_err()
+                .to_string()
+                .contains("Invalid CVSS AV value")
+        );
+
+        assert_eq!(Severity::from_score(9.8), Severity::Critical);
+        assert_eq!(Severity::from_score(5.9), Severity::Medium);
+        assert_eq!(Severity::from_score(0.0), Severity::None);
+        assert_eq!("moderate".parse::<Severity>().unwrap(), Severity::Medium);
+        assert!(Severity::High > Severity::Medium);
+    }
+
+    #[test]
+    fn test_parse_advisory() {
+        let advisory = Advisory::parse(TIME_ADVISORY).unwrap();
+        assert_eq!(advisory.id, "RUSTSEC-2020-0071");
+        assert_eq!(advisory.package, "time");
+        assert_eq!(advisory.title, "Potential segfault in the time crate");
+        assert!(advisory.description.starts_with("Unix-like"));
+        assert_eq!(advisory.aliases[0], "CVE-2020-26235");
+        assert_eq!(advisory.severity(), Some(Severity::Medium));
+        assert!(!advisory.is_informational());
+
+        let version = |v: &str| semver::Version::parse(v).unwrap();
+        assert!(advisory.affects(&version("0.1.43")));
+        assert!(advisory.affects(&version("0.2.22")));
+        assert!(!advisory.affects(&version("0.2.1")));
+        assert!(!advisory.affects(&version("0.2.23")));
+        assert!(!advisory.affects(&version("0.3.5")));
+
+        let informational = Advisory::parse(CHRONO_ADVISORY).unwrap();
+        assert_eq!(informational.informational.as_deref(), Some("unsound"));
+        assert_eq!(informational.severity(), None);
+
+        // Plain TOML advisories carry their title in the header
+        let toml = "[advisory]\nid = \"RUSTSEC-2019-0001\"\npackage = \"x\"\n\
+                    date = \"2019-01-01\"\ntitle = \"Old format\"\n";
+        assert_eq!(Advisory::parse(toml).unwrap().title, "Old format");
+
+        assert!(Advisory::parse("```toml\n[advisory]\nid = \"x\"\n").is_err());
+        let bad_range = TIME_ADVISORY.replace(">= 0.2.23", ">= zero");
+        assert!(format!("{:#}", Advisory::parse(&bad_range).unwrap_err())
+            .contains("Invalid version range: >= zero"));
+    }
+
+    #[test]
+    fn test_audit_lockfile() {
+        let dir = TempDir::new().unwrap();
+        write_db(dir.path());
+        let database = AdvisoryDatabase::open(dir.path()).unwrap();
+        assert_eq!(database.advisories().len(), 3);
+
+        let graph = DependencyGraph::parse(CARGO_LOCK).unwrap();
+        let report = database.audit(&graph);
+        assert_eq!(report.advisories, 3);
+        assert_eq!(report.packages, 4);
+        assert!(!report.is_clean());
+
+        // The git build of time 0.3.5 is not the crates.io crate
+        let ids: Vec<&str> = report.findings.iter().map(|f| f.id.as_str()).collect();
+        assert_eq!(ids, vec!["RUSTSEC-2020-0071", "RUSTSEC-2020-0159"]);
+
+        let time = &report.vulnerabilities()[0];
+        assert_eq!(time.version, "0.1.43");
+        assert_eq!(time.severity, Some(Severity::Medium));
+        assert_eq!(time.score, Some(5.9));
+        assert_eq!(time.patched, vec![">=0.2.23"]);
+        assert_eq!(time.path, vec!["app 0.1.0", "chrono 0.4.19", "time 0.1.43"]);
+
+        let chrono = &report.warnings()[0];
+        assert_eq!(chrono.severity_label(), "unsound");
+
+        let csv = report.table().render(OutputFormat::Csv);
+        assert!(csv.starts_with("ID,Crate,Version,Severity,Score,Title,Patched,Path\n"));
+        assert!(csv.contains("RUSTSEC-2020-0071,time,0.1.43,medium,5.9,"));
+        assert!(csv.contains("app 0.1.0 > chrono 0.4.19 > time 0.1.43"));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["findings"][0]["severity"], "medium");
+
+        // A graph without affected versions is clean
+        let patched = CARGO_LOCK
+            .replace("\"time 0.1.43\"", "\"time 0.2.23\"")
+            .replace("version = \"0.1.43\"", "version = \"0.2.23\"");
+        let report = database.audit(&DependencyGraph::parse(&patched).unwrap());
+        assert!(report.is_clean());
+        assert_eq!(report.warnings().len(), 1);
+    }
+
+    #[test]
+    fn test_audit_project() {
+        let dir = TempDir::new().unwrap();
+        let err = audit_project(".", dir.path().join("advisory-db")).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("No RustSec advisory database at"));
+        assert!(err.to_string().contains(ADVISORY_DB_ENV));
+
+        let db = dir.path().join("advisory-db");
+        write_db(&db);
+        std::fs::write(
+            db.join("crates").join("time").join("RUSTSEC-2022-0001.md"),
+            "no header",
+        )
+        .unwrap();
+        let err = AdvisoryDatabase::open(&db).unwrap_err();
+        assert!(err.to_string().contains("RUSTSEC-2022-0001.md"));
+
+        std::fs::remove_file(db.join("crates").join("time").join("RUSTSEC-2022-0001.md")).unwrap();
+        let project = dir.path().join("project");
+        std::fs::create_dir(&project).unwrap();
+        std::fs::write(project.join(LOCKFILE), CARGO_LOCK).unwrap();
+        let report = audit_project(&project, &db).unwrap();
+        assert_eq!(report.vulnerabilities().len(), 1);
+    }
+}
@@ -0,0 +1,268 @@
+// Tests for config module
+//
+// This module tests layer discovery, merge precedence, environment and
+// command line overrides, and provenance reporting
+
+use serde_json::json;
+use std::path::PathBuf;
+use tempfile::TempDir;
+use ubuntu_config_scripts::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_config(dir: &TempDir, content: &str) -> PathBuf {
+        let path = dir.path().join("config.json");
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_defaults_only() {
+        let loaded = ConfigLoader::empty().load().unwrap();
+        assert_eq!(loaded.config.audio.volume_level, Some(70));
+        assert_eq!(
+            loaded.source_of("audio.volume_level"),
+            ConfigSource::Default
+        );
+        assert_eq!(loaded.config.system.log_level, "info");
+    }
+
+    #[test]
+    fn test_layer_precedence() {
+        let system = TempDir::new().unwrap();
+        let user = TempDir::new().unwrap();
+        let system_file = write_config(
+            &system,
+            r#"{"system": {"log_level": "warn"}, "audio": {"volume_level": 40}}"#,
+        );
+        let user_file = write_config(&user, r#"{"audio": {"volume_level": 50}}"#);
+
+        let loaded = ConfigLoader::empty()
+            .system_dir(Some(system.path().to_path_buf()))
+            .user_dir(Some(user.path().to_path_buf()))
+            .env_vars(env(&[("UCS_AUDIO__VOLUME_LEVEL", "60")]))
+            .load()
+            .unwrap();
+
+        assert_eq!(loaded.config.system.log_level, "warn");
+        assert_eq!(
+            loaded.source_of("system.log_level"),
+            ConfigSource::System(system_file)
+        );
+        assert_eq!(loaded.config.audio.volume_level, Some(60));
+        assert_eq!(
+            loaded.source_of("audio.volume_level"),
+            ConfigSource::Env("UCS_AUDIO__VOLUME_LEVEL".to_string())
+        );
+        assert!(loaded
+            .explain()
+            .iter()
+            .all(|e| e.source != ConfigSource::User(user_file.clone())));
+    }
+
+    #[test]
+    fn test_cli_override_wins() {
+        let loaded = ConfigLoader::empty()
+            .env_vars(env(&[("UCS_DEV__BUILD_MODE", "debug")]))
+            .set("dev.build_mode", "release")
+            .set_assignment("dev.target_arch=[\"aarch64\"]")
+            .unwrap()
+            .load()
+            .unwrap();
+
+        assert_eq!(loaded.config.dev.build_mode, "release");
+        assert_eq!(loaded.config.dev.target_arch, vec!["aarch64"]);
+        assert_eq!(
+            loaded.source_of("dev.build_mode"),
+            ConfigSource::Cli("dev.build_mode=release".to_string())
+        );
+    }
+
+    #[test]
+    fn test_explicit_file_layer() {
+        let dir = TempDir::new().unwrap();
+        let path = write_config(&dir, r#"{"audio": {"enable_echo_cancellation": false}}"#);
+
+        let loaded = ConfigLoader::empty()
+            .file(Some(path.clone()))
+            .load()
+            .unwrap();
+        assert!(!loaded.config.audio.enable_echo_cancellation);
+        assert_eq!(
+            loaded.source_of("audio.enable_echo_cancellation"),
+            ConfigSource::File(path)
+        );
+    }
+
+    #[test]
+    fn test_toml_and_yaml_layers() {
+        let system = TempDir::new().unwrap();
+        let user = TempDir::new().unwrap();
+        std::fs::write(
+            system.path().join("config.toml"),
+            "[system]\nlog_level = \"debug\"\n",
+        )
+        .unwrap();
+        let user_file = user.path().join("config.yml");
+        std::fs::write(&user_file, "audio:\n  volume_level: 35\n").unwrap();
+
+        let loaded = ConfigLoader::empty()
+            .system_dir(Some(system.path().to_path_buf()))
+            .user_dir(Some(user.path().to_path_buf()))
+            .load()
+            .unwrap();
+
+        assert_eq!(loaded.config.system.log_level, "debug");
+        assert_eq!(loaded.config.audio.volume_level, Some(35));
+        assert_eq!(
+            loaded.source_of("audio.volume_level"),
+            ConfigSource::User(user_file)
+        );
+    }
+
+    #[test]
+    fn test_script_sections_from_layers() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("config.toml"),
+            "[obs]\nresolution = \"2560x1440\"\n\n[nvidia]\ntarget_version = \"550\"\n",
+        )
+        .unwrap();
+
+        let loaded = ConfigLoader::empty()
+            .user_dir(Some(dir.path().to_path_buf()))
+            .env_vars(env(&[("UCS_PIPEWIRE_MONITOR__CHECK_INTERVAL_SECS", "10")]))
+            .set("time.ntp_servers", "[\"0.pool.ntp.org\"]")
+            .load()
+            .unwrap();
+
+        assert_eq!(loaded.config.obs.resolution, "2560x1440");
+        assert_eq!(loaded.config.obs.fps, 30);
+        assert_eq!(loaded.config.nvidia.target_version.as_deref(), Some("550"));
+        assert_eq!(loaded.config.pipewire_monitor.check_interval_secs, 10);
+        assert_eq!(loaded.config.time.ntp_servers, vec!["0.pool.ntp.org"]);
+        assert!(loaded.config.extra.is_empty());
+        assert_eq!(
+            loaded.source_of("pipewire_monitor.check_interval_secs"),
+            ConfigSource::Env("UCS_PIPEWIRE_MONITOR__CHECK_INTERVAL_SECS".to_string())
+        );
+    }
+
+    #[test]
+    fn test_env_var_paths() {
+        assert_eq!(
+            env_var_path("UCS_AUDIO__VOLUME_LEVEL"),
+            Some(vec!["audio".to_string(), "volume_level".to_string()])
+        );
+        assert_eq!(env_var_path("UCS_LOG"), None);
+        assert_eq!(env_var_path("UCS_TRACE"), None);
+        assert_eq!(env_var_path("UCS_AUDIO__"), None);
+        assert_eq!(env_var_path("HOME"), None);
+    }
+
+    #[test]
+    fn test_numeric_text_for_string_fields() {
+        let loaded = ConfigLoader::empty()
+            .env_vars(vec![(
+                "UCS_AUDIO__DEFAULT_SINK".to_string(),
+                "1".to_string(),
+            )])
+            .set("nvidia.target_version", "550")
+            .load()
+            .unwrap();
+        assert_eq!(loaded.config.audio.default_sink.as_deref(), Some("1"));
+        assert_eq!(loaded.config.nvidia.target_version.as_deref(), Some("550"));
+        assert!(loaded.config.validation_errors().is_empty());
+    }
+
+    #[test]
+    fn test_override_values() {
+        assert_eq!(parse_override_value("60"), json!(60));
+        assert_eq!(parse_override_value("false"), json!(false));
+        assert_eq!(parse_override_value("pipewire"), json!("pipewire"));
+        assert!(split_path("audio..volume").is_err());
+
+        let defaults = serde_json::to_value(Config::default()).unwrap();
+        let path = |key: &str| split_path(key).unwrap();
+        assert_eq!(
+            typed_override_value(&defaults, &path("audio.volume_level"), "60"),
+            json!(60)
+        );
+        assert_eq!(
+            typed_override_value(&defaults, &path("nvidia.target_version"), "550"),
+            json!("550")
+        );
+        assert_eq!(
+            typed_override_value(&defaults, &path("profiles.work.audio.default_sink"), "1"),
+            json!("1")
+        );
+        assert_eq!(
+            typed_override_value(&defaults, &path("unknown.key"), "1"),
+            json!(1)
+        );
+        assert!(ConfigLoader::empty().set_assignment("novalue").is_err());
+    }
+
+    #[test]
+    fn test_merge_replaces_arrays_and_scalars() {
+        let mut base =
+            json!({"dev": {"target_arch": ["x86_64", "aarch64"], "optimization_level": 3}});
+        merge_values(
+            &mut base,
+            json!({"dev": {"target_arch": ["riscv64"]}, "new": {"key": 1}}),
+        );
+        assert_eq!(
+            base,
+            json!({"dev": {"target_arch": ["riscv64"], "optimization_level": 3}, "new": {"key": 1}})
+        );
+    }
+
+    #[test]
+    fn test_invalid_file_reports_path() {
+        let dir = TempDir::new().unwrap();
+        write_config(&dir, "{ not json");
+        let err = ConfigLoader::empty()
+            .user_dir(Some(dir.path().to_path_buf()))
+            .load()
+            .unwrap_err();
+        assert!(format!("{:#}", err).contains("config.json"));
+    }
+
+    #[test]
+    fn test_type_mismatch_is_an_error() {
+        let result = ConfigLoader::empty()
+            .env_vars(env(&[("UCS_AUDIO__VOLUME_LEVEL", "loud")]))
+            .load();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_explain_table_formats() {
+        let loaded = ConfigLoader::empty()
+            .set("audio.volume_level", "55")
+            .load()
+            .unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&loaded.explain_table().render(OutputFormat::Json)).unwrap();
+        let row = json
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|r| r["Key"] == "audio.volume_level")
+            .unwrap();
+        assert_eq!(row["Value"], "55");
+        assert_eq!(row["Source"], "cli (audio.volume_level=55)");
+
+        let csv = loaded.explain_table().render(OutputFormat::Csv);
+        assert!(csv.contains("system.log_level,\"\"\"info\"\"\",default"));
+    }
+}
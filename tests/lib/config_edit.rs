@@ -0,0 +1,282 @@
+// Tests for config_edit module
+//
+// This module tests schema-checked `set`/`unset` on TOML, YAML and JSON
+// files, preservation of comments and ordering, and `$EDITOR` round trips
+
+use serde_json::json;
+use std::path::PathBuf;
+use tempfile::TempDir;
+use ubuntu_config_scripts::*;
+
+const TOML: &str = r#"# Managed by hand
+version = 1
+
+[audio]
+# Speakers are loud
+volume_level = 40 # percent
+default_sink = "hdmi"
+
+[system]
+log_level = "warn"
+"#;
+
+const YAML: &str = r#"# Managed by hand
+version: 1
+audio:
+  # Speakers are loud
+  volume_level: 40  # percent
+  default_sink: hdmi
+time:
+  ntp_servers:
+    - a.ntp.org
+    - b.ntp.org
+  timezone: Europe/Berlin
+system:
+  log_level: warn
+"#;
+
+fn write(dir: &TempDir, name: &str, content: &str) -> PathBuf {
+    let path = dir.path().join(name);
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toml_set_keeps_comments_and_order() {
+        let dir = TempDir::new().unwrap();
+        let path = write(&dir, "config.toml", TOML);
+        let mut document = ConfigDocument::open(&path).unwrap();
+
+        assert_eq!(
+            document.assign("audio.volume_level", "55").unwrap(),
+            json!(55)
+        );
+        document
+            .assign("audio.enable_echo_cancellation", "false")
+            .unwrap();
+        document.assign("obs.fps", "60").unwrap();
+
+        assert_eq!(
+            document.text(),
+            r#"# Managed by hand
+version = 1
+
+[audio]
+# Speakers are loud
+volume_level = 55 # percent
+default_sink = "hdmi"
+enable_echo_cancellation = false
+
+[system]
+log_level = "warn"
+
+[obs]
+fps = 60
+"#
+        );
+
+        assert!(document.unset("audio.default_sink").unwrap());
+        assert!(!document.unset("audio.default_sink").unwrap());
+        assert!(!document.text().contains("hdmi"));
+        assert!(document.text().contains("# Speakers are loud"));
+    }
+
+    #[test]
+    fn test_yaml_set_keeps_comments_and_order() {
+        let dir = TempDir::new().unwrap();
+        let path = write(&dir, "config.yaml", YAML);
+        let mut document = ConfigDocument::open(&path).unwrap();
+
+        document.assign("audio.volume_level", "55").unwrap();
+        document
+            .assign("time.ntp_servers", "[\"ntp.ubuntu.com\"]")
+            .unwrap();
+        document.assign("system.temp_dir", "/tmp").unwrap();
+        document.assign("obs.resolution", "2560x1440").unwrap();
+
+        assert_eq!(
+            document.text(),
+            r#"# Managed by hand
+version: 1
+audio:
+  # Speakers are loud
+  volume_level: 55  # percent
+  default_sink: hdmi
+time:
+  ntp_servers: ["ntp.ubuntu.com"]
+  timezone: Europe/Berlin
+system:
+  log_level: warn
+  temp_dir: /tmp
+obs:
+  resolution: 2560x1440
+"#
+        );
+
+        assert!(document.unset("time").unwrap());
+        assert!(!document.text().contains("timezone"));
+        assert!(document.text().contains("system:\n  log_level: warn"));
+        assert!(!document.unset("time.timezone").unwrap());
+    }
+
+    #[test]
+    fn test_yaml_unset_last_child_drops_parent() {
+        let dir = TempDir::new().unwrap();
+        let path = write(
+            &dir,
+            "config.yaml",
+            "version: 1\naudio:\n  volume_level: 40\nprofiles:\n  quiet:\n    audio:\n      volume_level: 20\n",
+        );
+        let mut document = ConfigDocument::open(&path).unwrap();
+        assert!(document.unset("audio.volume_level").unwrap());
+        assert!(document.unset("profiles.quiet.audio.volume_level").unwrap());
+        assert_eq!(document.text(), "version: 1\n");
+
+        document.save().unwrap();
+        let document = ConfigDocument::open(&path).unwrap();
+        assert_eq!(document.value().unwrap(), json!({"version": 1}));
+        assert!(document.validate().unwrap().is_empty());
+        assert!(ConfigLoader::empty().file(Some(path)).load().is_ok());
+    }
+
+    #[test]
+    fn test_yaml_rejects_flow_parents() {
+        let dir = TempDir::new().unwrap();
+        let path = write(&dir, "config.yml", "audio: {volume_level: 40}\n");
+        let mut document = ConfigDocument::open(&path).unwrap();
+        let err = document.assign("audio.volume_level", "50").unwrap_err();
+        assert!(err.to_string().contains("not a block mapping"));
+    }
+
+    #[test]
+    fn test_json_set_keeps_key_order() {
+        let dir = TempDir::new().unwrap();
+        let path = write(
+            &dir,
+            "config.json",
+            r#"{"version": 1, "system": {"log_level": "warn"}, "audio": {"volume_level": 40}}"#,
+        );
+        let mut document = ConfigDocument::open(&path).unwrap();
+        document.assign("audio.volume_level", "45").unwrap();
+        document.assign("system.auto_update", "false").unwrap();
+
+        let text = document.text();
+        assert!(text.find("\"system\"").unwrap() < text.find("\"audio\"").unwrap());
+        assert_eq!(
+            document.value().unwrap(),
+            json!({"version": 1, "system": {"log_level": "warn", "auto_update": false}, "audio": {"volume_level": 45}})
+        );
+    }
+
+    #[test]
+    fn test_assign_checks_types_and_rules() {
+        let dir = TempDir::new().unwrap();
+        let path = write(&dir, "config.toml", TOML);
+        let mut document = ConfigDocument::open(&path).unwrap();
+
+        // Numeric text for a string field is kept as a string
+        assert_eq!(
+            document.assign("nvidia.target_version", "550").unwrap(),
+            json!("550")
+        );
+
+        let err = document.assign("audio.volume_level", "loud").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Invalid value for audio.volume_level"));
+
+        let before = document.text().to_string();
+        let err = document.assign("audio.volume_level", "150").unwrap_err();
+        assert!(err.to_string().contains("/audio/volume_level"));
+        assert_eq!(document.text(), before);
+
+        let err = document.assign("obs.fsp", "60").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown configuration key: obs.fsp (did you mean obs.fps?)"
+        );
+        assert!(document.assign("version", "2").is_err());
+        assert!(document.assign("audio..volume_level", "2").is_err());
+    }
+
+    #[test]
+    fn test_new_file_and_null_values() {
+        let dir = TempDir::new().unwrap();
+        let path = layer_file(&dir.path().join("ubuntu-config-scripts"));
+        assert!(path.ends_with("ubuntu-config-scripts/config.toml"));
+
+        let mut document = ConfigDocument::open(&path).unwrap();
+        document.assign("nvidia.target_version", "550").unwrap();
+        document.save().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!(
+                "version = {}\n\n[nvidia]\ntarget_version = \"550\"\n",
+                CONFIG_VERSION
+            )
+        );
+        assert_eq!(layer_file(path.parent().unwrap()), path);
+
+        // TOML cannot store null, so setting null removes the key
+        document.assign("nvidia.target_version", "null").unwrap();
+        assert_eq!(document.get("nvidia.target_version").unwrap(), None);
+    }
+
+    #[test]
+    fn test_validate_whole_document() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+
+        let document = ConfigDocument::from_text(&path, "[obs]\nfps = 0\nencoder = \"x265\"\n");
+        let errors = document.validate().unwrap();
+        assert_eq!(errors.len(), 2);
+
+        let document = ConfigDocument::from_text(&path, "[obs]\nfps = \"fast\"\n");
+        assert!(format!("{:#}", document.validate().unwrap_err()).contains("config.toml"));
+
+        let document = ConfigDocument::from_text(&path, "");
+        assert!(document.validate().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_edit_file_saves_valid_changes() {
+        let dir = TempDir::new().unwrap();
+        let path = write(&dir, "config.toml", TOML);
+
+        let changed = edit_file(
+            &path,
+            "sed -i 's/volume_level = 40/volume_level = 65/'",
+            |_| panic!("valid edit should not be retried"),
+        )
+        .unwrap();
+        assert!(changed);
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains("volume_level = 65 # percent"));
+
+        assert!(!edit_file(&path, "true", |_| Ok(false)).unwrap());
+    }
+
+    #[test]
+    fn test_edit_file_rejects_invalid_changes() {
+        let dir = TempDir::new().unwrap();
+        let path = write(&dir, "config.toml", TOML);
+
+        let mut attempts = Vec::new();
+        let err = edit_file(&path, "sed -i 's/warn/loud/'", |problems| {
+            attempts.push(problems.to_string());
+            Ok(attempts.len() < 2)
+        })
+        .unwrap_err();
+
+        assert_eq!(attempts.len(), 2);
+        assert!(attempts[0].contains("/system/log_level"));
+        assert!(err.to_string().contains("was not saved"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), TOML);
+
+        assert!(edit_file(&path, "false", |_| Ok(false)).is_err());
+    }
+}
@@ -0,0 +1,295 @@
+// Tests for license module
+//
+// This module tests SPDX expression parsing, the deny.toml license policy
+// (allow, deny, exceptions, clarify) and checking locked crates using the
+// registry source cache and workspace manifests
+
+use std::path::Path;
+use tempfile::TempDir;
+use ubuntu_config_scripts::*;
+
+const POLICY: &str = r#"
+[advisories]
+ignore = []
+
+[licenses]
+allow = ["MIT", "Apache-2.0", "Apache-2.0 WITH LLVM-exception", "ISC"]
+deny = ["GPL-3.0"]
+exceptions = [
+    { allow = ["OpenSSL"], crate = "ring" },
+    { allow = ["MPL-2.0"], name = "webpki-roots", version = "<0.26" },
+]
+
+[[licenses.clarify]]
+crate = "unclear@1.0.0"
+expression = "MIT AND ISC"
+"#;
+
+const CARGO_LOCK: &str = r#"version = 4
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["member", "ring", "gpl-only", "mystery", "unclear", "bare"]
+
+[[package]]
+name = "member"
+version = "0.1.0"
+
+[[package]]
+name = "ring"
+version = "0.17.8"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "gpl-only"
+version = "2.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "mystery"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "unclear"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "bare"
+version = "0.3.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, version: &str) -> Package {
+        Package {
+            name: name.to_string(),
+            version: semver::Version::parse(version).unwrap(),
+            source: PackageSource::parse(Some(CRATES_IO_SOURCE)),
+            checksum: None,
+            dependencies: Vec::new(),
+        }
+    }
+
+    fn write(path: &Path, content: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_parse_expressions() {
+        let expr = LicenseExpr::parse("(MIT OR Apache-2.0) AND Unicode-3.0").unwrap();
+        assert_eq!(expr.to_string(), "(MIT OR Apache-2.0) AND Unicode-3.0");
+        assert_eq!(expr.licenses().len(), 3);
+
+        // AND binds tighter than OR
+        let expr = LicenseExpr::parse("MIT OR Apache-2.0 AND ISC").unwrap();
+        assert!(matches!(expr, LicenseExpr::Or(ref terms) if terms.len() == 2));
+        assert_eq!(expr.to_string(), "MIT OR Apache-2.0 AND ISC");
+
+        let expr = LicenseExpr::parse("Apache-2.0 WITH LLVM-exception OR MIT").unwrap();
+        assert_eq!(
+            expr.licenses()[0],
+            &LicenseReq {
+                id: "Apache-2.0".to_string(),
+                exception: Some("LLVM-exception".to_string()),
+            }
+        );
+
+        // Legacy separators and lowercase operators
+        assert_eq!(
+            LicenseExpr::parse("MIT/Apache-2.0").unwrap(),
+            LicenseExpr::parse("MIT or Apache-2.0").unwrap()
+        );
+        assert_eq!(
+            LicenseExpr::parse("GPL-2.0+").unwrap().to_string(),
+            "GPL-2.0+"
+        );
+
+        for invalid in [
+            "",
+            "MIT AND",
+            "(MIT OR ISC",
+            "MIT ISC",
+            "MIT WITH",
+            "MIT, ISC",
+        ] {
+            assert!(LicenseExpr::parse(invalid).is_err(), "{}", invalid);
+        }
+        assert!(LicenseReq::parse("MIT OR ISC").is_err());
+    }
+
+    #[test]
+    fn test_policy_evaluation() {
+        let policy = LicensePolicy::parse(POLICY).unwrap();
+        assert_eq!(policy.allow.len(), 4);
+        assert_eq!(policy.exceptions[1].name, "webpki-roots");
+        assert_eq!(policy.clarify[0].name, "unclear");
+
+        let any = package("any", "1.0.0");
+        let check = |package: &Package, expr: &str| {
+            policy.evaluate(package, &LicenseExpr::parse(expr).unwrap())
+        };
+        assert_eq!(
+            check(&any, "MIT OR Apache-2.0"),
+            (LicenseStatus::Allowed, "MIT, Apache-2.0".to_string())
+        );
+        assert_eq!(
+            check(&any, "MIT OR Unlicense"),
+            (LicenseStatus::Allowed, "MIT".to_string())
+        );
+        assert_eq!(
+            check(&any, "MIT AND Unlicense"),
+            (
+                LicenseStatus::Rejected,
+                "not allowed: Unlicense".to_string()
+            )
+        );
+        assert_eq!(
+            check(&any, "GPL-3.0 OR Zlib"),
+            (LicenseStatus::Rejected, "denied: GPL-3.0".to_string())
+        );
+        // A denied alternative does not matter when another is allowed
+        assert_eq!(check(&any, "MIT OR GPL-3.0").0, LicenseStatus::Allowed);
+
+        // Exceptions must match exactly
+        assert_eq!(
+            check(&any, "Apache-2.0 WITH LLVM-exception").0,
+            LicenseStatus::Allowed
+        );
+        assert_eq!(
+            check(&any, "ISC WITH LLVM-exception").0,
+            LicenseStatus::Rejected
+        );
+
+        // Per-crate exceptions, with optional version ranges
+        assert_eq!(
+            check(&package("ring", "0.17.8"), "MIT AND ISC AND OpenSSL"),
+            (
+                LicenseStatus::Allowed,
+                "MIT, ISC, OpenSSL (exception)".to_string()
+            )
+        );
+        assert_eq!(check(&any, "OpenSSL").0, LicenseStatus::Rejected);
+        assert_eq!(
+            check(&package("webpki-roots", "0.25.4"), "MPL-2.0").0,
+            LicenseStatus::Allowed
+        );
+        assert_eq!(
+            check(&package("webpki-roots", "0.26.0"), "MPL-2.0").0,
+            LicenseStatus::Rejected
+        );
+    }
+
+    #[test]
+    fn test_policy_files() {
+        // The repository's own deny.toml
+        let policy = LicensePolicy::from_file(DENY_TOML).unwrap();
+        assert!(policy.allow.contains(&LicenseReq::parse("MIT").unwrap()));
+        assert!(policy
+            .allow
+            .contains(&LicenseReq::parse("Apache-2.0 WITH LLVM-exception").unwrap()));
+
+        let err = LicensePolicy::parse("[licenses]\nallow = [\"MIT OR ISC\"]\n").unwrap_err();
+        assert!(err.to_string().contains("Expected a single license"));
+        let err = LicensePolicy::parse("[[licenses.clarify]]\ncrate = \"x\"\n").unwrap_err();
+        assert_eq!(err.to_string(), "Clarification for x has no expression");
+    }
+
+    #[test]
+    fn test_check_locked_crates() {
+        let dir = TempDir::new().unwrap();
+        let project = dir.path().join("project");
+        write(&project.join(LOCKFILE), CARGO_LOCK);
+        write(
+            &project.join(MANIFEST),
+            "[package]\nname = \"app\"\nlicense = \"MIT\"\n\n\
+             [workspace]\nmembers = [\"member\"]\n\n\
+             [workspace.package]\nlicense = \"GPL-3.0\"\n",
+        );
+        write(
+            &project.join("member").join(MANIFEST),
+            "[package]\nname = \"member\"\nlicense.workspace = true\n",
+        );
+
+        let registry = dir.path().join("registry");
+        let manifest = |name: &str, fields: &str| {
+            write(
+                &registry.join(name).join("Cargo.toml"),
+                &format!("[package]\nname = \"x\"\n{}", fields),
+            );
+        };
+        manifest("ring-0.17.8", "license = \"MIT AND ISC AND OpenSSL\"\n");
+        manifest("gpl-only-2.0.0", "license = \"GPL-3.0\"\n");
+        manifest("unclear-1.0.0", "license-file = \"LICENSE\"\n");
+        manifest("bare-0.3.0", "license-file = \"COPYING\"\n");
+
+        let graph = DependencyGraph::load(&project).unwrap();
+        let sources = LicenseSources::new(&project)
+            .unwrap()
+            .registry_dirs(vec![registry.clone()]);
+        let report = LicensePolicy::parse(POLICY)
+            .unwrap()
+            .check(&graph, &sources);
+
+        let results: Vec<(&str, LicenseStatus)> = report
+            .crates
+            .iter()
+            .map(|c| (c.name.as_str(), c.status))
+            .collect();
+        assert_eq!(
+            results,
+            vec![
+                ("app", LicenseStatus::Allowed),
+                ("bare", LicenseStatus::Rejected),
+                ("gpl-only", LicenseStatus::Rejected),
+                ("member", LicenseStatus::Rejected),
+                ("mystery", LicenseStatus::Unknown),
+                ("ring", LicenseStatus::Allowed),
+                ("unclear", LicenseStatus::Allowed),
+            ]
+        );
+        assert!(!report.is_ok());
+        assert_eq!(report.rejected().len(), 3);
+        assert_eq!(report.failures(false).len(), 3);
+        let failing: Vec<&str> = report
+            .failures(true)
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(failing, vec!["bare", "gpl-only", "member", "mystery"]);
+
+        let bare = &report.crates[1];
+        assert!(bare.reason.contains("license-file = \"COPYING\""));
+        assert!(bare.reason.contains("[[licenses.clarify]]"));
+        // Workspace members inherit `[workspace.package]` values
+        assert_eq!(report.crates[3].license.as_deref(), Some("GPL-3.0"));
+        assert_eq!(report.crates[6].reason, "MIT, ISC (clarified in deny.toml)");
+
+        let csv = report.table(true).render(OutputFormat::Csv);
+        assert_eq!(csv.lines().count(), 4);
+        assert!(csv.contains("gpl-only,2.0.0,GPL-3.0,FAIL,denied: GPL-3.0\n"));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["crates"][0]["status"], "allowed");
+    }
+
+    #[test]
+    fn test_license_report_for_this_repository() {
+        let report = license_report(".", DENY_TOML).unwrap();
+        let own = report
+            .crates
+            .iter()
+            .find(|c| c.name == "ubuntu-config-scripts")
+            .unwrap();
+        assert_eq!(own.license.as_deref(), Some("MIT"));
+        assert_eq!(own.status, LicenseStatus::Allowed);
+
+        let licenses = check_licenses().unwrap();
+        assert_eq!(licenses["ubuntu-config-scripts-derive"], "MIT");
+    }
+}
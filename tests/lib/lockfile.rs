@@ -0,0 +1,317 @@
+// Tests for lockfile module
+//
+// This module tests Cargo.lock parsing, reference resolution, roots from the
+// manifest, graph queries (duplicates, dependents, why, depth) and the
+// tree, DOT and JSON output, and workspace member discovery
+
+use serde_json::json;
+use tempfile::TempDir;
+use ubuntu_config_scripts::*;
+
+const LOCKFILE_V4: &str = r#"# This file is automatically @generated by Cargo.
+version = 4
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = [
+ "helper",
+ "log",
+ "syn 2.0.106",
+]
+
+[[package]]
+name = "helper"
+version = "0.1.0"
+dependencies = [
+ "log",
+ "syn 1.0.109",
+ "wasi 0.11.0 (git+https://github.com/example/wasi?branch=main#0123abcd)",
+]
+
+[[package]]
+name = "log"
+version = "0.4.27"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "13dc2df351e3202783a1fe0d44375f7295ffb4049267b0f3018346dc122a1d94"
+
+[[package]]
+name = "syn"
+version = "1.0.109"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = [
+ "log",
+]
+
+[[package]]
+name = "syn"
+version = "2.0.106"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = [
+ "log",
+]
+
+[[package]]
+name = "wasi"
+version = "0.11.0"
+source = "git+https://github.com/example/wasi?branch=main#0123abcd"
+
+[[package]]
+name = "wasi"
+version = "0.11.0"
+source = "sparse+https://index.example.com/"
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(graph: &DependencyGraph, name: &str, version: &str) -> usize {
+        graph
+            .find(name)
+            .into_iter()
+            .find(|&i| graph.package(i).version.to_string() == version)
+            .unwrap()
+    }
+
+    fn names(graph: &DependencyGraph, indexes: &[usize]) -> Vec<String> {
+        indexes
+            .iter()
+            .map(|&i| graph.package(i).display_name())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_packages_and_references() {
+        let graph = DependencyGraph::parse(LOCKFILE_V4).unwrap();
+        assert_eq!(graph.packages().len(), 7);
+
+        let helper = graph.package(index(&graph, "helper", "0.1.0"));
+        assert_eq!(
+            names(&graph, &helper.dependencies),
+            vec![
+                "log v0.4.27",
+                "syn v1.0.109",
+                "wasi v0.11.0 (https://github.com/example/wasi?branch=main#0123abcd)",
+            ]
+        );
+
+        let log = graph.package(index(&graph, "log", "0.4.27"));
+        assert!(log.source.is_crates_io());
+        assert!(log.checksum.as_deref().unwrap().starts_with("13dc2df3"));
+
+        let wasi = graph.find("wasi");
+        assert_eq!(
+            graph.package(wasi[0]).source,
+            PackageSource::Git {
+                url: "https://github.com/example/wasi?branch=main".to_string(),
+                rev: Some("0123abcd".to_string()),
+            }
+        );
+        assert_eq!(
+            graph.package(wasi[1]).source.to_string(),
+            "sparse+https://index.example.com/"
+        );
+
+        // Without a manifest the roots are path packages nothing depends on
+        assert_eq!(names(&graph, graph.roots()), vec!["app v0.1.0 (path)"]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = DependencyGraph::parse(
+            "[[package]]\nname = \"a\"\nversion = \"1.0.0\"\ndependencies = [\"b\"]\n",
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Cannot resolve dependency 'b' of a 1.0.0");
+
+        let ambiguous = LOCKFILE_V4.replace("\"syn 2.0.106\"", "\"syn\"");
+        let err = DependencyGraph::parse(&ambiguous).unwrap_err();
+        assert!(format!("{:#}", err).contains("2 locked packages match"));
+
+        assert!(
+            DependencyGraph::parse("[[package]]\nname = \"a\"\nversion = \"one\"\n")
+                .unwrap_err()
+                .to_string()
+                .contains("Invalid version for a")
+        );
+    }
+
+    #[test]
+    fn test_duplicates_dependents_and_depth() {
+        let graph = DependencyGraph::parse(LOCKFILE_V4).unwrap();
+
+        let duplicates = graph.duplicates();
+        assert_eq!(
+            duplicates.keys().copied().collect::<Vec<_>>(),
+            vec!["syn", "wasi"]
+        );
+        let versions: Vec<String> = duplicates["syn"]
+            .iter()
+            .map(|p| p.version.to_string())
+            .collect();
+        assert_eq!(versions, vec!["1.0.109", "2.0.106"]);
+
+        let log = index(&graph, "log", "0.4.27");
+        assert_eq!(
+            names(&graph, &graph.dependents(log)),
+            vec![
+                "app v0.1.0 (path)",
+                "helper v0.1.0 (path)",
+                "syn v1.0.109",
+                "syn v2.0.106",
+            ]
+        );
+        assert_eq!(graph.depth(log), Some(1));
+        assert_eq!(graph.depth(index(&graph, "syn", "1.0.109")), Some(2));
+
+        // The sparse wasi is locked but nothing reaches it
+        let wasi = graph.find("wasi");
+        assert_eq!(graph.depth(wasi[1]), None);
+    }
+
+    #[test]
+    fn test_why() {
+        let graph = DependencyGraph::parse(LOCKFILE_V4).unwrap();
+        let paths = graph.why("syn").unwrap();
+        assert_eq!(paths.len(), 2);
+        assert_eq!(
+            names(&graph, &paths[0]),
+            vec!["app v0.1.0 (path)", "helper v0.1.0 (path)", "syn v1.0.109"]
+        );
+        assert_eq!(
+            names(&graph, &paths[1]),
+            vec!["app v0.1.0 (path)", "syn v2.0.106"]
+        );
+
+        assert_eq!(
+            graph.why("hepler").unwrap_err().to_string(),
+            "Package not found in Cargo.lock: hepler (did you mean helper?)"
+        );
+    }
+
+    #[test]
+    fn test_tree_dot_and_json() {
+        let graph = DependencyGraph::parse(LOCKFILE_V4).unwrap();
+        assert_eq!(
+            graph.tree(None),
+            "app v0.1.0 (path)\n\
+             ├── helper v0.1.0 (path)\n\
+             │   ├── log v0.4.27\n\
+             │   ├── syn v1.0.109\n\
+             │   │   └── log v0.4.27\n\
+             │   └── wasi v0.11.0 (https://github.com/example/wasi?branch=main#0123abcd)\n\
+             ├── log v0.4.27\n\
+             └── syn v2.0.106\n    \
+                 └── log v0.4.27\n"
+        );
+        assert_eq!(
+            graph.tree(Some(1)),
+            "app v0.1.0 (path)\n\
+             ├── helper v0.1.0 (path)\n\
+             ├── log v0.4.27\n\
+             └── syn v2.0.106\n"
+        );
+
+        let dot = graph.render(GraphFormat::Dot);
+        assert!(dot.starts_with("digraph dependencies {\n"));
+        assert!(dot.contains("    n0 [label=\"app 0.1.0\", shape=box];\n"));
+        assert!(dot.contains("    n3 [label=\"syn 1.0.109\", color=red];\n"));
+        assert!(dot.contains("    n0 -> n1;\n"));
+
+        let json = graph.to_json();
+        assert_eq!(json["roots"], json!(["app 0.1.0"]));
+        assert_eq!(json["packages"][1]["depth"], 1);
+        assert_eq!(
+            json["packages"][1]["dependencies"],
+            json!([
+                "log 0.4.27",
+                "syn 1.0.109",
+                "wasi 0.11.0 (git+https://github.com/example/wasi?branch=main#0123abcd)"
+            ])
+        );
+        assert_eq!(
+            json["packages"][6]["id"],
+            "wasi 0.11.0 (sparse+https://index.example.com/)"
+        );
+        assert_eq!(json["packages"][6]["depth"], json!(null));
+        assert_eq!("graphviz".parse::<GraphFormat>().unwrap(), GraphFormat::Dot);
+        assert!("svg".parse::<GraphFormat>().is_err());
+    }
+
+    #[test]
+    fn test_load_takes_roots_from_manifest() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join(LOCKFILE), LOCKFILE_V4).unwrap();
+        std::fs::write(
+            dir.path().join(MANIFEST),
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[workspace]\nmembers = [\"helper\"]\n",
+        )
+        .unwrap();
+        std::fs::create_dir(dir.path().join("helper")).unwrap();
+        std::fs::write(
+            dir.path().join("helper").join(MANIFEST),
+            "[package]\nname = \"helper\"\nversion = \"0.1.0\"\n",
+        )
+        .unwrap();
+
+        let graph = DependencyGraph::load(dir.path()).unwrap();
+        assert_eq!(
+            names(&graph, graph.roots()),
+            vec!["app v0.1.0 (path)", "helper v0.1.0 (path)"]
+        );
+        assert_eq!(graph.depth(index(&graph, "helper", "0.1.0")), Some(0));
+        assert!(graph.tree(None).contains("\n\nhelper v0.1.0 (path)\n"));
+
+        // This repository's own lockfile
+        let graph = DependencyGraph::load(".").unwrap();
+        assert_eq!(
+            graph.package(graph.roots()[0]).name,
+            "ubuntu-config-scripts"
+        );
+        assert!(find_duplicate_dependencies()
+            .unwrap()
+            .iter()
+            .all(|name| graph.find(name).len() > 1));
+    }
+
+    #[test]
+    fn test_workspace_member_globs() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(dir.path().join(LOCKFILE), LOCKFILE_V4).unwrap();
+        std::fs::write(
+            dir.path().join(MANIFEST),
+            "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/old\"]\n",
+        )
+        .unwrap();
+        for name in ["helper", "app", "old"] {
+            let member = dir.path().join("crates").join(name);
+            std::fs::create_dir_all(&member).unwrap();
+            std::fs::write(
+                member.join(MANIFEST),
+                format!("[package]\nname = \"{}\"\n", name),
+            )
+            .unwrap();
+        }
+        std::fs::create_dir_all(dir.path().join("crates").join("docs")).unwrap();
+
+        let manifests = workspace_manifests(dir.path()).unwrap();
+        let members: Vec<String> = manifests
+            .iter()
+            .skip(1)
+            .map(|(path, _)| path.strip_prefix(dir.path()).unwrap().display().to_string())
+            .collect();
+        assert_eq!(
+            members,
+            vec!["crates/app/Cargo.toml", "crates/helper/Cargo.toml"]
+        );
+        let graph = DependencyGraph::load(dir.path()).unwrap();
+        assert_eq!(graph.roots().len(), 2);
+
+        // ruchy/ declares its own [workspace]
+        assert_eq!(
+            nested_workspaces(".").unwrap(),
+            vec![std::path::PathBuf::from("./ruchy")]
+        );
+    }
+}
@@ -0,0 +1,248 @@
+// Tests for migrate module
+//
+// This module tests versioned configuration upgrades, migration reports
+// and in-place file migration with backups
+
+use serde_json::json;
+use tempfile::TempDir;
+use ubuntu_config_scripts::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legacy_migrations() -> Migrations {
+        Migrations::builtin().migration(
+            Migration::new(2, "Rename audio keys and move logging under system")
+                .rename("audio.volume", "audio.volume_level")
+                .move_to("logging.level", "system.log_level")
+                .remove("logging")
+                .add("dev.include_debug_symbols", json!(false)),
+        )
+    }
+
+    #[test]
+    fn test_builtin_matches_current_version() {
+        assert_eq!(Migrations::builtin().current_version(), CONFIG_VERSION);
+        assert_eq!(Config::default().version, CONFIG_VERSION);
+    }
+
+    #[test]
+    fn test_config_version() {
+        assert_eq!(config_version(&json!({})).unwrap(), 0);
+        assert_eq!(config_version(&json!({"version": 3})).unwrap(), 3);
+        assert!(config_version(&json!({"version": "two"})).is_err());
+    }
+
+    #[test]
+    fn test_unversioned_file_is_stamped() {
+        let report = migrate_value(json!({"audio": {"volume_level": 40}})).unwrap();
+        assert_eq!(report.from_version, 0);
+        assert_eq!(report.to_version, CONFIG_VERSION);
+        assert_eq!(
+            report.changes,
+            vec![MigrationChange::Versioned {
+                from: 0,
+                to: CONFIG_VERSION
+            }]
+        );
+        assert_eq!(report.value["version"], CONFIG_VERSION);
+        assert_eq!(report.value["audio"]["volume_level"], 40);
+    }
+
+    #[test]
+    fn test_current_file_is_unchanged() {
+        let report = migrate_value(json!({"version": CONFIG_VERSION})).unwrap();
+        assert!(!report.is_changed());
+    }
+
+    #[test]
+    fn test_newer_version_is_rejected() {
+        let err = migrate_value(json!({"version": CONFIG_VERSION + 1})).unwrap_err();
+        assert!(err.to_string().contains("newer than the supported version"));
+    }
+
+    #[test]
+    fn test_renames_and_moves() {
+        let report = legacy_migrations()
+            .migrate(json!({
+                "audio": {"volume": 55},
+                "logging": {"level": "debug", "color": true},
+            }))
+            .unwrap();
+
+        assert_eq!(report.value["audio"]["volume_level"], 55);
+        assert!(report.value["audio"].get("volume").is_none());
+        assert_eq!(report.value["system"]["log_level"], "debug");
+        assert!(report.value.get("logging").is_none());
+        assert_eq!(report.value["dev"]["include_debug_symbols"], false);
+        assert_eq!(report.value["version"], 2);
+
+        let changes: Vec<String> = report.changes.iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            changes,
+            vec![
+                "renamed audio.volume -> audio.volume_level",
+                "moved logging.level -> system.log_level",
+                "removed logging (was {\"color\":true})",
+                "added dev.include_debug_symbols = false",
+                "version 0 -> 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_existing_target_takes_precedence() {
+        let report = legacy_migrations()
+            .migrate(json!({"version": 1, "audio": {"volume": 10, "volume_level": 90}}))
+            .unwrap();
+        assert_eq!(report.value["audio"], json!({"volume_level": 90}));
+        assert_eq!(report.from_version, 1);
+    }
+
+    #[test]
+    fn test_migrate_file_writes_backup_in_same_format() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.yaml");
+        let original = "audio:\n  volume: 35\n";
+        std::fs::write(&path, original).unwrap();
+
+        let report = migrate_file(&path, &legacy_migrations(), false).unwrap();
+        let backup = report.backup.clone().unwrap();
+        assert_eq!(backup, dir.path().join("config.yaml.v0.bak"));
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), original);
+
+        let migrated = std::fs::read_to_string(&path).unwrap();
+        assert!(migrated.contains("volume_level: 35"));
+        assert!(migrated.contains("version: 2"));
+
+        let again = migrate_file(&path, &legacy_migrations(), false).unwrap();
+        assert!(!again.is_changed());
+        assert!(again.backup.is_none());
+    }
+
+    #[test]
+    fn test_profile_copies_are_migrated() {
+        let report = legacy_migrations()
+            .migrate(json!({
+                "version": 1,
+                "audio": {"volume": 30},
+                "profiles": {
+                    "quiet": {"audio": {"volume": 10}},
+                    "verbose": {"logging": {"level": "debug"}},
+                },
+            }))
+            .unwrap();
+        assert_eq!(
+            report.value["profiles"],
+            json!({
+                "quiet": {"audio": {"volume_level": 10}},
+                "verbose": {"system": {"log_level": "debug"}},
+            })
+        );
+        // Defaults are added to the file, not to every profile
+        assert!(report.value["profiles"]["quiet"].get("dev").is_none());
+
+        let changes: Vec<String> = report.changes.iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            changes,
+            vec![
+                "renamed audio.volume -> audio.volume_level",
+                "renamed profiles.quiet.audio.volume -> profiles.quiet.audio.volume_level",
+                "moved profiles.verbose.logging.level -> profiles.verbose.system.log_level",
+                "removed profiles.verbose.logging (was {})",
+                "added dev.include_debug_symbols = false",
+                "version 1 -> 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_migrate_file_keeps_comments() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "# Laptop settings\nversion = 1\n\n[audio]\n# quieter at night\nvolume = 20 # percent\n\n\
+             [system]\nlog_level = \"warn\" # keep logs short\n\n[logging]\ncolor = true\n",
+        )
+        .unwrap();
+
+        let report = migrate_file(&path, &legacy_migrations(), false).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "# Laptop settings\nversion = 2\n\n[audio]\nvolume_level = 20\n\n\
+             [system]\nlog_level = \"warn\" # keep logs short\n\n\
+             [dev]\ninclude_debug_symbols = false\n"
+        );
+        assert!(report.changes.contains(&MigrationChange::Removed {
+            path: "logging".to_string(),
+            value: json!({"color": true}),
+        }));
+
+        let path = dir.path().join("config.yaml");
+        std::fs::write(
+            &path,
+            "# Laptop settings\naudio:\n  volume: 30 # percent\n  default_sink: hdmi\n",
+        )
+        .unwrap();
+        migrate_file(&path, &legacy_migrations(), false).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "# Laptop settings\naudio:\n  default_sink: hdmi\n  volume_level: 30\n\
+             dev:\n  include_debug_symbols: false\nversion: 2\n"
+        );
+    }
+
+    #[test]
+    fn test_backups_are_never_overwritten() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(dir.path().join("config.toml.v0.bak"), "older backup\n").unwrap();
+        std::fs::write(&path, "[audio]\nvolume = 20\n").unwrap();
+
+        let report = migrate_file(&path, &legacy_migrations(), false).unwrap();
+        let backup = report.backup.unwrap();
+        assert_eq!(backup, dir.path().join("config.toml.v0.1.bak"));
+        assert_eq!(
+            std::fs::read_to_string(&backup).unwrap(),
+            "[audio]\nvolume = 20\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("config.toml.v0.bak")).unwrap(),
+            "older backup\n"
+        );
+        assert_eq!(
+            backup_path(&path, 0),
+            dir.path().join("config.toml.v0.2.bak")
+        );
+    }
+
+    #[test]
+    fn test_migrate_file_dry_run() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[audio]\nvolume = 20\n").unwrap();
+
+        let report = migrate_file(&path, &legacy_migrations(), true).unwrap();
+        assert!(report.is_changed());
+        assert!(report.backup.is_none());
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "[audio]\nvolume = 20\n"
+        );
+        assert!(!dir.path().join("config.toml.v0.bak").exists());
+    }
+
+    #[test]
+    fn test_unversioned_config_file_still_loads() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(&path, r#"{"system": {"log_level": "warn"}}"#).unwrap();
+
+        let config = Config::from_file(path.to_str().unwrap()).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.system.log_level, "warn");
+        assert!(config.extra.is_empty());
+    }
+}
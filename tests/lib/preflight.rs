@@ -0,0 +1,171 @@
+// Tests for preflight module
+//
+// This module tests mountinfo parsing, mount lookup, access and free space
+// checks, and the reasons Preflight gives for unmet requirements
+
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use tempfile::TempDir;
+use ubuntu_config_scripts::lib::schema::path;
+use ubuntu_config_scripts::*;
+
+const MOUNTINFO: &str = "\
+22 1 252:1 / / rw,relatime shared:1 - ext4 /dev/vda1 rw,errors=remount-ro
+25 22 0:23 / /tmp rw,nosuid,nodev,noexec shared:5 - tmpfs tmpfs rw,size=4096k
+26 22 0:24 / /mnt/media\\040disk ro,relatime shared:6 - vfat /dev/sdb1 ro,fmask=0022
+27 22 0:25 /@home /home rw,relatime shared:7 - btrfs /dev/vda2 rw,ssd,space_cache=v2
+28 25 0:26 / /tmp rw,relatime - tmpfs tmpfs rw
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_mountinfo() {
+        let mounts = parse_mountinfo(MOUNTINFO).unwrap();
+        assert_eq!(mounts.len(), 5);
+
+        let root = &mounts[0];
+        assert_eq!(root.mount_point, Path::new("/"));
+        assert_eq!(root.fs_type, "ext4");
+        assert_eq!(root.source, "/dev/vda1");
+        assert!(!root.is_read_only());
+
+        assert!(mounts[1].is_noexec());
+        assert_eq!(mounts[2].mount_point, Path::new("/mnt/media disk"));
+        assert!(mounts[2].is_read_only());
+        assert_eq!(mounts[3].root, Path::new("/@home"));
+        assert_eq!(mounts[3].super_options[1], "ssd");
+
+        assert!(parse_mountinfo("22 1 252:1 / / rw").is_err());
+        assert!(MountInfo::parse("x 1 0:1 / / rw - ext4 /dev/sda").is_err());
+    }
+
+    #[test]
+    fn test_mount_for_longest_and_latest() {
+        let mounts = parse_mountinfo(MOUNTINFO).unwrap();
+        let fs = |path: &str| mount_for(&mounts, Path::new(path)).unwrap().mount_id;
+        assert_eq!(fs("/etc/fstab"), 22);
+        assert_eq!(fs("/home/user/.cargo"), 27);
+        assert_eq!(fs("/mnt/media disk/a"), 26);
+        // The later mount on /tmp shadows the first one
+        assert_eq!(fs("/tmp/build"), 28);
+        // Prefixes only match whole components
+        assert_eq!(fs("/tmpfiles"), 22);
+    }
+
+    #[test]
+    fn test_real_mount_and_free_space() {
+        let dir = TempDir::new().unwrap();
+        let mount = find_mount(&dir.path().join("missing/child")).unwrap();
+        assert!(dir
+            .path()
+            .canonicalize()
+            .unwrap()
+            .starts_with(&mount.mount_point));
+        assert_eq!(filesystem_type(dir.path()).unwrap(), mount.fs_type);
+        assert!(available_space(dir.path()).unwrap() > 0);
+
+        assert!(path::validate_free_space(dir.path().to_str().unwrap(), 1).is_success());
+        let result = path::validate_free_space(dir.path().to_str().unwrap(), u64::MAX);
+        match result {
+            ValidationResult::Failure(message) => {
+                assert!(message.contains("Not enough free space"))
+            }
+            ValidationResult::Success(_) => panic!("u64::MAX bytes cannot be free"),
+        }
+        assert!(
+            path::validate_filesystem(dir.path().to_str().unwrap(), &[&mount.fs_type]).is_success()
+        );
+        assert!(
+            !path::validate_filesystem(dir.path().to_str().unwrap(), &["zfs-nope"]).is_success()
+        );
+    }
+
+    #[test]
+    fn test_writable_checks_real_access() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("out.txt");
+        std::fs::write(&file, "x").unwrap();
+
+        assert!(check_writable(&file).is_ok());
+        assert!(check_writable(&dir.path().join("new/nested/file")).is_ok());
+        assert!(check_readable(&file).is_ok());
+        assert!(check_readable(&dir.path().join("missing"))
+            .unwrap_err()
+            .to_string()
+            .contains("does not exist"));
+
+        // A regular file cannot hold new entries
+        let err = check_writable(&file.join("child")).unwrap_err();
+        assert!(err.to_string().contains("is not a directory"));
+
+        // Root bypasses permission bits, so only check them unprivileged
+        // (/proc/self is owned by the effective user)
+        if std::fs::metadata("/proc/self").unwrap().uid() != 0 {
+            let locked = dir.path().join("locked");
+            std::fs::create_dir(&locked).unwrap();
+            std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o500)).unwrap();
+            assert!(!path::validate_writable(locked.to_str().unwrap()).is_success());
+            assert!(check_writable(&locked.join("file"))
+                .unwrap_err()
+                .to_string()
+                .contains("Cannot create"));
+            std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o700)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_preflight_reports_mount_problems() {
+        // Resolve against the fixture table; "/" always exists
+        let report = Preflight::new("/")
+            .writable()
+            .executable()
+            .filesystems(&["btrfs", "xfs"])
+            .mountinfo(&MOUNTINFO.replace(" / / rw,relatime", " / / ro,noexec"))
+            .report();
+        assert!(!report.is_ok());
+        assert_eq!(report.mount.as_ref().unwrap().fs_type, "ext4");
+        assert_eq!(
+            report.failures,
+            vec![
+                "/ (ext4 on /dev/vda1) is mounted read-only",
+                "/ (ext4 on /dev/vda1) is mounted noexec, so programs there cannot run",
+                "ext4 filesystem is not supported here (expected btrfs, xfs)",
+            ]
+        );
+        let err = report.into_result().unwrap_err().to_string();
+        assert!(err.starts_with("Preflight checks failed for /:\n  - / (ext4"));
+
+        let tmpfs_root = "1 0 0:1 / / rw - tmpfs tmpfs rw\n";
+        let report = Preflight::swapfile("/swapfile-test", u64::MAX)
+            .mountinfo(tmpfs_root)
+            .report();
+        assert!(report
+            .failures
+            .contains(&"tmpfs filesystem is not supported here".to_string()));
+        assert!(report.failures.iter().any(|f| f.starts_with("needs ")));
+    }
+
+    #[test]
+    fn test_preflight_passes_for_temp_dir() {
+        // /tmp may be mounted noexec; the target directory is not
+        let dir = TempDir::new_in(env!("CARGO_TARGET_TMPDIR")).unwrap();
+        let report = Preflight::build_dir(dir.path().join("target"), 1)
+            .readable()
+            .check();
+        // The target does not exist yet, so only readability fails
+        let err = report.unwrap_err().to_string();
+        assert!(err.contains("not readable"));
+        assert_eq!(err.lines().count(), 2);
+
+        let report = Preflight::new(dir.path())
+            .writable()
+            .free_space(1)
+            .check()
+            .unwrap();
+        assert!(report.available_bytes.unwrap() >= 1);
+        assert!(report.mount.is_some());
+    }
+}
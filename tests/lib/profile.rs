@@ -0,0 +1,304 @@
+// Tests for profile module
+//
+// This module tests the built-in profiles, profile validation, where the
+// active profile sits among the configuration layers, and profile diffs
+
+use serde_json::json;
+use std::path::PathBuf;
+use tempfile::TempDir;
+use ubuntu_config_scripts::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_config(dir: &TempDir, content: &str) -> PathBuf {
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_builtin_profiles_are_valid() {
+        let profiles = builtin_profiles();
+        let names: Vec<&str> = profiles.keys().map(String::as_str).collect();
+        assert_eq!(names, vec!["laptop", "rust-dev", "streaming"]);
+        for (name, profile) in &profiles {
+            assert!(profile.description.is_some(), "{} has no description", name);
+            assert!(
+                profile.validation_errors().is_empty(),
+                "{}: {}",
+                name,
+                profile.validation_errors()
+            );
+        }
+
+        let config = Config::default();
+        assert_eq!(config.profile, None);
+        assert_eq!(config.profiles.len(), 3);
+        assert!(config.validation_errors().is_empty());
+        assert_eq!(
+            profiles["streaming"].keys()[..2],
+            ["audio.enable_echo_cancellation", "audio.volume_level"]
+        );
+    }
+
+    #[test]
+    fn test_profile_validation() {
+        let profile = Profile::new("Broken")
+            .set("obs.fsp", json!(60))
+            .set("obs.fps", json!("fast"))
+            .set("profile", json!("laptop"));
+        let errors = profile.validation_errors();
+        let paths: Vec<&str> = errors.errors().iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["/obs/fsp", "/obs/fps", "/profile"]);
+        assert_eq!(
+            errors.get("/obs/fsp").unwrap().suggestion.as_deref(),
+            Some("obs.fps")
+        );
+        assert!(errors
+            .get("/obs/fps")
+            .unwrap()
+            .message
+            .contains("invalid type"));
+
+        // Rules are checked once the types fit
+        let profile = Profile::default().set("obs.fps", json!(250));
+        assert_eq!(
+            profile.validation_errors().errors()[0].constraint,
+            Constraint::range(1.0, 240.0)
+        );
+
+        let mut config = Config::default();
+        config
+            .profiles
+            .insert("with space".to_string(), Profile::default());
+        config.profiles.insert(
+            "quiet".to_string(),
+            Profile::default().set("audio.volume_level", json!(101)),
+        );
+        let errors = config.validation_errors();
+        assert!(errors.get("/profiles/with space").is_some());
+        assert!(errors.get("/profiles/quiet/audio/volume_level").is_some());
+    }
+
+    #[test]
+    fn test_profiles_from_files() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "profile = \"recording\"\n\n\
+             [profiles.recording]\n\
+             description = \"Podcast\"\n\
+             audio = { volume_level = 35 }\n\n\
+             [profiles.laptop.obs]\n\
+             fps = 24\n",
+        )
+        .unwrap();
+
+        let config = Config::from_file(path.to_str().unwrap()).unwrap();
+        assert_eq!(config.profile.as_deref(), Some("recording"));
+        // A single file replaces the built-in set; ConfigLoader merges over it
+        assert_eq!(config.profiles.len(), 2);
+        assert_eq!(
+            config.profiles["recording"].description.as_deref(),
+            Some("Podcast")
+        );
+        assert_eq!(config.profiles["laptop"].overlay["obs"], json!({"fps": 24}));
+    }
+
+    #[test]
+    fn test_active_profile_layer() {
+        let system = TempDir::new().unwrap();
+        let user = TempDir::new().unwrap();
+        write_config(
+            &system,
+            "[profiles.streaming.obs]\nfps = 50\n\n[audio]\nvolume_level = 20\n",
+        );
+        let user_file = write_config(&user, "profile = \"streaming\"\n");
+
+        let loader = ConfigLoader::empty()
+            .system_dir(Some(system.path().to_path_buf()))
+            .user_dir(Some(user.path().to_path_buf()));
+        let loaded = loader.load().unwrap();
+
+        // The profile sits above the files, and files can adjust built-in profiles
+        assert_eq!(loaded.config.profile.as_deref(), Some("streaming"));
+        assert_eq!(loaded.source_of("profile"), ConfigSource::User(user_file));
+        assert_eq!(loaded.config.audio.volume_level, Some(80));
+        assert_eq!(loaded.config.obs.fps, 50);
+        assert_eq!(loaded.config.obs.encoder, "nvenc");
+        assert_eq!(
+            loaded.source_of("obs.fps"),
+            ConfigSource::Profile("streaming".to_string())
+        );
+
+        // Environment and command line overrides still win
+        let loaded = loader
+            .clone()
+            .env_vars(env(&[("UCS_OBS__FPS", "25")]))
+            .set("audio.volume_level", "65")
+            .load()
+            .unwrap();
+        assert_eq!(loaded.config.obs.fps, 25);
+        assert_eq!(loaded.config.audio.volume_level, Some(65));
+
+        // UCS_PROFILE and an explicit choice replace the recorded profile
+        let loaded = loader
+            .clone()
+            .env_vars(env(&[("UCS_PROFILE", "laptop")]))
+            .load()
+            .unwrap();
+        assert_eq!(loaded.config.obs.encoder, "vaapi");
+        assert_eq!(
+            loaded.source_of("profile"),
+            ConfigSource::Env("UCS_PROFILE".to_string())
+        );
+
+        let loaded = loader.clone().profile(None).load().unwrap();
+        assert_eq!(loaded.config.profile, None);
+        assert_eq!(loaded.config.audio.volume_level, Some(20));
+    }
+
+    #[test]
+    fn test_unknown_profile_is_an_error() {
+        let err = ConfigLoader::empty()
+            .strict_profile(true)
+            .profile(Some("streamng"))
+            .load()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown profile: streamng (did you mean streaming?)"
+        );
+
+        // Overrides keep the field's type, so only a file can hold a non-string
+        let err = ConfigLoader::empty()
+            .strict_profile(true)
+            .set("profile", "[1]")
+            .load()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Unknown profile: [1]");
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(&path, r#"{"profile": [1]}"#).unwrap();
+        let err = ConfigLoader::empty()
+            .strict_profile(true)
+            .file(Some(path))
+            .load()
+            .unwrap_err();
+        assert!(err.to_string().contains("must be a profile name"));
+
+        // Scripts warn and carry on without the profile
+        let loaded = ConfigLoader::empty()
+            .env_vars([("UCS_PROFILE".to_string(), "streamng".to_string())])
+            .load()
+            .unwrap();
+        assert_eq!(loaded.config.profile.as_deref(), Some("streamng"));
+        assert_eq!(loaded.config.obs.fps, 30);
+    }
+
+    #[test]
+    fn test_diff_between_profiles() {
+        let laptop = ConfigLoader::empty()
+            .profile(Some("laptop"))
+            .load()
+            .unwrap();
+        let streaming = ConfigLoader::empty()
+            .profile(Some("streaming"))
+            .load()
+            .unwrap();
+
+        let changes = diff_values(&laptop.value, &streaming.value);
+        let paths: Vec<&str> = changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "audio.volume_level",
+                "obs.resolution",
+                "obs.fps",
+                "obs.encoder",
+                "swap.swappiness",
+                "sysctl.vm_dirty_writeback_centisecs",
+            ]
+        );
+        assert_eq!(changes[2].from, Some(json!(30)));
+        assert_eq!(changes[2].to, Some(json!(60)));
+
+        let csv = diff_table(&changes, "laptop", "streaming").render(OutputFormat::Csv);
+        assert!(csv.starts_with("Key,laptop,streaming\n"));
+        assert!(csv.contains("obs.fps,30,60\n"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&profiles_table(&streaming.config).render(OutputFormat::Json))
+                .unwrap();
+        assert_eq!(json[2]["Profile"], "streaming");
+        assert_eq!(json[2]["Active"], "*");
+    }
+
+    #[test]
+    fn test_profile_keys_in_config_files() {
+        assert_eq!(
+            typed_value("profiles.streaming.obs.fps", "50").unwrap(),
+            json!(50)
+        );
+        assert_eq!(
+            typed_value("profiles.work.description", "42").unwrap(),
+            json!("42")
+        );
+        assert!(typed_value("profiles.work", "{}").is_err());
+        assert!(typed_value("profiles.work.profile", "laptop").is_err());
+        assert_eq!(
+            typed_value("profiles.work.obs.fsp", "50")
+                .unwrap_err()
+                .to_string(),
+            "Unknown configuration key: obs.fsp (did you mean obs.fps?)"
+        );
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        let mut document = ConfigDocument::open(&path).unwrap();
+        document.assign("profile", "rust-dev").unwrap();
+        document.assign("profiles.work.obs.fps", "48").unwrap();
+        assert!(document.assign("profiles.work.obs.fps", "500").is_err());
+        assert_eq!(
+            document.text(),
+            format!(
+                "version = {}\nprofile = \"rust-dev\"\n\n[profiles.work.obs]\nfps = 48\n",
+                CONFIG_VERSION
+            )
+        );
+    }
+
+    #[test]
+    fn test_sysctl_settings() {
+        let sysctl = SysctlConfig {
+            vm_dirty_ratio: Some(40),
+            fs_inotify_max_user_watches: Some(524288),
+            ..Default::default()
+        };
+        assert_eq!(
+            sysctl.settings(),
+            vec![
+                ("vm.dirty_ratio", "40".to_string()),
+                ("fs.inotify.max_user_watches", "524288".to_string()),
+            ]
+        );
+        assert_eq!(SwapConfig::default().file, "/swapfile");
+
+        let config = ConfigLoader::empty()
+            .profile(Some("rust-dev"))
+            .load()
+            .unwrap()
+            .config;
+        assert_eq!(config.swap.size_mb, Some(16384));
+        assert_eq!(config.sysctl.settings().len(), 2);
+    }
+}
@@ -0,0 +1,166 @@
+// Tests for progress module
+//
+// This module tests progress rendering, rate/ETA estimation and nested bars
+
+use std::time::Duration;
+use ubuntu_config_scripts::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.0 KiB");
+        assert_eq!(format_bytes(1536 * 1024), "1.5 MiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(42)), "42s");
+        assert_eq!(format_duration(Duration::from_secs(185)), "3m05s");
+        assert_eq!(format_duration(Duration::from_secs(3720)), "1h02m");
+    }
+
+    #[test]
+    fn test_rate_and_eta() {
+        assert_eq!(rate_for(100, Duration::from_secs(10)), 10.0);
+        assert_eq!(rate_for(100, Duration::ZERO), 0.0);
+
+        assert_eq!(
+            eta_for(25, 100, Duration::from_secs(5)),
+            Some(Duration::from_secs(15))
+        );
+        assert_eq!(
+            eta_for(100, 100, Duration::from_secs(5)),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(eta_for(0, 100, Duration::from_secs(5)), None);
+        assert_eq!(eta_for(10, 0, Duration::from_secs(5)), None);
+    }
+
+    #[test]
+    fn test_zero_total_has_no_percentage() {
+        let mut state = ProgressState::new(0, "Scanning", ProgressUnit::Items);
+        state.current = 5;
+        assert_eq!(state.fraction(), None);
+        assert_eq!(state.percent(), None);
+        assert!(render_bar(&state, 80).contains("?%"));
+        assert!(!render_log_line(&state).contains('%'));
+    }
+
+    #[test]
+    fn test_render_bar_respects_width() {
+        let mut state = ProgressState::new(
+            200,
+            "Downloading a rather long file name",
+            ProgressUnit::Bytes,
+        );
+        state.current = 100;
+        let line = render_bar(&state, 60);
+        assert!(line.chars().count() <= 60);
+        assert!(line.contains("50%"));
+        assert!(line.contains("100 B/200 B"));
+    }
+
+    #[test]
+    fn test_render_bar_indents_nested_bars() {
+        let mut state = ProgressState::new(10, "child", ProgressUnit::Items);
+        state.depth = 2;
+        assert!(render_bar(&state, 80).starts_with("    child"));
+    }
+
+    #[test]
+    fn test_render_log_line() {
+        let mut state = ProgressState::new(10, "Cleaning caches", ProgressUnit::Items);
+        state.current = 4;
+        let line = render_log_line(&state);
+        assert!(line.contains("Cleaning caches"));
+        assert!(line.contains("(4/10)"));
+        assert!(line.contains("40%"));
+    }
+
+    #[test]
+    fn test_progress_bar_position_and_total() {
+        let multi = MultiProgress::with_mode(ProgressMode::Hidden);
+        let bar = multi.add_bytes(0, "download");
+        bar.inc(512);
+        bar.set_total(2048);
+        bar.inc(512);
+
+        let state = bar.state().unwrap();
+        assert_eq!(state.current, 1024);
+        assert_eq!(state.total, 2048);
+        assert_eq!(state.unit, ProgressUnit::Bytes);
+        assert_eq!(state.percent(), Some(50));
+    }
+
+    #[test]
+    fn test_nested_bars() {
+        let multi = MultiProgress::with_mode(ProgressMode::Hidden);
+        let parent = multi.add(2, "cleanup");
+        let child = parent.child(100, "apt cache", ProgressUnit::Bytes);
+        assert_eq!(child.state().unwrap().depth, 1);
+
+        let grandchild = child.child(3, "archives", ProgressUnit::Items);
+        assert_eq!(grandchild.state().unwrap().depth, 2);
+
+        grandchild.finish();
+        assert!(grandchild.state().is_none());
+        child.finish();
+        parent.inc(1);
+        assert_eq!(parent.position(), 1);
+    }
+
+    #[test]
+    fn test_finish_stops_updates() {
+        let multi = MultiProgress::with_mode(ProgressMode::Log).log_interval(Duration::ZERO);
+        let bar = multi.add(10, "items");
+        bar.set_position(3);
+        assert_eq!(bar.position(), 3);
+        bar.finish();
+        bar.inc(1);
+        // Finished bars leave the group once their completion line is logged
+        assert!(bar.state().is_none());
+    }
+
+    #[test]
+    fn test_concurrent_bars_across_threads() {
+        let multi = MultiProgress::with_mode(ProgressMode::Hidden);
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let multi = multi.clone();
+                std::thread::spawn(move || {
+                    let bar = multi.add(100, &format!("worker {}", i));
+                    for _ in 0..100 {
+                        bar.inc(1);
+                    }
+                    bar.position()
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), 100);
+        }
+    }
+
+    #[test]
+    fn test_progress_tracker_zero_total() {
+        let mut tracker = ProgressTracker::new(0, "Nothing to do");
+        tracker.update(0);
+        tracker.increment();
+        tracker.finish();
+    }
+
+    #[test]
+    fn test_progress_tracker_bytes() {
+        let mut tracker = ProgressTracker::bytes(4096, "Scanning disk");
+        tracker.advance(1024);
+        assert_eq!(tracker.bar().position(), 1024);
+        tracker.finish();
+    }
+}
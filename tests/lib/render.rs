@@ -0,0 +1,133 @@
+// Tests for render module
+//
+// This module tests display-width aware tables and the alternative output formats
+
+use std::collections::HashMap;
+use ubuntu_config_scripts::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn devices() -> Table {
+        Table::new(vec![
+            Column::new("Device"),
+            Column::new("Channels").align(Align::Right),
+        ])
+        .rows(vec![
+            vec!["Scarlett 2i2 USB – Analog".to_string(), "2".to_string()],
+            vec!["Built-in Audio".to_string(), "8".to_string()],
+        ])
+    }
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("–"), 1);
+        assert_eq!(display_width("音声"), 4);
+        assert_eq!(display_width(""), 0);
+    }
+
+    #[test]
+    fn test_box_table_aligns_utf8_cells() {
+        let table = devices().render(OutputFormat::Table);
+        let widths: Vec<usize> = table.lines().map(display_width).collect();
+        assert!(widths.iter().all(|w| *w == widths[0]), "{}", table);
+        assert!(table.contains("Scarlett 2i2 USB – Analog"));
+    }
+
+    #[test]
+    fn test_box_table_right_alignment() {
+        let table = devices().render(OutputFormat::Table);
+        assert!(table.contains("│        2 │"), "{}", table);
+    }
+
+    #[test]
+    fn test_box_table_truncates_to_max_width() {
+        let table = devices().max_width(24).render(OutputFormat::Table);
+        for line in table.lines() {
+            assert!(display_width(line) <= 24, "{}", table);
+        }
+        assert!(table.contains('…'));
+    }
+
+    #[test]
+    fn test_box_table_wraps_columns() {
+        let table = Table::new(vec![Column::new("Note").max_width(10).wrap()])
+            .rows(vec![vec!["echo cancellation enabled".to_string()]])
+            .render(OutputFormat::Table);
+        assert!(table.contains("│ echo       │"), "{}", table);
+        assert!(table.contains("│ cancellati │"), "{}", table);
+    }
+
+    #[test]
+    fn test_missing_cells_render_empty() {
+        let table = Table::from_headers(&["A", "B"])
+            .rows(vec![vec!["only".to_string()]])
+            .render(OutputFormat::Csv);
+        assert_eq!(table, "A,B\nonly,\n");
+    }
+
+    #[test]
+    fn test_markdown_output() {
+        let table = devices().render(OutputFormat::Markdown);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "| Device | Channels |");
+        assert_eq!(lines[1], "| --- | ---: |");
+        assert_eq!(lines[2], "| Scarlett 2i2 USB – Analog | 2 |");
+
+        let escaped = Table::from_headers(&["Cmd"])
+            .rows(vec![vec!["a | b".to_string()]])
+            .render(OutputFormat::Markdown);
+        assert!(escaped.contains("a \\| b"));
+    }
+
+    #[test]
+    fn test_csv_quoting() {
+        let table = Table::from_headers(&["Name", "Value"])
+            .rows(vec![vec!["a,b".to_string(), "say \"hi\"".to_string()]])
+            .render(OutputFormat::Csv);
+        assert_eq!(table, "Name,Value\n\"a,b\",\"say \"\"hi\"\"\"\n");
+    }
+
+    #[test]
+    fn test_json_output_keeps_column_order() {
+        let json = devices().render(OutputFormat::Json);
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed[0]["Device"], "Scarlett 2i2 USB – Analog");
+        assert_eq!(parsed[1]["Channels"], "8");
+        assert!(json.find("\"Device\"").unwrap() < json.find("\"Channels\"").unwrap());
+
+        assert_eq!(Table::from_headers(&["A"]).render(OutputFormat::Json), "[]");
+    }
+
+    #[test]
+    fn test_truncate_and_wrap_helpers() {
+        assert_eq!(truncate_to_width("abcdef", 4), "abc…");
+        assert_eq!(truncate_to_width("abc", 4), "abc");
+        assert_eq!(truncate_to_width("音声入力", 5), "音声…");
+        assert_eq!(wrap_to_width("one two three", 7), vec!["one two", "three"]);
+        assert_eq!(wrap_to_width("abcdefgh", 3), vec!["abc", "def", "gh"]);
+        assert_eq!(pad_to_width("ab", 6, Align::Center), "  ab  ");
+    }
+
+    #[test]
+    fn test_output_format_selection() {
+        let mut args = HashMap::new();
+        assert_eq!(
+            Args::from_hashmap(args.clone()).output_format().unwrap(),
+            OutputFormat::Table
+        );
+
+        args.insert("format".to_string(), "md".to_string());
+        assert_eq!(
+            Args::from_hashmap(args.clone()).output_format().unwrap(),
+            OutputFormat::Markdown
+        );
+
+        args.insert("format".to_string(), "yaml".to_string());
+        let parsed = Args::from_hashmap(args);
+        assert!(parsed.validate().is_err());
+        assert!(parsed.output_format().is_err());
+    }
+}
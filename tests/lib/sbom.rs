@@ -0,0 +1,305 @@
+// Tests for sbom module
+//
+// This module tests package URLs, the CycloneDX and SPDX documents built
+// from a lockfile (components, licenses, checksums, relationships) and
+// leaving out crates only needed by dev-dependencies or other platforms
+
+use serde_json::{json, Value};
+use std::path::Path;
+use tempfile::TempDir;
+use ubuntu_config_scripts::*;
+
+const CARGO_LOCK: &str = r#"version = 4
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["serde_json", "proptest", "fork", "winapi"]
+
+[[package]]
+name = "serde_json"
+version = "1.0.140"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "20068b6e96dc6c9bd23e01df8827e6c7e1f2fddd43c21810382803c136b99373"
+
+[[package]]
+name = "proptest"
+version = "1.6.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "14cae93065090804185d3b75f0bf93b8eeda30c7a9b4a33d3bdb3988d6229e50"
+dependencies = ["rand"]
+
+[[package]]
+name = "rand"
+version = "0.8.5"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "winapi"
+version = "0.3.9"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "fork"
+version = "0.2.0"
+source = "git+https://github.com/example/fork#0123abcd"
+"#;
+
+const MANIFEST_TOML: &str = r#"[package]
+name = "app"
+version = "0.1.0"
+license = "MIT/Apache-2.0"
+
+[dependencies]
+serde_json = "1.0"
+forked = { package = "fork", git = "https://github.com/example/fork" }
+
+[target.'cfg(windows)'.dependencies]
+winapi = "0.3"
+
+[dev-dependencies]
+proptest = "1.0"
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources(dir: &Path) -> (DependencyGraph, LicenseSources) {
+        std::fs::write(dir.join(LOCKFILE), CARGO_LOCK).unwrap();
+        std::fs::write(dir.join(MANIFEST), MANIFEST_TOML).unwrap();
+        let registry = dir.join("registry");
+        let crate_dir = registry.join("serde_json-1.0.140");
+        std::fs::create_dir_all(&crate_dir).unwrap();
+        std::fs::write(
+            crate_dir.join("Cargo.toml"),
+            "[package]\nname = \"serde_json\"\nlicense = \"MIT OR Apache-2.0\"\n",
+        )
+        .unwrap();
+
+        let graph = DependencyGraph::load(dir).unwrap();
+        let sources = LicenseSources::new(dir)
+            .unwrap()
+            .registry_dirs(vec![registry]);
+        (graph, sources)
+    }
+
+    fn project(dir: &Path) -> Sbom {
+        let (graph, sources) = sources(dir);
+        Sbom::for_target(&graph, &sources, "x86_64-unknown-linux-gnu")
+            .unwrap()
+            .serial_number("3e671687-395b-41f5-a30f-a58921a69b79")
+            .timestamp("2026-01-02T03:04:05Z")
+    }
+
+    fn system_package() -> SystemPackage {
+        SystemPackage {
+            name: "git".to_string(),
+            version: "1:2.43.0-1ubuntu7".to_string(),
+            architecture: "amd64".to_string(),
+            vendor: "ubuntu".to_string(),
+            distro: Some("ubuntu-24.04".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_purls_and_formats() {
+        let crates_io = PackageSource::parse(Some(CRATES_IO_SOURCE));
+        assert_eq!(
+            cargo_purl("serde_json", "1.0.140", &crates_io),
+            "pkg:cargo/serde_json@1.0.140"
+        );
+        assert_eq!(
+            cargo_purl("app", "0.1.0+local", &PackageSource::Path),
+            "pkg:cargo/app@0.1.0%2Blocal"
+        );
+        assert_eq!(
+            cargo_purl(
+                "fork",
+                "0.2.0",
+                &PackageSource::parse(Some("git+https://github.com/example/fork#0123abcd"))
+            ),
+            "pkg:cargo/fork@0.2.0?vcs_url=git%2Bhttps%3A%2F%2Fgithub.com%2Fexample%2Ffork%400123abcd"
+        );
+        assert_eq!(
+            cargo_purl(
+                "internal",
+                "1.0.0",
+                &PackageSource::parse(Some("sparse+https://index.example.com/"))
+            ),
+            "pkg:cargo/internal@1.0.0?repository_url=https%3A%2F%2Findex.example.com%2F"
+        );
+        assert_eq!(
+            system_package().purl(),
+            "pkg:deb/ubuntu/git@1%3A2.43.0-1ubuntu7?arch=amd64&distro=ubuntu-24.04"
+        );
+
+        assert_eq!("cdx".parse::<SbomFormat>().unwrap(), SbomFormat::CycloneDx);
+        assert_eq!(SbomFormat::Spdx.file_name(), "sbom.spdx.json");
+        assert!("swid".parse::<SbomFormat>().is_err());
+    }
+
+    #[test]
+    fn test_cyclonedx_document() {
+        let dir = TempDir::new().unwrap();
+        let sbom = project(dir.path());
+
+        // proptest and rand are only reached through [dev-dependencies],
+        // winapi only on Windows
+        let names: Vec<&str> = sbom.components.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["app", "fork", "serde_json"]);
+        assert_eq!(
+            sbom.components[0].license.as_deref(),
+            Some("MIT OR Apache-2.0")
+        );
+
+        let bom = sbom.to_cyclonedx();
+        assert_eq!(bom["specVersion"], "1.5");
+        assert_eq!(
+            bom["serialNumber"],
+            "urn:uuid:3e671687-395b-41f5-a30f-a58921a69b79"
+        );
+        assert_eq!(bom["metadata"]["timestamp"], "2026-01-02T03:04:05Z");
+        assert_eq!(bom["metadata"]["component"]["type"], "application");
+        assert_eq!(
+            bom["components"][1],
+            json!({
+                "type": "library",
+                "bom-ref": "pkg:cargo/serde_json@1.0.140",
+                "name": "serde_json",
+                "version": "1.0.140",
+                "purl": "pkg:cargo/serde_json@1.0.140",
+                "licenses": [{ "expression": "MIT OR Apache-2.0" }],
+                "hashes": [{
+                    "alg": "SHA-256",
+                    "content": "20068b6e96dc6c9bd23e01df8827e6c7e1f2fddd43c21810382803c136b99373"
+                }],
+            })
+        );
+        // Git crates have neither a license in the cache nor a checksum
+        assert!(bom["components"][0].get("hashes").is_none());
+        assert_eq!(
+            bom["dependencies"][0]["dependsOn"],
+            json!([
+                bom["components"][0]["bom-ref"],
+                "pkg:cargo/serde_json@1.0.140"
+            ])
+        );
+    }
+
+    #[test]
+    fn test_spdx_document() {
+        let dir = TempDir::new().unwrap();
+        let doc = project(dir.path()).to_spdx();
+
+        assert_eq!(doc["spdxVersion"], "SPDX-2.3");
+        assert_eq!(doc["dataLicense"], "CC0-1.0");
+        assert_eq!(doc["creationInfo"]["created"], "2026-01-02T03:04:05Z");
+        assert!(doc["documentNamespace"]
+            .as_str()
+            .unwrap()
+            .ends_with("/app-0.1.0-3e671687-395b-41f5-a30f-a58921a69b79"));
+
+        let packages = doc["packages"].as_array().unwrap();
+        assert_eq!(packages[0]["SPDXID"], "SPDXRef-Package-app-0.1.0");
+        assert_eq!(packages[0]["primaryPackagePurpose"], "APPLICATION");
+        assert_eq!(packages[0]["downloadLocation"], "NOASSERTION");
+        assert_eq!(
+            packages[1]["downloadLocation"],
+            "git+https://github.com/example/fork@0123abcd"
+        );
+        assert_eq!(packages[1]["licenseDeclared"], "NOASSERTION");
+        assert_eq!(packages[2]["SPDXID"], "SPDXRef-Package-serde-json-1.0.140");
+        assert_eq!(packages[2]["licenseDeclared"], "MIT OR Apache-2.0");
+        assert_eq!(packages[2]["checksums"][0]["algorithm"], "SHA256");
+        assert_eq!(
+            packages[2]["externalRefs"][0]["referenceLocator"],
+            "pkg:cargo/serde_json@1.0.140"
+        );
+
+        let relationships: Vec<(&str, &str, &str)> = doc["relationships"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| {
+                (
+                    r["spdxElementId"].as_str().unwrap(),
+                    r["relationshipType"].as_str().unwrap(),
+                    r["relatedSpdxElement"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            relationships,
+            vec![
+                ("SPDXRef-DOCUMENT", "DESCRIBES", "SPDXRef-Package-app-0.1.0"),
+                (
+                    "SPDXRef-Package-app-0.1.0",
+                    "DEPENDS_ON",
+                    "SPDXRef-Package-fork-0.2.0"
+                ),
+                (
+                    "SPDXRef-Package-app-0.1.0",
+                    "DEPENDS_ON",
+                    "SPDXRef-Package-serde-json-1.0.140"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_target_specific_dependencies() {
+        let dir = TempDir::new().unwrap();
+        project(dir.path());
+        let (graph, sources) = sources(dir.path());
+
+        let windows = Sbom::for_target(&graph, &sources, "x86_64-pc-windows-msvc").unwrap();
+        let names: Vec<&str> = windows.components.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["app", "fork", "serde_json", "winapi"]);
+        assert_eq!(windows.components[0].dependencies, vec![1, 2, 3]);
+
+        assert!(Sbom::for_target(&graph, &sources, "x86_64-unknown-nowhere").is_err());
+
+        // This repository's own SBOM has no Windows-only crates on Linux
+        if BUILD_TARGET.contains("linux") {
+            let sbom = generate_sbom(".", false).unwrap();
+            assert!(!sbom
+                .components
+                .iter()
+                .any(|c| c.name.starts_with("windows")));
+        }
+    }
+
+    #[test]
+    fn test_system_packages_and_files() {
+        let dir = TempDir::new().unwrap();
+        let sbom = project(dir.path()).system_packages(&[system_package()]);
+        assert_eq!(sbom.components.len(), 4);
+        assert_eq!(sbom.components[0].dependencies, vec![1, 2, 3]);
+
+        let paths = sbom.write_all(dir.path()).unwrap();
+        assert_eq!(paths.len(), 2);
+        let bom: Value =
+            serde_json::from_str(&std::fs::read_to_string(&paths[0]).unwrap()).unwrap();
+        assert_eq!(bom["bomFormat"], "CycloneDX");
+        assert_eq!(bom["components"][2]["type"], "application");
+        assert!(bom["components"][2]["purl"]
+            .as_str()
+            .unwrap()
+            .starts_with("pkg:deb/ubuntu/git@"));
+        let doc: Value =
+            serde_json::from_str(&std::fs::read_to_string(&paths[1]).unwrap()).unwrap();
+        assert_eq!(
+            doc["packages"][3]["SPDXID"],
+            "SPDXRef-Package-git-1-2.43.0-1ubuntu7"
+        );
+
+        assert!(installed_system_packages(&["ucs-no-such-command"]).is_empty());
+
+        // This repository's own SBOM leaves out its test-only crates
+        let sbom = generate_sbom(".", false).unwrap();
+        assert_eq!(sbom.name, "ubuntu-config-scripts");
+        assert!(sbom.components.iter().any(|c| c.name == "serde_json"));
+        assert!(!sbom.components.iter().any(|c| c.name == "proptest"));
+    }
+}
@@ -2,9 +2,37 @@
 //
 // This module tests configuration validation and type safety
 
-use tempfile::NamedTempFile;
+use serde::Serialize;
+use std::path::Path;
+use tempfile::{NamedTempFile, TempDir};
 use ubuntu_config_scripts::*;
 
+#[derive(Serialize, Validate)]
+struct Mount {
+    #[validate(path_exists)]
+    source: String,
+    #[validate(regex = "^/")]
+    target: String,
+}
+
+#[derive(Serialize, Validate)]
+struct Runner {
+    #[validate(one_of("apt", "snap"))]
+    installer: String,
+    #[validate(range(1, 65535))]
+    port: u32,
+    #[validate(range(min = 1))]
+    workers: Option<u8>,
+    #[validate(regex = "^[a-z0-9-]+$")]
+    name: String,
+    #[validate(one_of("x86_64", "aarch64"))]
+    arches: Vec<String>,
+    #[validate(nested)]
+    mounts: Vec<Mount>,
+    #[validate(nested)]
+    primary: Option<Mount>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -180,6 +208,479 @@ mod tests {
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn test_config_validation_collects_all_errors() {
+        let mut config = Config::default();
+        config.system.log_level = "inf".to_string();
+        config.audio.volume_level = Some(150);
+        config.dev.build_mode = "relase".to_string();
+        config.dev.optimization_level = 7;
+
+        let errors = config.validation_errors();
+        assert_eq!(errors.len(), 4);
+
+        let log_level = errors.get("/system/log_level").unwrap();
+        assert_eq!(log_level.value, serde_json::json!("inf"));
+        assert_eq!(log_level.suggestion.as_deref(), Some("info"));
+        assert_eq!(
+            log_level.constraint,
+            Constraint::OneOf {
+                allowed: LOG_LEVELS.iter().map(|l| l.to_string()).collect()
+            }
+        );
+
+        let volume = errors.get("/audio/volume_level").unwrap();
+        assert_eq!(volume.value, serde_json::json!(150));
+        assert_eq!(
+            volume.message,
+            "150 is out of range, expected between 0 and 100"
+        );
+        assert_eq!(
+            volume.constraint,
+            Constraint::Range {
+                min: Some(0.0),
+                max: Some(100.0)
+            }
+        );
+        assert_eq!(volume.suggestion, None);
+
+        assert_eq!(
+            errors.get("/dev/build_mode").unwrap().suggestion.as_deref(),
+            Some("release")
+        );
+        assert!(errors.get("/dev/optimization_level").is_some());
+
+        let err = config.validate().unwrap_err();
+        assert_eq!(err.downcast_ref::<ValidationErrors>(), Some(&errors));
+
+        // Scripts fail only on their own section
+        let (dev, others) = errors.partition_at("/dev");
+        assert_eq!(dev.len(), 2);
+        assert_eq!(others.len(), 2);
+        assert!(others.get("/audio/volume_level").is_some());
+        let (devices, _) = config.validation_errors().partition_at("/de");
+        assert!(devices.is_empty());
+    }
+
+    #[test]
+    fn test_validation_errors_render_as_text_and_json() {
+        let mut config = Config::default();
+        config.system.log_level = "warning".to_string();
+        config.dev.build_mode = "xyz".to_string();
+        let errors = config.validation_errors();
+
+        assert_eq!(
+            errors.to_string(),
+            "2 validation errors:\n  \
+             - /system/log_level: invalid value \"warning\", expected one of: debug, info, warn, error (did you mean \"warn\"?)\n  \
+             - /dev/build_mode: invalid value \"xyz\", expected one of: debug, release"
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&errors.to_json().unwrap()).unwrap();
+        assert_eq!(json[0]["path"], "/system/log_level");
+        assert_eq!(json[0]["constraint"]["kind"], "one_of");
+        assert_eq!(json[0]["suggestion"], "warn");
+        assert_eq!(json[1]["value"], "xyz");
+        assert!(json[1].get("suggestion").is_none());
+    }
+
+    #[test]
+    fn test_suggest_closest_value() {
+        assert_eq!(suggest("DEBG", &LOG_LEVELS).as_deref(), Some("debug"));
+        assert_eq!(suggest("eror", &LOG_LEVELS).as_deref(), Some("error"));
+        assert_eq!(suggest("verbose", &LOG_LEVELS), None);
+    }
+
+    #[test]
+    fn test_json_schema_draft_and_descriptions() {
+        let schema = Config::json_schema();
+        assert_eq!(
+            schema["$schema"],
+            "https://json-schema.org/draft/2020-12/schema"
+        );
+        let system = &schema["properties"]["system"];
+        assert_eq!(system["description"], "Settings for system scripts");
+        assert_eq!(
+            system["properties"]["log_level"]["description"],
+            "Minimum level of log messages"
+        );
+        assert!(schema.get("required").is_none());
+    }
+
+    #[test]
+    fn test_json_schema_defaults_enums_and_ranges() {
+        let schema = Config::json_schema();
+        let props = |section: &str| schema["properties"][section]["properties"].clone();
+
+        assert_eq!(props("system")["log_level"]["default"], "info");
+        assert_eq!(
+            props("system")["log_level"]["enum"],
+            serde_json::json!(LOG_LEVELS)
+        );
+        assert_eq!(
+            props("dev")["build_mode"]["enum"],
+            serde_json::json!(BUILD_MODES)
+        );
+        assert_eq!(props("audio")["volume_level"]["default"], 70);
+        assert_eq!(props("audio")["volume_level"]["minimum"], 0);
+        assert_eq!(props("audio")["volume_level"]["maximum"], 100);
+        assert_eq!(props("dev")["optimization_level"]["maximum"], 3);
+        assert_eq!(
+            props("dev")["target_arch"]["default"],
+            serde_json::json!(["x86_64"])
+        );
+    }
+
+    #[test]
+    fn test_json_schema_matches_validation_rules() {
+        let schema = Config::json_schema();
+        for rule in config_rules() {
+            let pointer: String = rule
+                .path
+                .split('/')
+                .skip(1)
+                .map(|s| format!("/properties/{}", s))
+                .collect();
+            let field = schema.pointer(&pointer).unwrap_or_else(|| {
+                panic!("{} missing from schema", rule.path);
+            });
+            match rule.constraint {
+                Constraint::OneOf { ref allowed } => {
+                    assert_eq!(field["enum"], serde_json::json!(allowed))
+                }
+                Constraint::Range { max, .. } => {
+                    assert_eq!(field["maximum"].as_f64(), max)
+                }
+                Constraint::Pattern { ref pattern } => {
+                    let target = if field["type"] == "array" {
+                        &field["items"]
+                    } else {
+                        field
+                    };
+                    assert_eq!(target["pattern"], serde_json::json!(pattern))
+                }
+                Constraint::PathExists => {}
+                ref other => panic!("no schema check for {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_partial_config_file_uses_defaults() {
+        let config = Config::from_json(r#"{"audio": {"volume_level": 30}}"#).unwrap();
+        assert_eq!(config.audio.volume_level, Some(30));
+        assert!(config.audio.enable_echo_cancellation);
+        assert_eq!(config.dev.build_mode, "release");
+    }
+
+    fn runner_shape() -> Shape {
+        Shape::object()
+            .required(
+                "runner",
+                Shape::object()
+                    .required("name", Shape::string().min_length(1))
+                    .optional("labels", Shape::array(Shape::string()).max_length(3))
+                    .optional("port", Shape::integer().min(1.0).max(65535.0))
+                    .deny_additional(),
+            )
+            .optional("mode", Shape::enumeration(&["apt", "snap"]))
+            .optional(
+                "proxy",
+                Shape::one_of(vec![
+                    Shape::string().pattern("^https?://").unwrap(),
+                    Shape::object().required("url", Shape::string()),
+                ])
+                .nullable(),
+            )
+    }
+
+    #[test]
+    fn test_shape_accepts_valid_document() {
+        let document = serde_json::json!({
+            "runner": {"name": "ci-1", "labels": ["self-hosted", "Linux"], "port": 8080},
+            "mode": "apt",
+            "proxy": null,
+            "extra": {"anything": true},
+        });
+        let errors = runner_shape().validate(&document);
+        assert!(errors.is_empty(), "{}", errors);
+    }
+
+    #[test]
+    fn test_shape_reports_every_error_with_path() {
+        let document = serde_json::json!({
+            "runner": {"labels": ["ok", 5, "a", "b"], "port": 0, "nmae": "x"},
+            "mode": "snapp",
+        });
+        let errors = runner_shape().validate(&document);
+        let paths: Vec<&str> = errors.errors().iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/runner/name",
+                "/runner/labels",
+                "/runner/labels/1",
+                "/runner/port",
+                "/runner/nmae",
+                "/mode",
+            ]
+        );
+
+        assert_eq!(
+            errors.get("/runner/name").unwrap().constraint,
+            Constraint::Required
+        );
+        assert_eq!(
+            errors.get("/runner/labels/1").unwrap().message,
+            "expected string, found integer"
+        );
+        let unknown = errors.get("/runner/nmae").unwrap();
+        assert_eq!(unknown.constraint, Constraint::NoAdditionalProperties);
+        assert_eq!(unknown.suggestion.as_deref(), Some("name"));
+        assert_eq!(
+            errors.get("/mode").unwrap().suggestion.as_deref(),
+            Some("snap")
+        );
+    }
+
+    #[test]
+    fn test_shape_one_of() {
+        let shape = runner_shape();
+        let base = |proxy: serde_json::Value| serde_json::json!({"runner": {"name": "ci"}, "proxy": proxy});
+
+        assert!(shape.matches(&base(serde_json::json!("http://proxy:3128"))));
+        assert!(shape.matches(&base(serde_json::json!({"url": "http://proxy"}))));
+
+        // Only the string alternative accepts a string, so its error is reported
+        let errors = shape.validate(&base(serde_json::json!("proxy:3128")));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors.get("/proxy").unwrap().constraint,
+            Constraint::Pattern {
+                pattern: "^https?://".to_string()
+            }
+        );
+
+        let errors = shape.validate(&base(serde_json::json!(3128)));
+        let error = errors.get("/proxy").unwrap();
+        assert_eq!(
+            error.constraint,
+            Constraint::Union {
+                alternatives: vec!["string".to_string(), "object".to_string()]
+            }
+        );
+        assert_eq!(
+            error.message,
+            "integer does not match any alternative, expected exactly one of: string, object"
+        );
+    }
+
+    #[test]
+    fn test_shape_one_of_rejects_ambiguous_match() {
+        let shape = Shape::one_of(vec![Shape::number(), Shape::integer()]);
+        assert!(shape.matches(&serde_json::json!(1.5)));
+        let errors = shape.validate(&serde_json::json!(2));
+        assert!(errors.errors()[0]
+            .message
+            .starts_with("value matches 2 alternatives"));
+    }
+
+    #[test]
+    fn test_shape_any_of_and_additional() {
+        let shape =
+            Shape::object().additional(Shape::any_of(vec![Shape::string(), Shape::boolean()]));
+        assert!(shape.matches(&serde_json::json!({"a": "x", "b": true})));
+
+        let errors = shape.validate(&serde_json::json!({"a/b": 1}));
+        assert_eq!(errors.errors()[0].path, "/a~1b");
+        assert_eq!(shape.type_name(), "object");
+    }
+
+    #[test]
+    fn test_shape_errors_render_as_json() {
+        let shape = Shape::object().required("volume", Shape::integer().max(100.0));
+        let errors = shape.validate(&serde_json::json!({"volume": 150}));
+        let json: serde_json::Value = serde_json::from_str(&errors.to_json().unwrap()).unwrap();
+        assert_eq!(json[0]["path"], "/volume");
+        assert_eq!(json[0]["value"], 150);
+        assert_eq!(json[0]["constraint"]["kind"], "range");
+        assert!(Shape::string().pattern("(").is_err());
+    }
+
+    #[test]
+    fn test_shape_pattern_needs_string_shape() {
+        let err = Shape::integer().pattern("^[0-9]+$").unwrap_err();
+        assert!(err.to_string().contains("needs a string shape, not integer"));
+        assert!(Shape::object().pattern(".*").is_err());
+
+        let shape = Shape::string().pattern("^[a-z]+$").unwrap();
+        let errors = shape.validate(&serde_json::json!("Abc"));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors.errors()[0].message, "\"Abc\" does not match /^[a-z]+$/");
+        assert!(shape.matches(&serde_json::json!("abc")));
+    }
+
+    fn runner(dir: &TempDir) -> Runner {
+        let mount = || Mount {
+            source: dir.path().to_string_lossy().to_string(),
+            target: "/mnt/work".to_string(),
+        };
+        Runner {
+            installer: "apt".to_string(),
+            port: 8080,
+            workers: None,
+            name: "ci-runner-1".to_string(),
+            arches: vec!["x86_64".to_string()],
+            mounts: vec![mount()],
+            primary: Some(mount()),
+        }
+    }
+
+    #[test]
+    fn test_derive_validate_accepts_valid_struct() {
+        let dir = TempDir::new().unwrap();
+        assert!(Validate::validate(&runner(&dir)).is_ok());
+    }
+
+    #[test]
+    fn test_derive_validate_reports_field_paths() {
+        let dir = TempDir::new().unwrap();
+        let mut runner = runner(&dir);
+        runner.installer = "snapp".to_string();
+        runner.port = 0;
+        runner.workers = Some(0);
+        runner.name = "CI Runner".to_string();
+        runner.arches.push("armv7".to_string());
+        runner.mounts.push(Mount {
+            source: "/definitely/not/here".to_string(),
+            target: "relative".to_string(),
+        });
+        runner.primary.as_mut().unwrap().target = "mnt".to_string();
+
+        let errors = Validate::validate(&runner).unwrap_err();
+        let paths: Vec<&str> = errors.errors().iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/installer",
+                "/port",
+                "/workers",
+                "/name",
+                "/arches/1",
+                "/mounts/1/source",
+                "/mounts/1/target",
+                "/primary/target",
+            ]
+        );
+        assert_eq!(
+            errors.get("/installer").unwrap().suggestion.as_deref(),
+            Some("snap")
+        );
+        assert_eq!(
+            errors.get("/port").unwrap().constraint,
+            Constraint::Range {
+                min: Some(1.0),
+                max: Some(65535.0)
+            }
+        );
+        assert_eq!(
+            errors.get("/mounts/1/source").unwrap().constraint,
+            Constraint::PathExists
+        );
+    }
+
+    #[test]
+    fn test_derive_field_rules_include_nested_paths() {
+        let paths: Vec<String> = Config::field_rules().into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/system/log_level",
+                "/audio/volume_level",
+                "/dev/build_mode",
+                "/dev/optimization_level",
+                "/cleanup/older_than_days",
+                "/time/timezone",
+                "/time/ntp_servers",
+                "/obs/resolution",
+                "/obs/fps",
+                "/obs/encoder",
+                "/nvidia/target_version",
+                "/pipewire_monitor/check_interval_secs",
+                "/pipewire_monitor/restart_delay_secs",
+                "/runner/url",
+                "/swap/swappiness",
+                "/sysctl/vm_dirty_ratio",
+            ]
+        );
+        assert!(Runner::field_rules()
+            .iter()
+            .all(|r| !r.path.starts_with("/mounts")));
+    }
+
+    #[test]
+    fn test_script_section_defaults() {
+        let config = Config::from_json(r#"{"obs": {"fps": 60}}"#).unwrap();
+        assert_eq!(config.obs.fps, 60);
+        assert_eq!(config.obs.resolution, "1920x1080");
+        assert_eq!(config.cleanup.older_than_days, 30);
+        assert_eq!(config.time.ntp_servers, vec!["ntp.ubuntu.com"]);
+        assert_eq!(config.nvidia.target_version, None);
+        assert_eq!(config.pipewire_monitor.check_interval_secs, 30);
+        assert!(config.kde.restart_plasmashell);
+        assert_eq!(config.deploy.install_dir, "~/.local/bin");
+        assert!(config.extra.is_empty());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_script_section_validation() {
+        let config = Config::from_json(
+            r#"{
+                "time": {"timezone": "Europe/Berlin", "ntp_servers": ["ntp.ubuntu.com", "bad host"]},
+                "obs": {"resolution": "1080p", "fps": 0, "encoder": "nvenk"},
+                "nvidia": {"target_version": "latest"},
+                "pipewire_monitor": {"check_interval_secs": 0}
+            }"#,
+        )
+        .unwrap();
+        let errors = config.validation_errors();
+        let paths: Vec<&str> = errors.errors().iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/time/ntp_servers/1",
+                "/obs/resolution",
+                "/obs/fps",
+                "/obs/encoder",
+                "/nvidia/target_version",
+                "/pipewire_monitor/check_interval_secs",
+            ]
+        );
+        assert_eq!(
+            errors.get("/obs/encoder").unwrap().suggestion.as_deref(),
+            Some("nvenc")
+        );
+
+        let schema = Config::json_schema();
+        assert_eq!(
+            schema["properties"]["time"]["properties"]["ntp_servers"]["items"]["pattern"],
+            HOSTNAME_PATTERN
+        );
+        assert_eq!(
+            schema["properties"]["obs"]["properties"]["fps"]["maximum"],
+            MAX_OBS_FPS
+        );
+    }
+
+    #[test]
+    fn test_config_temp_dir_may_not_exist_yet() {
+        // The directory is created when a script first needs it
+        let mut config = Config::default();
+        config.system.temp_dir = Some("/nonexistent/ucs-tmp".to_string());
+        assert!(config.validation_errors().is_empty());
+    }
+
     #[test]
     fn test_args_from_hashmap() {
         let mut map = std::collections::HashMap::new();
@@ -216,6 +717,114 @@ mod tests {
         assert!(args.validate().is_err());
     }
 
+    #[test]
+    fn test_args_validation_reports_every_flag() {
+        let mut map = std::collections::HashMap::new();
+        map.insert("log-level".to_string(), "infoo".to_string());
+        map.insert("format".to_string(), "jsn".to_string());
+
+        let errors = Args::from_hashmap(map).validation_errors();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors.get("/log-level").unwrap().suggestion.as_deref(),
+            Some("info")
+        );
+        assert_eq!(
+            errors.get("/format").unwrap().suggestion.as_deref(),
+            Some("json")
+        );
+    }
+
+    #[test]
+    fn test_config_format_from_path() {
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("a.toml")),
+            ConfigFormat::Toml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("a.YML")),
+            ConfigFormat::Yaml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("a.yaml")),
+            ConfigFormat::Yaml
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("a.json")),
+            ConfigFormat::Json
+        );
+        assert_eq!(
+            ConfigFormat::from_path(Path::new("config")),
+            ConfigFormat::Json
+        );
+    }
+
+    #[test]
+    fn test_config_file_roundtrip_keeps_format() {
+        let dir = TempDir::new().unwrap();
+        let mut config = Config::default();
+        config.audio.volume_level = Some(42);
+        config.dev.target_arch = vec!["x86_64".to_string(), "aarch64".to_string()];
+
+        for (name, marker) in [
+            ("config.toml", "[audio]"),
+            ("config.yaml", "audio:"),
+            ("config.json", "\"audio\": {"),
+        ] {
+            let path = dir.path().join(name);
+            let path = path.to_str().unwrap();
+            config.to_file(path).unwrap();
+            let content = std::fs::read_to_string(path).unwrap();
+            assert!(content.contains(marker), "{} written as {}", name, content);
+
+            let loaded = Config::from_file(path).unwrap();
+            assert_eq!(loaded.audio.volume_level, Some(42));
+            assert_eq!(loaded.dev.target_arch, config.dev.target_arch);
+
+            loaded.to_file(path).unwrap();
+            assert_eq!(std::fs::read_to_string(path).unwrap(), content);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors_carry_location() {
+        let toml = "[audio]\nvolume_level = 50\nenable_echo_cancellation = yes\n";
+        let err = ConfigFormat::Toml
+            .parse::<serde_json::Value>(toml)
+            .unwrap_err();
+        assert_eq!((err.line, err.column), (Some(3), Some(28)));
+
+        let yaml = "audio:\n  volume_level: 50\n bad: [\n";
+        let err = ConfigFormat::Yaml
+            .parse::<serde_json::Value>(yaml)
+            .unwrap_err();
+        assert_eq!(err.line, Some(3));
+        assert!(err.column.is_some());
+
+        let json = "{\n  \"audio\": {\n    \"volume_level\": ,\n  }\n}";
+        let err = ConfigFormat::Json
+            .parse::<serde_json::Value>(json)
+            .unwrap_err();
+        assert_eq!((err.line, err.column), (Some(3), Some(21)));
+        assert!(err
+            .to_string()
+            .starts_with("JSON error at line 3, column 21: "));
+        assert!(!err.message.contains("at line"));
+    }
+
+    #[test]
+    fn test_from_file_error_includes_path_and_location() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("broken.yaml");
+        std::fs::write(&path, "system:\n  log_level: [debug\n").unwrap();
+
+        let err = Config::from_file(path.to_str().unwrap()).unwrap_err();
+        let parse_error = err.downcast_ref::<ConfigParseError>().unwrap();
+        assert_eq!(parse_error.format, ConfigFormat::Yaml);
+        assert!(parse_error.line.is_some());
+        assert!(format!("{:#}", err).contains("broken.yaml"));
+    }
+
     // Property-based tests
     #[cfg(test)]
     use proptest::prelude::*;
@@ -0,0 +1,305 @@
+// Tests for schema_suite module
+//
+// This module tests loading the schemas/*.yaml suites, program generation,
+// expectation matching and the text, TAP and JUnit reports. The suites
+// themselves run when ruchydbg is installed.
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+use ubuntu_config_scripts::*;
+
+const SUITE: &str = r#"
+name: "Sample suite"
+description: "Runner behaviour"
+enum_definition: |
+  enum Mode { A = 0 }
+struct_definition: |
+  struct Probe {
+      mode: Mode,
+  }
+test_config:
+  default_timeout_ms: 2500
+variants:
+  - id: ok
+    name: "Passes"
+    status: verified_pass
+    expected: pass
+    timeout_ms: 1000
+    impl_method: |
+      fun run(&self) { println!("PASS"); }
+    test_code: |
+      Probe { mode: Mode::A }.run();
+  - id: hang
+    name: "Times out"
+    status: verified_fail
+    expected: timeout
+    dependencies: ["use chrono::Utc;"]
+    test_code: |
+      println!("TIMEOUT");
+  - id: broke
+    name: "Used to pass"
+    status: verified_pass
+    expected: pass
+    test_code: |
+      println!("FAIL <fatal> & done");
+  - id: fixed
+    name: "Unknown but passes"
+    status: untested
+    expected: unknown
+    test_code: |
+      println!("PASS");
+  - id: no_code
+    name: "Missing test code"
+    status: untested
+    expected: unknown
+  - id: off
+    name: "Disabled"
+    status: untested
+    enabled: false
+    expected: pass
+"#;
+
+/// Stand-in for ruchydbg that exits according to markers in the program
+fn fake_runner(dir: &TempDir) -> Vec<String> {
+    let path = dir.path().join("fake-ruchydbg");
+    std::fs::write(
+        &path,
+        "#!/bin/sh\n\
+         grep -q PASS \"$1\" && { echo ran \"$2\" \"$3\"; exit 0; }\n\
+         grep -q TIMEOUT \"$1\" && exit 124\n\
+         echo 'error: <boom>' >&2\n\
+         exit 1\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    vec![path.to_string_lossy().to_string()]
+}
+
+fn repo_schema_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(SCHEMA_DIR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_repository_suites() {
+        let paths = find_suites(&repo_schema_dir()).unwrap();
+        assert!(!paths.is_empty());
+
+        for path in paths {
+            let suite = SchemaSuite::from_file(&path).unwrap();
+            assert!(
+                !suite.variants.is_empty(),
+                "{} has no variants",
+                path.display()
+            );
+            for variant in suite.variants.iter().filter(|v| v.enabled) {
+                let program = suite.program(variant).unwrap();
+                assert!(program.contains("fun main() {"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_issue79_program_generation() {
+        let suite =
+            SchemaSuite::from_file(&repo_schema_dir().join("issue79_comprehensive.yaml")).unwrap();
+        assert_eq!(suite.struct_name().as_deref(), Some("Logger"));
+        assert_eq!(suite.test_config.default_timeout_ms, 5000);
+        assert!(!suite.variant("variant_13").unwrap().enabled);
+
+        let variant = suite.variant("variant_5").unwrap();
+        assert_eq!(variant.expected, Expectation::Timeout);
+        let program = suite.program(variant).unwrap();
+        assert!(program.starts_with("use chrono::Utc;\n\nenum LogLevel {"));
+        assert!(program.contains("impl Logger {\nfun test(&self) {"));
+        assert!(program.ends_with("logger.test();\n}"));
+    }
+
+    #[test]
+    fn test_invalid_suites_are_rejected() {
+        let err =
+            SchemaSuite::from_yaml("name: x\nvariants:\n  - id: a\n    name: [\n").unwrap_err();
+        assert!(format!("{:#}", err).contains("line"));
+
+        let duplicate = "name: x\nvariants:\n\
+            \x20 - {id: a, name: one, status: untested, expected: pass}\n\
+            \x20 - {id: a, name: two, status: untested, expected: pass}\n";
+        assert!(SchemaSuite::from_yaml(duplicate)
+            .unwrap_err()
+            .to_string()
+            .contains("Duplicate variant id: a"));
+    }
+
+    #[test]
+    fn test_expectations() {
+        assert!(Expectation::Pass.accepts(Outcome::Pass));
+        assert!(!Expectation::Pass.accepts(Outcome::Timeout));
+        assert!(Expectation::Timeout.accepts(Outcome::Timeout));
+        assert!(Expectation::Fail.accepts(Outcome::Fail));
+        assert!(!Expectation::Fail.accepts(Outcome::Error));
+        assert!(Expectation::Unknown.accepts(Outcome::Error));
+        assert!(Expectation::Pass.accepts(Outcome::Skipped));
+    }
+
+    #[tokio::test]
+    async fn test_run_compares_outcomes() {
+        let dir = TempDir::new().unwrap();
+        let suite = SchemaSuite::from_yaml(SUITE).unwrap();
+        let report = SuiteRunner::new()
+            .command(fake_runner(&dir))
+            .run(&suite)
+            .await
+            .unwrap();
+
+        let outcome = |id: &str| {
+            report
+                .results
+                .iter()
+                .find(|r| r.id == id)
+                .map(|r| r.outcome)
+                .unwrap()
+        };
+        assert_eq!(outcome("ok"), Outcome::Pass);
+        assert_eq!(outcome("hang"), Outcome::Timeout);
+        assert_eq!(outcome("broke"), Outcome::Fail);
+        assert_eq!(outcome("fixed"), Outcome::Pass);
+        assert_eq!(outcome("no_code"), Outcome::Error);
+        assert_eq!(outcome("off"), Outcome::Skipped);
+
+        let ok = &report.results[0];
+        assert_eq!(ok.stdout.trim(), "ran --timeout 1000");
+        assert_eq!(report.results[2].exit_code, Some(1));
+
+        let ids = |results: Vec<&VariantResult>| -> Vec<String> {
+            results.iter().map(|r| r.id.clone()).collect()
+        };
+        assert_eq!(ids(report.mismatches()), vec!["broke"]);
+        assert_eq!(ids(report.regressions()), vec!["broke"]);
+        assert_eq!(ids(report.new_fixes()), vec!["fixed"]);
+        assert!(!report.is_success());
+        assert_eq!(report.executed(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_run_selected_variants_and_stop() {
+        let dir = TempDir::new().unwrap();
+        let suite = SchemaSuite::from_yaml(SUITE).unwrap();
+
+        let report = SuiteRunner::new()
+            .command(fake_runner(&dir))
+            .only(vec!["hang".to_string(), "fixed".to_string()])
+            .run(&suite)
+            .await
+            .unwrap();
+        assert_eq!(report.results.len(), 2);
+        assert!(report.is_success());
+
+        let report = SuiteRunner::new()
+            .command(fake_runner(&dir))
+            .stop_on_first_failure(true)
+            .run(&suite)
+            .await
+            .unwrap();
+        assert_eq!(report.results[3].outcome, Outcome::Skipped);
+        assert_eq!(
+            report.results[3].message.as_deref(),
+            Some("stopped after first failure")
+        );
+
+        assert!(SuiteRunner::new()
+            .only(vec!["missing".to_string()])
+            .run(&suite)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_missing_runner_is_an_error_outcome() {
+        let suite = SchemaSuite::from_yaml(SUITE).unwrap();
+        let runner = SuiteRunner::new()
+            .command(vec!["/nonexistent/ruchydbg".to_string()])
+            .only(vec!["ok".to_string()]);
+        assert!(!runner.is_available());
+
+        let report = runner.run(&suite).await.unwrap();
+        assert_eq!(report.results[0].outcome, Outcome::Error);
+        assert!(report.results[0]
+            .message
+            .as_deref()
+            .unwrap()
+            .contains("Failed to execute command"));
+    }
+
+    #[tokio::test]
+    async fn test_tap_and_junit_reports() {
+        let dir = TempDir::new().unwrap();
+        let suite = SchemaSuite::from_yaml(SUITE).unwrap();
+        let report = SuiteRunner::new()
+            .command(fake_runner(&dir))
+            .run(&suite)
+            .await
+            .unwrap();
+
+        let tap = report.render(ReportFormat::Tap).unwrap();
+        assert!(tap.starts_with("TAP version 13\n# Sample suite\n1..6\n"));
+        assert!(tap.contains("ok 1 - ok - Passes\n"));
+        assert!(
+            tap.contains("not ok 3 - broke - Used to pass\n  ---\n  expected: pass\n  got: fail\n")
+        );
+        assert!(tap.contains("    error: <boom>\n  ...\n"));
+        assert!(tap.contains("ok 6 - off - Disabled # SKIP disabled\n"));
+
+        let junit = report.render(ReportFormat::Junit).unwrap();
+        assert!(
+            junit.contains("<testsuites tests=\"6\" failures=\"1\" errors=\"0\" skipped=\"1\">")
+        );
+        assert!(junit.contains(
+            "<failure message=\"expected pass, got fail\">error: &lt;boom&gt;</failure>"
+        ));
+        assert!(junit.contains("<skipped message=\"disabled\"/>"));
+        assert!(junit.contains("name=\"no_code - Missing test code\""));
+
+        let text = report.render(ReportFormat::Text).unwrap();
+        assert!(text.contains("5/6 matched expectations"));
+        assert!(text.contains("regression: Used to pass (fail)"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&report.render(ReportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["results"][1]["outcome"], "timeout");
+    }
+
+    #[test]
+    fn test_report_format_names() {
+        assert_eq!(
+            "junit".parse::<ReportFormat>().unwrap(),
+            ReportFormat::Junit
+        );
+        assert_eq!("TAP".parse::<ReportFormat>().unwrap(), ReportFormat::Tap);
+        assert!("html".parse::<ReportFormat>().is_err());
+        assert_eq!(xml_escape("a<b & \"c\""), "a&lt;b &amp; &quot;c&quot;");
+    }
+
+    #[tokio::test]
+    async fn test_repository_suites_with_ruchydbg() {
+        let runner = SuiteRunner::new();
+        if !runner.is_available() {
+            eprintln!("ruchydbg not installed; skipping schema suites");
+            return;
+        }
+
+        for path in find_suites(&repo_schema_dir()).unwrap() {
+            let suite = SchemaSuite::from_file(&path).unwrap();
+            let report = runner.run(&suite).await.unwrap();
+            assert!(
+                report.is_success(),
+                "{} did not match its expectations:\n{}",
+                path.display(),
+                report.to_tap()
+            );
+        }
+    }
+}
@@ -0,0 +1,180 @@
+// Tests for secret module
+//
+// This module tests `${env:}`, `${file:}` and `${cmd:}` references, lazy
+// resolution of SecretString values, that loading a configuration never
+// resolves them, and that saving writes the reference rather than the secret
+
+use tempfile::TempDir;
+use ubuntu_config_scripts::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_references() {
+        assert_eq!(references("plain text").unwrap(), vec![]);
+        assert_eq!(
+            references("${env:GH_RUNNER_TOKEN}").unwrap(),
+            vec![Reference::Env("GH_RUNNER_TOKEN".to_string())]
+        );
+        assert_eq!(
+            references("https://${file:~/.user}:${cmd:awk '{print $1}' /etc/pw}@host").unwrap(),
+            vec![
+                Reference::File("~/.user".to_string()),
+                Reference::Cmd("awk '{print $1}' /etc/pw".to_string()),
+            ]
+        );
+        assert_eq!(
+            Reference::Cmd("pass show x".to_string()).to_string(),
+            "${cmd:pass show x}"
+        );
+
+        assert_eq!(
+            resolve_references("cost: $5, $${env:X}").unwrap(),
+            "cost: $5, ${env:X}"
+        );
+        assert!(references("${vault:x}")
+            .unwrap_err()
+            .to_string()
+            .contains("Unknown reference kind 'vault'"));
+        assert!(references("${env:}").is_err());
+        assert!(references("${GH_RUNNER_TOKEN}").is_err());
+        assert!(references("${env:X")
+            .unwrap_err()
+            .to_string()
+            .contains("Unterminated"));
+    }
+
+    #[test]
+    fn test_resolve_each_kind() {
+        std::env::set_var("UCS_TEST_SECRET_ENV", "from-env");
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("token");
+        std::fs::write(&file, "from-file\n").unwrap();
+
+        let text = format!(
+            "${{env:UCS_TEST_SECRET_ENV}}/${{file:{}}}/${{cmd:printf 'from-%s\\n' cmd}}",
+            file.display()
+        );
+        assert_eq!(
+            resolve_references(&text).unwrap(),
+            "from-env/from-file/from-cmd"
+        );
+
+        let err = resolve_references("${env:UCS_TEST_SECRET_UNSET}").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to resolve ${env:UCS_TEST_SECRET_UNSET}"
+        );
+        assert!(format!("{:#}", err).contains("UCS_TEST_SECRET_UNSET is not set"));
+
+        let err = resolve_references("${cmd:echo denied >&2; exit 3}").unwrap_err();
+        assert!(format!("{:#}", err).contains("denied"));
+        assert!(resolve_references("${file:/nonexistent/token}").is_err());
+    }
+
+    #[test]
+    fn test_secret_string_resolves_once_and_is_redacted() {
+        let dir = TempDir::new().unwrap();
+        let counter = dir.path().join("calls");
+        let secret = SecretString::new(&format!(
+            "${{cmd:echo x >> {} && echo s3cr3t-runner-token}}",
+            counter.display()
+        ));
+        assert!(secret.is_reference());
+
+        assert_eq!(secret.resolve().unwrap(), "s3cr3t-runner-token");
+        assert_eq!(secret.resolve().unwrap(), "s3cr3t-runner-token");
+        assert_eq!(std::fs::read_to_string(&counter).unwrap(), "x\n");
+        assert_eq!(
+            redact("token is s3cr3t-runner-token"),
+            format!("token is {}", MASK)
+        );
+
+        let plain = SecretString::from("hunter22");
+        assert!(!plain.is_reference());
+        assert_eq!(plain.resolve().unwrap(), "hunter22");
+        assert_eq!(format!("{:?}", plain), format!("SecretString({})", MASK));
+        assert_eq!(
+            format!("{:?}", SecretString::from("${env:X}")),
+            "SecretString(\"${env:X}\")"
+        );
+    }
+
+    #[test]
+    fn test_config_writes_references_back() {
+        std::env::set_var("UCS_TEST_RUNNER_TOKEN", "AABBCCDDEEFF");
+        let config = Config::from_json(
+            r#"{"runner": {"url": "https://github.com/acme/app", "token": "${env:UCS_TEST_RUNNER_TOKEN}"}}"#,
+        )
+        .unwrap();
+        let token = config.runner.token.as_ref().unwrap();
+        assert_eq!(token.resolve().unwrap(), "AABBCCDDEEFF");
+
+        let json = config.to_json().unwrap();
+        assert!(json.contains("\"token\": \"${env:UCS_TEST_RUNNER_TOKEN}\""));
+        assert!(!json.contains("AABBCCDDEEFF"));
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.toml");
+        config.to_file(path.to_str().unwrap()).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("token = \"${env:UCS_TEST_RUNNER_TOKEN}\""));
+        assert!(!saved.contains("AABBCCDDEEFF"));
+        assert!(!format!("{:?}", config).contains("AABBCCDDEEFF"));
+    }
+
+    #[test]
+    fn test_invalid_references_fail_validation() {
+        let config = Config::from_json(
+            r#"{"runner": {"url": "https://gitlab.com/x", "token": "${pass:x}"}}"#,
+        )
+        .unwrap();
+        let errors = config.validation_errors();
+        assert!(errors.get("/runner/url").is_some());
+        assert!(errors
+            .get("/runner/token")
+            .unwrap()
+            .message
+            .contains("Unknown reference kind 'pass'"));
+
+        let schema = Config::json_schema();
+        let token = &schema["properties"]["runner"]["properties"]["token"];
+        assert_eq!(token["type"][0], "string");
+    }
+
+    #[test]
+    fn test_loading_leaves_references_unresolved() {
+        let dir = TempDir::new().unwrap();
+        let counter = dir.path().join("calls");
+        let raw = format!(
+            "${{cmd:echo x >> {} && echo cmd-runner-token}}",
+            counter.display()
+        );
+        let loaded = ConfigLoader::empty()
+            .set("runner.token", &raw)
+            .set("deploy.install_dir", "${env:UCS_TEST_UNSET_DIR}")
+            .load()
+            .unwrap();
+
+        // Loading, validating and showing the configuration run nothing
+        assert!(loaded.config.validate().is_ok());
+        assert_eq!(loaded.value["runner"]["token"], raw.as_str());
+        assert!(loaded.config.to_json().unwrap().contains(&raw));
+        assert!(!counter.exists());
+
+        let token = loaded.config.runner.token.as_ref().unwrap();
+        assert_eq!(token.resolve().unwrap(), "cmd-runner-token");
+        assert_eq!(std::fs::read_to_string(&counter).unwrap(), "x\n");
+
+        // A malformed token only fails the runner section
+        let loaded = ConfigLoader::empty()
+            .set("runner.token", "${pass:x}")
+            .load()
+            .unwrap();
+        let (own, others) = loaded.config.validation_errors().partition_at("/time");
+        assert!(own.into_result().is_ok());
+        assert!(others.get("/runner/token").is_some());
+    }
+}
@@ -0,0 +1,166 @@
+// Tests for system_deps module
+//
+// This module tests parsing the system dependency manifest, selecting the
+// requirements of one script, loose version parsing, checking the host and
+// building per-distro install plans
+
+use ubuntu_config_scripts::*;
+
+const MANIFEST: &str = r#"
+[capabilities.shell]
+description = "POSIX shell"
+commands = ["sh"]
+apt = ["dash"]
+dnf = ["bash"]
+pacman = ["bash"]
+scripts = ["*"]
+core = true
+
+[capabilities.widget]
+description = "Something no host has"
+commands = ["ucs-no-such-command"]
+apt = ["ucs-widget"]
+min_version = "1.2"
+scripts = ["fix_audio"]
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_manifest() {
+        let requirements = SystemRequirements::parse(MANIFEST).unwrap();
+        assert_eq!(requirements.requirements.len(), 2);
+
+        let widget = requirements.get("widget").unwrap();
+        assert_eq!(widget.min_version, Some(semver::Version::new(1, 2, 0)));
+        assert_eq!(
+            widget.version_command,
+            vec!["ucs-no-such-command", "--version"]
+        );
+        assert_eq!(widget.packages_for(PackageManager::Apt), ["ucs-widget"]);
+        assert!(widget.packages_for(PackageManager::Dnf).is_empty());
+        assert_eq!(
+            requirements.providing("sh").map(|r| r.name.as_str()),
+            Some("shell")
+        );
+
+        let fix_audio = requirements.for_script("fix_audio");
+        assert_eq!(fix_audio.requirements.len(), 2);
+        let deploy = requirements.for_script("deploy");
+        assert_eq!(deploy.commands(), vec!["sh"]);
+        assert_eq!(requirements.common().requirements.len(), 1);
+        assert_eq!(requirements.core().commands(), vec!["sh"]);
+
+        let err = SystemRequirements::parse("[capabilities.empty]\ncommands = []\n").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Capability empty lists no commands"));
+        let err = SystemRequirements::parse(
+            "[capabilities.x]\ncommands = [\"x\"]\nmin_version = \"latest\"\n",
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Invalid min_version for x: latest"));
+        assert!(SystemRequirements::parse(
+            "[capabilities.x]\ncommands = [\"x\"]\nzypper = [\"x\"]\n"
+        )
+        .is_err());
+
+        assert_eq!(
+            "apt-get".parse::<PackageManager>().unwrap(),
+            PackageManager::Apt
+        );
+        assert_eq!(
+            "yum".parse::<PackageManager>().unwrap(),
+            PackageManager::Yum
+        );
+        let shell = requirements.get("shell").unwrap();
+        assert_eq!(shell.packages_for(PackageManager::Yum), ["bash"]);
+        assert_eq!(
+            PackageManager::Yum.install_command(&["bash".to_string()]),
+            vec!["sudo", "yum", "install", "-y", "bash"]
+        );
+        assert!("brew".parse::<PackageManager>().is_err());
+    }
+
+    #[test]
+    fn test_parse_loose_version() {
+        let cases = [
+            ("git version 2.39.5", Some("2.39.5")),
+            ("systemd 252 (252.38-1~deb12u1)", Some("252.0.0")),
+            ("rustc 1.95.0 (abcdef 2026-01-01)", Some("1.95.0")),
+            ("OBS Studio - 30.0.2-1", Some("30.0.2")),
+            ("pw-cli\nCompiled with libpipewire 1.0.5", Some("1.0.5")),
+            ("no version here", None),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(
+                parse_loose_version(text).map(|v| v.to_string()).as_deref(),
+                expected,
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_check_and_install_plan() {
+        let requirements = SystemRequirements::parse(MANIFEST).unwrap();
+        let report = requirements.check();
+        assert_eq!(report.checks.len(), 2);
+        assert!(!report.is_ok());
+        assert_eq!(report.unmet().len(), 1);
+        assert_eq!(report.unmet()[0].status, RequirementStatus::Missing);
+        assert_eq!(report.missing_commands(), vec!["ucs-no-such-command"]);
+
+        let plan = requirements.install_plan(&report, PackageManager::Apt);
+        assert_eq!(plan.packages, vec!["ucs-widget"]);
+        assert_eq!(
+            plan.command().unwrap(),
+            vec!["sudo", "apt", "install", "-y", "ucs-widget"]
+        );
+        let plan = requirements.install_plan(&report, PackageManager::Pacman);
+        assert!(plan.command().is_none());
+        assert_eq!(plan.unavailable, vec!["widget"]);
+        assert!(!plan.is_empty());
+
+        let shell = requirements.for_script("deploy").check();
+        assert!(shell.is_ok());
+        assert!(requirements
+            .for_script("deploy")
+            .install_plan(&shell, PackageManager::Dnf)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_builtin_manifest_scripts() {
+        let requirements = SystemRequirements::builtin();
+        let binaries = lib::deploy::binary_names(".").unwrap();
+        for requirement in &requirements.requirements {
+            assert!(!requirement.scripts.is_empty(), "{}", requirement.name);
+            for script in &requirement.scripts {
+                assert!(
+                    script == ALL_SCRIPTS || binaries.contains(script),
+                    "{} names unknown script {}",
+                    requirement.name,
+                    script
+                );
+            }
+        }
+        assert!(requirements.common().commands().contains(&"systemctl"));
+        assert!(requirements
+            .for_script("fix_audio")
+            .get("pipewire")
+            .is_some());
+        assert!(requirements.for_script("deploy").get("pipewire").is_none());
+
+        // The full dependency check keeps the commands it always covered
+        let core = requirements.core();
+        for command in ["systemctl", "ps", "git", "make", "cargo", "rustc"] {
+            assert!(core.commands().contains(&command), "{}", command);
+        }
+    }
+}
@@ -0,0 +1,153 @@
+// Tests for trace module
+//
+// This module tests span nesting, per-task span stacks, LogContext span links
+// and Chrome trace export
+
+use tempfile::TempDir;
+use ubuntu_config_scripts::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_spans_link_to_parent() {
+        assert!(current_span().is_none());
+
+        let outer = Span::enter("outer");
+        assert_eq!(outer.parent(), None);
+        {
+            let inner = Span::enter("inner");
+            assert_eq!(inner.parent(), Some(outer.id()));
+            assert_eq!(current_span().unwrap().id, inner.id());
+            assert_eq!(span_path(), vec!["outer", "inner"]);
+        }
+        assert_eq!(current_span().unwrap().id, outer.id());
+        drop(outer);
+        assert!(current_span().is_none());
+    }
+
+    #[test]
+    fn test_spans_closed_out_of_order() {
+        let first = Span::enter("first");
+        let second = Span::enter("second");
+        drop(first);
+        assert_eq!(current_span().unwrap().id, second.id());
+        drop(second);
+        assert!(current_span().is_none());
+    }
+
+    #[test]
+    fn test_spans_are_per_thread() {
+        let _outer = Span::enter("main thread");
+        let parent = std::thread::spawn(|| Span::enter("worker").parent())
+            .join()
+            .unwrap();
+        assert_eq!(parent, None);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_task_spans_follow_the_task() {
+        let outer = Span::enter("deploy");
+        let outer_id = outer.id();
+
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                tokio::spawn(task_scope(async {
+                    let build = Span::enter("build");
+                    for _ in 0..10 {
+                        tokio::task::yield_now().await;
+                    }
+                    let link = Span::enter("link");
+                    tokio::task::yield_now().await;
+                    assert_eq!(current_span().unwrap().id, link.id());
+                    assert_eq!(span_path(), vec!["deploy", "build", "link"]);
+                    (build.parent(), link.parent() == Some(build.id()))
+                }))
+            })
+            .collect();
+        for task in tasks {
+            let (parent, linked) = task.await.unwrap();
+            assert_eq!(parent, Some(outer_id));
+            assert!(linked);
+        }
+
+        // Nothing is left behind on the worker threads the tasks ran on
+        for _ in 0..8 {
+            let stale = tokio::spawn(async { current_span() }).await.unwrap();
+            assert_eq!(stale, None);
+        }
+        assert_eq!(current_span().unwrap().id, outer_id);
+    }
+
+    #[test]
+    fn test_log_context_span_ids() {
+        let outer = LogContext::new("diagnose");
+        let inner = LogContext::new("pipewire");
+        assert_eq!(inner.parent_id(), Some(outer.span_id()));
+        assert_ne!(inner.span_id(), outer.span_id());
+        inner.log(LogLevel::Info, "inside nested context");
+    }
+
+    #[test]
+    fn test_chrome_trace_export() {
+        let _ = init_logger();
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("trace.json");
+
+        {
+            let _guard = TraceGuard::new(path.to_str().unwrap());
+            let outer = LogContext::new("build_all");
+            {
+                let timer = PerformanceTimer::new("cargo build");
+                log_info("compiling", "DEPLOY");
+                outer.log(LogLevel::Info, "waiting for cargo");
+                timer.finish();
+            }
+            outer.log(LogLevel::Info, "all built");
+            drop(outer);
+        }
+        assert!(!is_recording());
+
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let events = json["traceEvents"].as_array().unwrap();
+
+        let span = |name: &str| {
+            events
+                .iter()
+                .find(|e| e["ph"] == "X" && e["name"] == name)
+                .unwrap_or_else(|| panic!("missing span {}", name))
+                .clone()
+        };
+        let outer = span("build_all");
+        let inner = span("cargo build");
+        assert_eq!(inner["args"]["parent_id"], outer["args"]["span_id"]);
+        assert!(outer["dur"].as_u64().unwrap() >= inner["dur"].as_u64().unwrap());
+        assert!(outer["ts"].as_u64().unwrap() <= inner["ts"].as_u64().unwrap());
+
+        let log_event = events
+            .iter()
+            .find(|e| e["ph"] == "i" && e["name"] == "compiling")
+            .expect("log record recorded as instant event");
+        assert_eq!(log_event["args"]["span_id"], inner["args"]["span_id"]);
+        assert_eq!(log_event["args"]["target"], "DEPLOY");
+
+        // The logger tags records with the innermost span, so a context only
+        // names itself while an inner span is active
+        let names: Vec<&str> = events
+            .iter()
+            .filter(|e| e["ph"] == "i")
+            .filter_map(|e| e["name"].as_str())
+            .collect();
+        assert!(names.contains(&"[build_all] waiting for cargo"));
+        assert!(names.contains(&"all built"));
+    }
+
+    #[test]
+    fn test_to_chrome_trace_empty() {
+        let json: serde_json::Value = serde_json::from_str(&to_chrome_trace(&[]).unwrap()).unwrap();
+        assert_eq!(json["traceEvents"], serde_json::json!([]));
+        assert_eq!(json["displayTimeUnit"], "ms");
+    }
+}